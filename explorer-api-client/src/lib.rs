@@ -0,0 +1,184 @@
+//! Typed client for the explorer's JSON API, for Rust services integrating
+//! with an explorer instance.
+//!
+//! ```no_run
+//! # async fn example() -> eyre::Result<()> {
+//! let client = explorer_api_client::ExplorerClient::new("https://explorer.e.cash")?;
+//! let blocks = client.blocks(0, 10).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod types;
+
+pub use types::*;
+
+use eyre::{bail, Result};
+use reqwest::{Client, RequestBuilder};
+use serde::{de::DeserializeOwned, Serialize};
+
+pub struct ExplorerClient {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl ExplorerClient {
+    /// Client for the explorer at `base_url`, e.g. "https://explorer.e.cash"
+    pub fn new(base_url: impl Into<String>) -> Result<Self> {
+        let base_url = base_url.into();
+        if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+            bail!(
+                "base_url must be an http:// or https:// URL, got {}",
+                base_url
+            );
+        }
+        Ok(ExplorerClient {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+        })
+    }
+
+    /// Sends the key in the `X-API-Key` header, to use its quota instead of
+    /// the anonymous one.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub async fn blocks(&self, start_height: i32, end_height: i32) -> Result<Vec<JsonBlock>> {
+        let url = format!("/api/blocks/{}/{}", start_height, end_height);
+        Ok(self.get::<JsonData<_>>(&url, &[]).await?.data)
+    }
+
+    pub async fn difficulty_chart(&self, days: u32) -> Result<Vec<JsonDifficultyPoint>> {
+        let query = [("days", days.to_string())];
+        Ok(self
+            .get::<JsonData<_>>("/api/charts/difficulty", &query)
+            .await?
+            .data)
+    }
+
+    pub async fn block_txs(&self, block_hash: &str) -> Result<Vec<JsonTx>> {
+        let url = format!("/api/block/{}/transactions", block_hash);
+        Ok(self.get::<JsonData<_>>(&url, &[]).await?.data)
+    }
+
+    pub async fn address_txs(
+        &self,
+        address: &str,
+        page: usize,
+        take: usize,
+    ) -> Result<Vec<JsonTx>> {
+        let url = format!("/api/address/{}/transactions", address);
+        let query = [("page", page.to_string()), ("take", take.to_string())];
+        Ok(self.get::<JsonData<_>>(&url, &query).await?.data)
+    }
+
+    pub async fn address_utxos(
+        &self,
+        address: &str,
+        page: usize,
+        take: usize,
+    ) -> Result<JsonUtxos> {
+        let url = format!("/api/address/{}/utxos", address);
+        let query = [("page", page.to_string()), ("take", take.to_string())];
+        self.get(&url, &query).await
+    }
+
+    pub async fn address_summary(&self, address: &str) -> Result<JsonAddressSummary> {
+        self.get(&format!("/api/address/{}/summary", address), &[])
+            .await
+    }
+
+    pub async fn address_balances(&self, addresses: &[&str]) -> Result<Vec<JsonAddressBalance>> {
+        #[derive(Serialize)]
+        struct Request<'a> {
+            addresses: &'a [&'a str],
+        }
+        let request = Request { addresses };
+        Ok(self
+            .post::<_, JsonData<_>>("/api/addresses/balances", &request)
+            .await?
+            .data)
+    }
+
+    pub async fn tx(&self, tx_hash: &str) -> Result<JsonTxDetails> {
+        self.get(&format!("/api/tx/{}", tx_hash), &[]).await
+    }
+
+    pub async fn tx_merkle_proof(&self, tx_hash: &str) -> Result<JsonMerkleProof> {
+        self.get(&format!("/api/tx/{}/merkle-proof", tx_hash), &[])
+            .await
+    }
+
+    pub async fn tx_graph(&self, tx_hash: &str, depth: u32) -> Result<JsonTxGraph> {
+        let query = [("depth", depth.to_string())];
+        self.get(&format!("/api/tx/{}/graph", tx_hash), &query)
+            .await
+    }
+
+    /// Broadcasts dependent raw txs (hex) in order, see `POST /api/tx/package`
+    pub async fn broadcast_tx_package(&self, raw_txs: &[&str]) -> Result<JsonTxPackageResponse> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Request<'a> {
+            raw_txs: &'a [&'a str],
+        }
+        self.post("/api/tx/package", &Request { raw_txs }).await
+    }
+
+    pub async fn batch(&self, queries: &[JsonBatchQuery]) -> Result<Vec<JsonBatchResult>> {
+        Ok(self
+            .post::<_, JsonData<_>>("/api/batch", &queries)
+            .await?
+            .data)
+    }
+
+    pub async fn search_suggestions(&self, query: &str) -> Result<Vec<JsonSearchSuggestion>> {
+        let query = [("q", query.to_string())];
+        Ok(self
+            .get::<JsonData<_>>("/api/search/suggest", &query)
+            .await?
+            .data)
+    }
+
+    /// Quota and usage of this client's API key, or of its IP address
+    pub async fn usage(&self) -> Result<JsonApiUsage> {
+        self.get("/api/me/usage", &[]).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T> {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query);
+        self.send(request).await
+    }
+
+    async fn post<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        let request = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(body);
+        self.send(request).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, mut request: RequestBuilder) -> Result<T> {
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-API-Key", api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            bail!("Explorer API returned {}: {}", status, message);
+        }
+        Ok(response.json().await?)
+    }
+}
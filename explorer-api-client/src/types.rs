@@ -0,0 +1,291 @@
+//! Response types of the explorer API. These mirror the structs in
+//! explorer-server's `server_primitives.rs` and must be kept in sync with them.
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Token amounts are sent as decimal strings, as they can exceed the integers
+/// JavaScript numbers hold exactly
+fn deserialize_token_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+    let amount = String::deserialize(deserializer)?;
+    amount.parse().map_err(serde::de::Error::custom)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUtxo {
+    pub tx_hash: String,
+    pub out_idx: u32,
+    pub sats_amount: i64,
+    pub token_amount: u64,
+    pub is_coinbase: bool,
+    pub block_height: i32,
+    pub confirmations: i32,
+    pub age_days: u32,
+    pub is_dust: bool,
+    pub immature_blocks_remaining: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonToken {
+    pub token_id: String,
+    pub token_type: u32,
+    pub token_ticker: String,
+    pub token_name: String,
+    pub decimals: u32,
+    pub group_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlock {
+    pub hash: String,
+    pub height: i32,
+    pub timestamp: i64,
+    pub difficulty: f64,
+    pub size: u64,
+    pub num_txs: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDifficultyPoint {
+    pub timestamp: i64,
+    pub height: i32,
+    pub difficulty: f64,
+    pub hashrate: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTx {
+    pub tx_hash: String,
+    pub block_height: Option<i32>,
+    pub is_mempool: bool,
+    pub timestamp: i64,
+    pub is_coinbase: bool,
+    pub size: i32,
+    pub num_inputs: u32,
+    pub num_outputs: u32,
+    pub stats: JsonTxStats,
+    pub token_id: Option<String>,
+    pub token: Option<JsonToken>,
+    pub price_usd: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxStats {
+    pub sats_input: i64,
+    pub sats_output: i64,
+    pub delta_sats: i64,
+    #[serde(deserialize_with = "deserialize_token_amount")]
+    pub delta_tokens: i128,
+    #[serde(deserialize_with = "deserialize_token_amount")]
+    pub token_input: i128,
+    #[serde(deserialize_with = "deserialize_token_amount")]
+    pub token_output: i128,
+    pub does_burn_slp: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxInput {
+    pub prev_tx_hash: String,
+    pub prev_out_idx: u32,
+    pub sats_amount: i64,
+    pub address: Option<String>,
+    pub sequence: u32,
+    pub sequence_description: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxOutput {
+    pub sats_amount: i64,
+    pub address: Option<String>,
+    pub output_script: String,
+    pub spent_by_tx_hash: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxDetails {
+    pub tx_hash: String,
+    pub version: i32,
+    pub lock_time: u32,
+    pub lock_time_description: String,
+    pub size: u32,
+    pub is_coinbase: bool,
+    pub block_height: Option<i32>,
+    pub timestamp: i64,
+    pub time_first_seen: Option<i64>,
+    pub confirmation_delay_secs: Option<i64>,
+    pub stats: JsonTxStats,
+    pub coin_days_destroyed: Option<f64>,
+    pub size_breakdown: JsonTxSizeBreakdown,
+    pub warnings: Vec<JsonTxWarning>,
+    pub inputs: Vec<JsonTxInput>,
+    pub outputs: Vec<JsonTxOutput>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TxWarningKind {
+    DustOutput,
+    NonStandardScript,
+    LargeOpReturn,
+    LowFeeRate,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxWarning {
+    pub kind: TxWarningKind,
+    pub out_idx: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxSizeBreakdown {
+    pub inputs_size: u32,
+    pub outputs_size: u32,
+    pub overhead_size: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUtxos {
+    pub data: Vec<JsonUtxo>,
+    pub num_utxos: usize,
+    pub unconfirmed_received_sats: i64,
+    pub unconfirmed_sent_sats: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenBalance {
+    pub token_id: String,
+    pub sats_amount: i64,
+    #[serde(deserialize_with = "deserialize_token_amount")]
+    pub token_amount: i128,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressBalance {
+    pub address: String,
+    pub sats_amount: i64,
+    pub tokens: Vec<JsonTokenBalance>,
+    pub num_txs: u32,
+    pub last_activity: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressSummary {
+    pub num_txs: u32,
+    pub first_seen: Option<i64>,
+    pub last_activity: Option<i64>,
+    pub total_received: Option<i64>,
+    pub total_sent: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxPackageResponse {
+    pub accepted_txids: Vec<String>,
+    pub failed_index: Option<usize>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonApiUsage {
+    pub key_name: Option<String>,
+    pub per_minute_limit: u32,
+    pub daily_limit: u32,
+    pub used_this_minute: u32,
+    pub used_today: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraphNode {
+    pub tx_hash: String,
+    pub depth: i32,
+    pub block_height: Option<i32>,
+    pub is_coinbase: bool,
+    pub sats_output: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraphEdge {
+    pub from_tx_hash: String,
+    pub out_idx: u32,
+    pub to_tx_hash: String,
+    pub input_idx: u32,
+    pub sats_amount: i64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraph {
+    pub nodes: Vec<JsonTxGraphNode>,
+    pub edges: Vec<JsonTxGraphEdge>,
+    pub is_truncated: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchSuggestionKind {
+    Block,
+    Tx,
+    Token,
+    Address,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSearchSuggestion {
+    pub kind: SearchSuggestionKind,
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMerkleProof {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub block_height: i32,
+    pub merkle_root: String,
+    pub position: usize,
+    pub branch: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonBatchQuery {
+    Tx { hash: String },
+    Block { hash: String },
+    BlockHeight { height: i32 },
+    AddressBalance { address: String },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum JsonBatchResult {
+    Tx(JsonTxDetails),
+    Block(JsonBlock),
+    AddressBalance(JsonAddressBalance),
+    Error { error: String },
+}
+
+/// Wrapper of the responses that put their results in `data`
+#[derive(Deserialize)]
+pub(crate) struct JsonData<T> {
+    pub data: T,
+}
@@ -1,26 +1,168 @@
-use std::{fs, sync::Arc};
+use std::{fs, io::ErrorKind, net::SocketAddr, sync::Arc, time::Duration};
 
-use axum::Extension;
+use axum::{middleware, Extension};
 use bitcoinsuite_chronik_client::ChronikClient;
 use bitcoinsuite_error::Result;
-use explorer_server::{config, server::Server};
+use explorer_server::{
+    api_keys::ApiQuotas,
+    chainwork::ChainworkIndex,
+    compression::compression_layer,
+    config::{self, Config},
+    labels::AddressLabels,
+    prices::PriceHistory,
+    server::Server,
+    server_http::{api_quota, error_page, maintenance_mode},
+    token_docs::TokenDocChecker,
+    token_icons::TokenIcons,
+    upgrades::Upgrades,
+    webhooks::Webhooks,
+    xpub::XpubScanner,
+};
+use eyre::{eyre, WrapErr};
+use tower_http::timeout::TimeoutLayer;
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let config_path = std::env::args().nth(1);
-    let config_path = config_path.as_deref().unwrap_or("config.toml");
-    let config_string = fs::read_to_string(config_path)?;
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+fn main() -> Result<()> {
+    let config_string = read_config_file()?;
     let config = config::load_config(&config_string)?;
 
+    if let Some(upstream_proxy) = &config.upstream_proxy {
+        // The Chronik client builds its own HTTP client, which can only be
+        // given a proxy through the environment. The explorer's other HTTP
+        // clients are built with `no_proxy`, so only Chronik is proxied.
+        // Setting env vars isn't thread-safe, so this has to happen before
+        // the runtime starts its worker threads.
+        std::env::set_var("HTTP_PROXY", upstream_proxy);
+        std::env::set_var("HTTPS_PROXY", upstream_proxy);
+    }
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .wrap_err("Failed to start the Tokio runtime")?
+        .block_on(run(config))
+}
+
+async fn run(config: Config) -> Result<()> {
     let chronik = ChronikClient::new(config.chronik_api_url)?;
-    let base_dir = config.base_dir.unwrap_or_else(|| "../explorer-server".into());
-    let server = Arc::new(Server::setup(chronik, base_dir).await?);
-    let app = server.router().layer(Extension(server));
+    let base_dir = config
+        .base_dir
+        .unwrap_or_else(|| "../explorer-server".into());
+    let labels = AddressLabels::load(config.labels_path)?;
+    let request_timeout = Duration::from_secs(config.limits.request_timeout_secs);
+    let mut server = Server::setup(
+        chronik,
+        base_dir,
+        labels,
+        config.admin_token,
+        config.limits,
+        config
+            .public_url
+            .map(|public_url| public_url.trim_end_matches('/').to_string()),
+    )
+    .await?
+    .with_network(config.network)
+    .await?
+    .with_chainwork_index(ChainworkIndex::load(config.chainwork_path.as_deref())?)
+    .with_price_history(PriceHistory::load(config.price_history_path.as_deref())?)
+    .with_token_icons(TokenIcons::new(&config.token_icons)?)
+    .with_webhooks(Webhooks::new(&config.webhooks).wrap_err("Invalid webhooks config")?)
+    .with_upgrades(Upgrades::new(config.upgrades));
+    if config.api_quota.enabled {
+        server = server.with_api_quotas(ApiQuotas::load(
+            config.api_keys_path.as_deref(),
+            config.anonymous_quota,
+            config.api_quota.client_ip_header,
+        )?);
+    }
+    if config.token_doc_check.enabled {
+        server = server.with_token_doc_checker(TokenDocChecker::new(&config.token_doc_check)?);
+    }
+    if config.xpub.enabled {
+        server = server.with_xpub_scanner(XpubScanner::new(&config.xpub));
+    }
+    if let Some(announcement) = config.announcement {
+        server = server.with_announcement(announcement);
+    }
+    if config.maintenance.enabled {
+        server = server.with_maintenance(config.maintenance);
+    }
+    let server = Arc::new(server);
+    tokio::spawn(server.clone().poll_events());
+
+    let mut app = server
+        .router()
+        .layer(TimeoutLayer::new(request_timeout))
+        .layer(middleware::from_fn(error_page))
+        .layer(middleware::from_fn(api_quota))
+        .layer(middleware::from_fn(maintenance_mode));
+    if config.compression.enabled {
+        app = app.layer(compression_layer(&config.compression));
+    }
+    let shutdown_server = Arc::clone(&server);
+    let app = app.layer(Extension(server));
 
     axum::Server::bind(&config.host)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            shutdown_server.shut_down();
+        })
         .await
         .unwrap();
 
     Ok(())
 }
+
+/// Reads the file given via `--config <path>` (or as the only argument). If no
+/// path is given, "config.toml" is optional, as the config can also be set
+/// entirely via environment variables.
+fn read_config_file() -> Result<String> {
+    let mut args = std::env::args().skip(1);
+    let config_path = match args.next() {
+        Some(arg) if arg == "--config" => Some(
+            args.next()
+                .ok_or_else(|| eyre!("--config requires a path to a config file"))?,
+        ),
+        Some(arg) => Some(arg),
+        None => None,
+    };
+
+    match config_path {
+        Some(config_path) => fs::read_to_string(&config_path)
+            .wrap_err_with(|| format!("Failed to read config file {}", config_path)),
+        None => match fs::read_to_string(DEFAULT_CONFIG_PATH) {
+            Ok(config_string) => Ok(config_string),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(String::new()),
+            Err(err) => Err(err)
+                .wrap_err_with(|| format!("Failed to read config file {}", DEFAULT_CONFIG_PATH)),
+        },
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl-C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    println!("Shutting down, finishing in-flight requests");
+}
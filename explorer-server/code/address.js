@@ -4,15 +4,39 @@ function renderTxHashCoins(row) {
   return '<a href="/tx/' + row.txHash + '">' + 
   minifyBlockID(row.txHash) + ':' + row.outIdx +
     (row.isCoinbase ? '<div class="ui green horizontal label cointable-coinbase">Coinbase</div>' : '') +
+    (row.isDust ? '<div class="ui orange horizontal label cointable-dust">Dust</div>' : '') +
+    (row.immatureBlocksRemaining !== null
+      ? '<div class="ui yellow horizontal label cointable-immature">Immature (' +
+        row.immatureBlocksRemaining + (row.immatureBlocksRemaining === 1 ? ' block' : ' blocks') +
+        ' remaining)</div>'
+      : '') +
     '</a>';
 }
 
+function renderUtxoAge(row) {
+  if (row.confirmations === 0) {
+    return '<div class="ui gray horizontal label">Mempool</div>';
+  }
+  return '~' + renderInteger(row.ageDays) + (row.ageDays === 1 ? ' day' : ' days');
+}
+
+function sortUtxos(utxos, sort) {
+  const sorted = [...utxos];
+  if (sort === 'value') {
+    sorted.sort((a, b) => b.satsAmount - a.satsAmount);
+  } else if (sort === 'age') {
+    sorted.sort((a, b) => b.confirmations - a.confirmations);
+  }
+  return sorted;
+}
+
 function renderRowsCoins(row, type, decimals, ticker) {
   if (type === 'token') {
     return ( 
       '<div class="coin-row">' +
       '<div>' + renderTxHashCoins(row) + '</div>' +
       '<div>' + '<a href="/block-height/' + row.blockHeight + '">' + renderInteger(row.blockHeight) + '</a>' + '</div>' +
+      '<div>' + renderUtxoAge(row) + '</div>' +
       '<div>' + renderAmount(row.tokenAmount, decimals) + ' ' + ticker + '</div>' +
       '</div>'
       ); 
@@ -21,7 +45,8 @@ function renderRowsCoins(row, type, decimals, ticker) {
       '<div class="coin-row">' +
       '<div>' + renderTxHashCoins(row) + '</div>' +
       '<div>' + '<a href="/block-height/' + row.blockHeight + '">' + renderInteger(row.blockHeight) + '</a>' + '</div>' +
-      '<div>' + renderSats(row.satsAmount) + ' XEC' + '</div>' +
+      '<div>' + renderUtxoAge(row) + '</div>' +
+      '<div>' + renderSats(row.satsAmount) + ' ' + unitSymbol() + '</div>' +
       '</div>'
       ); 
 }
@@ -39,8 +64,9 @@ function renderRowsCoins(row, type, decimals, ticker) {
 var isSatsTableLoaded = false;
 function loadSatsTable() {
   const listArray = []
-  for (let i = 0; i < addrBalances["main"].utxos.length; i++) {
-    listArray.push(renderRowsCoins(addrBalances["main"].utxos[i]))
+  const utxos = sortUtxos(addrBalances["main"].utxos, $('#sats-coins-sort').val());
+  for (let i = 0; i < utxos.length; i++) {
+    listArray.push(renderRowsCoins(utxos[i]))
   }
   const numberOfItems = listArray.length
   const numberPerPage = 10
@@ -91,7 +117,7 @@ function loadSatsTable() {
     buildPage(0)
     buildPagination(currentPage)
 
-    $('.paginator').on('click', 'button', function() {
+    $('.paginator').off('click').on('click', 'button', function() {
       var clickedPage = parseInt($(this).val())
       buildPagination(clickedPage)
       buildPage(clickedPage)
@@ -113,14 +139,14 @@ const renderAge = timestamp => {
   if (timestamp == 0) {
     return '<div class="ui gray horizontal label">Mempool</div>';
   }
-  return moment(timestamp * 1000).fromNow();
+  return `<span class="timestamp-age" data-timestamp="${timestamp}">${moment(timestamp * 1000).fromNow()}</span>`;
 };
 
 const renderTimestamp = timestamp => {
   if (timestamp == 0) {
     return '<div class="ui gray horizontal label">Mempool</div>';
   }
-  return moment(timestamp * 1000).format('ll, LTS');
+  return formatTimestamp(timestamp);
 };
 
 const renderTxID = (data) => {
@@ -166,8 +192,16 @@ const renderFeePerByte = (_value, _type, row) => {
 
 const renderAmountXEC = (_value, _type, row) => {
   if (row.stats.deltaSats < 0) {
-  return '<span>' + renderSats(row.stats.deltaSats) + ' XEC</span>'
-  } else return '<span style="color:#15ee3e">+' + renderSats(row.stats.deltaSats) + ' XEC</span>'
+  return '<span>' + renderSats(row.stats.deltaSats) + ' ' + unitSymbol() + '</span>'
+  } else return '<span style="color:#15ee3e">+' + renderSats(row.stats.deltaSats) + ' ' + unitSymbol() + '</span>'
+};
+
+// Value of the amount at the XEC price of the day, if the server has a price history
+const renderValueUsd = (_value, _type, row) => {
+  if (row.priceUsd === null) {
+    return '';
+  }
+  return '$' + (row.stats.deltaSats / 100 * row.priceUsd).toFixed(2);
 };
 
 const renderToken = (_value, _type, row) => {
@@ -209,7 +243,11 @@ const datatable = () => {
       zeroRecords: '',
       emptyTable: '',
     },
-    ajax: `/api/address/${address}/transactions`,
+    ajax: {
+      url: `/api/address/${address}/transactions`,
+      // Unconfirmed txs have their own section above the table
+      dataSrc: json => json.data.filter(tx => !tx.isMempool),
+    },
     order: [],
     responsive: {
         details: {
@@ -224,7 +262,7 @@ const datatable = () => {
     } ],
     columns:[
       { name: "age", data: 'timestamp', title: "Age", render: renderAge, orderSequence: ['desc', 'asc'] },
-      { name: "timestamp", data: 'timestamp', title: "Date (UTC" + tzOffset + ")", render: renderTimestamp, orderSequence: ['desc', 'asc'] },
+      { name: "timestamp", data: 'timestamp', title: getTimezonePreference() === 'utc' ? "Date (UTC)" : "Date (UTC" + tzOffset + ")", render: renderTimestamp, orderSequence: ['desc', 'asc'] },
       { name: "txHash", data: {txHash: 'txHash', blockHeight: 'blockHeight'}, title: "Transaction ID", className: "hash", render: renderTxID, orderable: false },
       { name: "blockHeight", title: "Block Height", render: renderBlockHeight, orderSequence: ['desc', 'asc'] },
       { name: "size", data: 'size', title: "Size", render: renderSize, orderSequence: ['desc', 'asc'] },
@@ -233,6 +271,9 @@ const datatable = () => {
       { name: "numOutputs", data: 'numOutputs', title: "Outputs", orderSequence: ['desc', 'asc'] },
       { name: "deltaSats", data: 'deltaSats', title: "Amount", render: renderAmountXEC, orderSequence: ['desc', 'asc'], className: 'text-right' },
       { name: "token", title: "Amount Token", render: renderToken },
+      ...(window.hasPriceHistory
+        ? [{ name: "valueUsd", title: "Value (USD)", render: renderValueUsd, orderable: false, className: 'text-right' }]
+        : []),
       { name: 'responsive', render: () => '' },
     ],
   });
@@ -243,6 +284,14 @@ $('#address-txs-table').on('xhr.dt', () => {
 });
 
 const updateTable = (paginationRequest) => {
+  const urlParams = new URLSearchParams(window.location.search);
+  for (const filter of ['token', 'from', 'to']) {
+    const value = urlParams.get(filter);
+    if (value) {
+      paginationRequest[filter] = value;
+    }
+  }
+
   const params = new URLSearchParams(paginationRequest).toString();
   const address = getAddress();
 
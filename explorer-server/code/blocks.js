@@ -69,6 +69,17 @@ const updateTable = (startPosition, endPosition) => {
 }
 
 
+// The first page is rendered by the server and embedded as JSON, so it only
+// has to be fetched again when the URL asks for a different page
+const isServerRenderedPage = () => {
+  const { page, rows, start, end } = window.state.getParameters();
+  return page === 0 &&
+    rows === DEFAULT_ROWS_PER_PAGE &&
+    start === 0 &&
+    end === window.state.getPaginationTotalEntries();
+};
+
+
 // UI actions
 const goToPage = (event, page) => {
   event.preventDefault();
@@ -90,6 +101,7 @@ const dataTable = () => {
   $('#date').text(`Date (${tzString})`)
 
   $('#blocks-table').DataTable({
+    data: isServerRenderedPage() ? window.initialBlocks : [],
     searching: false,
     lengthMenu: [50, 100, 250, 500, 1000],
     pageLength: DEFAULT_ROWS_PER_PAGE,
@@ -172,5 +184,10 @@ webix.ready(() => {
   dataTable();
 
   // global state update
-  reRenderPage();
+  if (isServerRenderedPage()) {
+    const { currentPage, pageArray } = window.pagination.generatePaginationUIParams();
+    window.pagination.generatePaginationUI(currentPage, pageArray);
+  } else {
+    reRenderPage();
+  }
 });
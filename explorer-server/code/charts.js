@@ -0,0 +1,60 @@
+const formatHashrate = hashrate => {
+  if (hashrate < 1e12) {
+    return (hashrate / 1e9).toFixed(2) + ' GH/s';
+  } else if (hashrate < 1e15) {
+    return (hashrate / 1e12).toFixed(2) + ' TH/s';
+  } else if (hashrate < 1e18) {
+    return (hashrate / 1e15).toFixed(2) + ' PH/s';
+  } else {
+    return (hashrate / 1e18).toFixed(2) + ' EH/s';
+  }
+};
+
+const renderLineChart = (container, data, value, formatValue) => {
+  $$(container) && $$(container).destructor();
+  webix.ui({
+    view: 'chart',
+    id: container,
+    container,
+    type: 'line',
+    height: 300,
+    value: `#${value}#`,
+    line: { color: '#0074c2', width: 2 },
+    item: { radius: 0 },
+    tooltip: { template: item => `${item.date}<br>${formatValue(item[value])}` },
+    xAxis: {
+      template: item => item.label,
+      lines: false,
+    },
+    yAxis: {
+      template: formatValue,
+    },
+    data,
+  });
+};
+
+const loadDifficultyChart = () => {
+  const days = $('#chart-days').val();
+
+  $.get('/api/charts/difficulty', { days }, response => {
+    // Only label about ten points on the x axis to keep it readable
+    const labelEvery = Math.max(1, Math.floor(response.data.length / 10));
+    const data = response.data.map((point, idx) => {
+      const date = moment(point.timestamp * 1000).format('ll');
+      return {
+        ...point,
+        date,
+        label: idx % labelEvery === 0 ? date : '',
+      };
+    });
+
+    renderLineChart('difficulty-chart', data, 'difficulty', difficulty => (
+      renderInteger(Math.round(difficulty))
+    ));
+    renderLineChart('hashrate-chart', data, 'hashrate', formatHashrate);
+  });
+};
+
+webix.ready(() => {
+  loadDifficultyChart();
+});
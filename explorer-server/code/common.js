@@ -12,6 +12,122 @@ var tzOffset;
   }
 }
 
+function getTimezonePreference() {
+  const match = document.cookie.match(/(?:^|; )timezone=(utc|local)/);
+  return match ? match[1] : 'local';
+}
+
+function toggleTimezonePreference() {
+  const timezone = getTimezonePreference() === 'utc' ? 'local' : 'utc';
+  document.cookie = `timezone=${timezone}; path=/; max-age=31536000; samesite=lax`;
+  location.reload();
+}
+
+const LOCALE_TAGS = { en: 'en-US', de: 'de-DE', fr: 'fr-FR' };
+
+// Same as the server: the locale cookie, or else the browser language
+function getLocalePreference() {
+  const match = document.cookie.match(/(?:^|; )locale=(en|de|fr)/);
+  if (match) {
+    return match[1];
+  }
+  const language = (navigator.language || 'en').substr(0, 2).toLowerCase();
+  return LOCALE_TAGS[language] ? language : 'en';
+}
+
+function setLocalePreference(locale) {
+  document.cookie = `locale=${locale}; path=/; max-age=31536000; samesite=lax`;
+  location.reload();
+}
+
+const UNIT_SYMBOLS = { xec: 'XEC', kxec: 'kXEC', sats: 'sats', bits: 'bits' };
+
+// Same as the server: the unit query parameter, or else the unit cookie
+function getUnitPreference() {
+  const param = new URLSearchParams(location.search).get('unit');
+  if (param && UNIT_SYMBOLS[param.toLowerCase()]) {
+    return param.toLowerCase();
+  }
+  const match = document.cookie.match(/(?:^|; )unit=(xec|kxec|sats|bits)/);
+  return match ? match[1] : 'xec';
+}
+
+function setUnitPreference(unit) {
+  document.cookie = `unit=${unit}; path=/; max-age=31536000; samesite=lax`;
+  // The query parameter would override the new cookie
+  const url = new URL(location.href);
+  url.searchParams.delete('unit');
+  location.href = url.toString();
+}
+
+function unitSymbol() {
+  return UNIT_SYMBOLS[getUnitPreference()];
+}
+
+function decimalSeparator() {
+  return getLocalePreference() === 'en' ? '.' : ',';
+}
+
+moment.locale(getLocalePreference());
+
+function formatTimestamp(timestamp) {
+  const time = moment(timestamp * 1000);
+  if (getTimezonePreference() === 'utc') {
+    return time.utc().format('ll, LTS') + ' UTC';
+  }
+  return time.format('ll, LTS');
+}
+
+$(document).ready(() => {
+  const label = getTimezonePreference() === 'utc' ? 'Times in UTC' : 'Times in local time';
+  $('#timezone-toggle').text(label);
+  $('#locale-select').val(getLocalePreference());
+  $('#unit-select').val(getUnitPreference());
+});
+
+function formatCountdown(secs) {
+  const days = Math.floor(secs / 86400);
+  const hours = Math.floor(secs % 86400 / 3600);
+  const minutes = Math.floor(secs % 3600 / 60);
+  const pad = number => String(number).padStart(2, '0');
+  return `${days}d ${pad(hours)}h ${pad(minutes)}m ${pad(secs % 60)}s`;
+}
+
+// Counts down to the next scheduled network upgrade, or shows the block the
+// last one activated at once they all activated
+function renderUpgradeBanner(upgrades) {
+  const pending = upgrades
+    .filter(upgrade => !upgrade.activated)
+    .sort((a, b) => a.estimatedActivationTime - b.estimatedActivationTime);
+  const banner = $('#upgrade-banner');
+  if (pending.length > 0) {
+    const upgrade = pending[0];
+    const render = () => {
+      const secs = upgrade.estimatedActivationTime - Math.floor(Date.now() / 1000);
+      banner.text(secs > 0
+        ? `The ${upgrade.name} network upgrade activates in ${formatCountdown(secs)}`
+        : `The ${upgrade.name} network upgrade is activating`);
+    };
+    render();
+    setInterval(render, 1000);
+  } else if (upgrades.length > 0) {
+    const upgrade = upgrades.reduce((a, b) =>
+      a.activationBlockHeight > b.activationBlockHeight ? a : b);
+    banner
+      .text(`The ${upgrade.name} network upgrade activated at block `)
+      .append($('<a>')
+        .attr('href', '/block-height/' + upgrade.activationBlockHeight)
+        .text(renderInteger(upgrade.activationBlockHeight)));
+  } else {
+    return;
+  }
+  banner.show();
+}
+
+$(document).ready(() => {
+  $.get('/api/upgrade', response => renderUpgradeBanner(response.upgrades));
+});
+
 function formatByteSize(size) {
   if (size < 1024) {
     return size + ' B';
@@ -23,7 +139,7 @@ function formatByteSize(size) {
 }
 
 function renderInteger(number) {
-  var fmt = Intl.NumberFormat('en-EN').format(number);
+  var fmt = Intl.NumberFormat(LOCALE_TAGS[getLocalePreference()]).format(number);
   // var parts = fmt.split(',');
   // var str = '';
   // for (var i = 0; i < parts.length; ++i) {
@@ -37,16 +153,18 @@ function renderInteger(number) {
   return fmt;
 }
 
+// Token amounts are sent as strings, as they can exceed Number.MAX_SAFE_INTEGER
 function renderAmount(baseAmount, decimals) {
   if (decimals === 0) {
-    return renderInteger(baseAmount);
+    return renderInteger(BigInt(baseAmount));
   }
-  var factor = Math.pow(10, decimals);
-  var humanAmount = baseAmount / factor;
-  var fmt = humanAmount.toFixed(decimals);
-  var parts = fmt.split('.');
-  var integerPart = parseInt(parts[0]);
-  var fractPart = parts[1];
+  // Split the digits as a string: dividing loses precision for large amounts,
+  // and toFixed switches to exponent notation from 1e21
+  var amount = BigInt(baseAmount);
+  var sign = amount < 0 ? '-' : '';
+  var digits = (amount < 0 ? -amount : amount).toString().padStart(decimals + 1, '0');
+  var integerPart = BigInt(digits.slice(0, digits.length - decimals));
+  var fractPart = digits.slice(digits.length - decimals);
   var numFractSections = Math.ceil(decimals / 3);
   var fractRendered = '';
   var allZeros = true;
@@ -59,10 +177,17 @@ function renderAmount(baseAmount, decimals) {
       (sectionIdx != numFractSections - 1 ? 'digit-sep ' : '');
     fractRendered = '<small class="' + classes + '">' + section + '</small>' + fractRendered;
   }
-  return renderInteger(integerPart) + '.' + fractRendered;
+  return sign + renderInteger(integerPart) + decimalSeparator() + fractRendered;
 }
 
+// Amount in the preferred unit, without the unit symbol
 function renderSats(sats) {
+  switch (getUnitPreference()) {
+    case 'sats':
+      return renderInteger(sats);
+    case 'kxec':
+      return renderAmount(sats, 5);
+  }
   var coins = sats / 100;
   var fmt = coins.toFixed('2');
   var parts = fmt.split('.');
@@ -71,9 +196,9 @@ function renderSats(sats) {
   var fractZero = fractPart === '00';
 
   if (fractZero) {
-    return renderInteger(integerPart) + '.<small>' + fractPart + '</small>';
+    return renderInteger(integerPart) + decimalSeparator() + '<small>' + fractPart + '</small>';
   } else {
-    return renderInteger(integerPart) + '.<small>' + fractPart + '</small>';
+    return renderInteger(integerPart) + decimalSeparator() + '<small>' + fractPart + '</small>';
   }
 }
 
@@ -89,7 +214,37 @@ function searchBarChange() {
   var search = $('#search-bar').val();
   if (search.match(regHex32) !== null) {
     location.href = '/search/' + search;
+    return;
   }
+  updateSearchSuggestions(search);
+}
+
+var searchSuggestionsTimeout;
+function updateSearchSuggestions(search) {
+  clearTimeout(searchSuggestionsTimeout);
+  if (search === '' || search === null) {
+    $('#search-suggestions').empty().hide();
+    return;
+  }
+
+  searchSuggestionsTimeout = setTimeout(() => {
+    $.get('/api/search/suggest', { q: search }, response => {
+      // Ignore responses for a query that has been edited since
+      if ($('#search-bar').val() !== search) {
+        return;
+      }
+
+      const suggestions = $('#search-suggestions').empty();
+      response.data.forEach(suggestion => {
+        $('<a class="search-suggestions__item">')
+          .attr('href', suggestion.url)
+          .append($('<span class="search-suggestions__kind">').text(suggestion.kind))
+          .append($('<span class="search-suggestions__label">').text(suggestion.label))
+          .appendTo(suggestions);
+      });
+      suggestions.toggle(response.data.length > 0);
+    });
+  }, 250);
 }
 
 function searchButton() {
@@ -98,10 +253,10 @@ function searchButton() {
   else if (search.match(regHex32) !== null) {
     location.href = '/search/' + search;
   }
-  else if (search.slice(0, 6) === 'ecash:' || search.slice(0, 7) === 'etoken:' ) {
+  else if (search.startsWith(window.addressPrefix + ':') || search.startsWith(window.tokenAddressPrefix + ':')) {
   location.href = '/search/' + search;
   } else if (search.length > 6) {
-    search = 'ecash:' + search;
+    search = window.addressPrefix + ':' + search;
     location.href = '/search/' + search;
   } else if (!isNaN(search)) {
     location.href = '/block-height/' + search;
@@ -114,6 +269,15 @@ function toggleTransactionScriptData() {
   });
 }
 
+// Switches annotated scripts between hex pushes, UTF-8 pushes and plain ASM
+$(document).on('click', '.script-ops__toggle button', function () {
+  const view = $(this).data('view');
+  const scriptOps = $(this).closest('.script-ops');
+  scriptOps.attr('data-view', view);
+  scriptOps.find('.script-ops__toggle button').removeClass('active');
+  $(this).addClass('active');
+});
+
 function minifyHash(hash) {
   return `${hash.slice(0, 1)}...${hash.slice(39, 64)}`;
 }
@@ -1,12 +1,30 @@
-$(document).ready(() => {
-  $('.moment__timestamp').each((index, element) => {
+const renderTimestamps = () => {
+  const useUtc = getTimezonePreference() === 'utc';
+
+  $('.timestamp').each((index, element) => {
     if (!element.dataset.timestamp) {
       return;
     }
 
-    const timestamp = element.dataset.timestamp * 1000;
-    const human_timestamp = moment(timestamp).format('L LTS');
+    const time = moment(element.dataset.timestamp * 1000);
+    const absolute = useUtc
+      ? `${time.clone().utc().format('L LTS')} UTC`
+      : `${time.format('L LTS')} (UTC ${tzOffset})`;
+
+    $(element).find('.timestamp__absolute').text(absolute);
+    $(element).find('.timestamp__relative').text(`(${time.fromNow()})`);
+  });
+
+  $('.timestamp-age').each((index, element) => {
+    if (!element.dataset.timestamp) {
+      return;
+    }
 
-    element.innerHTML = `${human_timestamp} <small>(UTC ${tzOffset})</small>`;
+    element.textContent = moment(element.dataset.timestamp * 1000).fromNow();
   });
+};
+
+$(document).ready(() => {
+  renderTimestamps();
+  setInterval(renderTimestamps, 30 * 1000);
 });
@@ -57,7 +57,7 @@ const renderOutput = (satsOutput, _type, row) => {
     var ticker = ' <a href="/tx/' + row.txHash + '">' + row.token.tokenTicker + '</a>';
     return renderAmount(row.stats.tokenOutput, row.token.decimals) + ticker;
   }
-  return renderSats(row.stats.satsOutput) + ' XEC';
+  return renderSats(row.stats.satsOutput) + ' ' + unitSymbol();
 };
 
 
@@ -0,0 +1,178 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bitcoin::hashes::{sha256, Hash};
+
+/// Failed admin token attempts allowed per IP address within
+/// `ADMIN_LOCKOUT`
+const MAX_ADMIN_FAILURES: u32 = 10;
+
+/// How long an IP address is locked out after `MAX_ADMIN_FAILURES` failed
+/// attempts, counted from its first failure
+const ADMIN_LOCKOUT: Duration = Duration::from_secs(15 * 60);
+
+/// IP addresses with failed attempts above which expired ones are pruned
+const MAX_TRACKED_IPS: usize = 10_000;
+
+pub enum AdminCheck {
+    Allowed,
+    Unauthorized,
+    LockedOut { retry_after_secs: u64 },
+}
+
+struct Failures {
+    first_at: Instant,
+    count: u32,
+}
+
+/// Checks the admin token of requests. Tokens are compared by their SHA-256
+/// digest, so the comparison doesn't leak how much of the token was right,
+/// and IP addresses with too many failed attempts are locked out for a while.
+pub struct AdminAuth {
+    token_hash: sha256::Hash,
+    failures: Mutex<HashMap<IpAddr, Failures>>,
+}
+
+impl AdminAuth {
+    pub fn new(admin_token: &str) -> Self {
+        AdminAuth {
+            token_hash: sha256::Hash::hash(admin_token.as_bytes()),
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn check(&self, token: Option<&str>, ip: IpAddr) -> AdminCheck {
+        self.check_at(token, ip, Instant::now())
+    }
+
+    fn check_at(&self, token: Option<&str>, ip: IpAddr, now: Instant) -> AdminCheck {
+        let mut failures = self.failures.lock().unwrap();
+        let is_expired = |ip_failures: &Failures| {
+            now.saturating_duration_since(ip_failures.first_at) >= ADMIN_LOCKOUT
+        };
+        if failures.len() > MAX_TRACKED_IPS {
+            failures.retain(|_, ip_failures| !is_expired(ip_failures));
+        }
+        if let Some(ip_failures) = failures.get(&ip) {
+            if is_expired(ip_failures) {
+                failures.remove(&ip);
+            } else if ip_failures.count >= MAX_ADMIN_FAILURES {
+                let locked_for =
+                    ADMIN_LOCKOUT - now.saturating_duration_since(ip_failures.first_at);
+                return AdminCheck::LockedOut {
+                    retry_after_secs: locked_for.as_secs().max(1),
+                };
+            }
+        }
+
+        let is_valid = token.map_or(false, |token| {
+            sha256::Hash::hash(token.as_bytes()) == self.token_hash
+        });
+        if is_valid {
+            failures.remove(&ip);
+            AdminCheck::Allowed
+        } else {
+            failures
+                .entry(ip)
+                .or_insert(Failures {
+                    first_at: now,
+                    count: 0,
+                })
+                .count += 1;
+            AdminCheck::Unauthorized
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Instant,
+    };
+
+    use super::{AdminAuth, AdminCheck, ADMIN_LOCKOUT, MAX_ADMIN_FAILURES};
+
+    const TOKEN: &str = "0123456789abcdef";
+
+    #[test]
+    fn test_check_token() {
+        let admin_auth = AdminAuth::new(TOKEN);
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        assert!(matches!(
+            admin_auth.check(Some(TOKEN), ip),
+            AdminCheck::Allowed
+        ));
+        assert!(matches!(
+            admin_auth.check(Some("0123456789abcdeg"), ip),
+            AdminCheck::Unauthorized
+        ));
+        assert!(matches!(
+            admin_auth.check(Some(""), ip),
+            AdminCheck::Unauthorized
+        ));
+        assert!(matches!(
+            admin_auth.check(None, ip),
+            AdminCheck::Unauthorized
+        ));
+    }
+
+    #[test]
+    fn test_lockout() {
+        let admin_auth = AdminAuth::new(TOKEN);
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        let other_ip = IpAddr::V4(Ipv4Addr::new(5, 6, 7, 8));
+        let start = Instant::now();
+        for _ in 0..MAX_ADMIN_FAILURES {
+            assert!(matches!(
+                admin_auth.check_at(Some("wrong"), ip, start),
+                AdminCheck::Unauthorized
+            ));
+        }
+
+        // Even the right token is rejected while locked out
+        let later = start + ADMIN_LOCKOUT / 3;
+        match admin_auth.check_at(Some(TOKEN), ip, later) {
+            AdminCheck::LockedOut { retry_after_secs } => {
+                assert_eq!(retry_after_secs, (ADMIN_LOCKOUT * 2 / 3).as_secs())
+            }
+            _ => panic!("Expected a lockout"),
+        }
+        assert!(matches!(
+            admin_auth.check_at(Some(TOKEN), other_ip, later),
+            AdminCheck::Allowed
+        ));
+
+        let expired = start + ADMIN_LOCKOUT;
+        assert!(matches!(
+            admin_auth.check_at(Some(TOKEN), ip, expired),
+            AdminCheck::Allowed
+        ));
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let admin_auth = AdminAuth::new(TOKEN);
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        let now = Instant::now();
+        for _ in 0..MAX_ADMIN_FAILURES - 1 {
+            admin_auth.check_at(Some("wrong"), ip, now);
+        }
+        assert!(matches!(
+            admin_auth.check_at(Some(TOKEN), ip, now),
+            AdminCheck::Allowed
+        ));
+        assert!(matches!(
+            admin_auth.check_at(Some("wrong"), ip, now),
+            AdminCheck::Unauthorized
+        ));
+        assert!(matches!(
+            admin_auth.check_at(Some(TOKEN), ip, now),
+            AdminCheck::Allowed
+        ));
+    }
+}
@@ -0,0 +1,213 @@
+//! Parsing of ALP (Augmented Ledger Protocol) token sections. ALP sections
+//! are pushes of an eMPP OP_RETURN output: OP_RETURN OP_RESERVED <section>...
+//!
+//! Chronik doesn't validate ALP txs yet, so sections are only decoded for
+//! display and may be invalid spends.
+
+use crate::{
+    blockchain::to_be_hex,
+    script_analysis::{parse_script, Instruction},
+};
+
+const OP_RETURN: u8 = 0x6a;
+const OP_RESERVED: u8 = 0x50;
+const ALP_LOKAD_ID: &[u8] = b"SLP2";
+pub const ALP_STANDARD: u8 = 0;
+
+pub struct AlpGenesisInfo {
+    pub token_ticker: String,
+    pub token_name: String,
+    pub url: String,
+    pub data: Vec<u8>,
+    pub auth_pubkey: Vec<u8>,
+    pub decimals: u8,
+}
+
+pub enum AlpTxType {
+    Genesis(AlpGenesisInfo),
+    Mint,
+    Send,
+    Burn,
+}
+
+pub struct AlpSection {
+    pub token_type: u8,
+    /// Big-endian hex, `None` for GENESIS, where the token id is the txid
+    pub token_id: Option<String>,
+    pub tx_type: AlpTxType,
+    /// Amounts by output index starting at output 1, or the burned amount
+    pub amounts: Vec<u64>,
+    pub num_batons: u8,
+}
+
+impl AlpSection {
+    pub fn tx_type_name(&self) -> &'static str {
+        match self.tx_type {
+            AlpTxType::Genesis(_) => "GENESIS",
+            AlpTxType::Mint => "MINT",
+            AlpTxType::Send => "SEND",
+            AlpTxType::Burn => "BURN",
+        }
+    }
+
+    pub fn genesis_info(&self) -> Option<&AlpGenesisInfo> {
+        match &self.tx_type {
+            AlpTxType::Genesis(genesis_info) => Some(genesis_info),
+            _ => None,
+        }
+    }
+}
+
+/// ALP sections of an eMPP output script, in order. Sections of other
+/// protocols are skipped and malformed ALP sections are returned as errors.
+pub fn parse_alp_sections(output_script: &[u8]) -> Vec<Result<AlpSection, String>> {
+    let instructions = match output_script {
+        [OP_RETURN, OP_RESERVED, pushes @ ..] => match parse_script(pushes) {
+            Some(instructions) => instructions,
+            None => return vec![],
+        },
+        _ => return vec![],
+    };
+    instructions
+        .into_iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Push(section) if section.starts_with(ALP_LOKAD_ID) => {
+                Some(parse_alp_section(&section[ALP_LOKAD_ID.len()..]))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_alp_section(data: &[u8]) -> Result<AlpSection, String> {
+    let mut reader = Reader { data };
+    let token_type = reader.read_u8()?;
+    if token_type != ALP_STANDARD {
+        return Err(format!("Unknown ALP token type {}", token_type));
+    }
+    let tx_type = reader.read_var_bytes()?;
+    let section = match tx_type {
+        b"GENESIS" => {
+            let genesis_info = AlpGenesisInfo {
+                token_ticker: reader.read_var_string()?,
+                token_name: reader.read_var_string()?,
+                url: reader.read_var_string()?,
+                data: reader.read_var_bytes()?.to_vec(),
+                auth_pubkey: reader.read_var_bytes()?.to_vec(),
+                decimals: reader.read_u8()?,
+            };
+            let (amounts, num_batons) = reader.read_mint_data()?;
+            AlpSection {
+                token_type,
+                token_id: None,
+                tx_type: AlpTxType::Genesis(genesis_info),
+                amounts,
+                num_batons,
+            }
+        }
+        b"MINT" => {
+            let token_id = reader.read_token_id()?;
+            let (amounts, num_batons) = reader.read_mint_data()?;
+            AlpSection {
+                token_type,
+                token_id: Some(token_id),
+                tx_type: AlpTxType::Mint,
+                amounts,
+                num_batons,
+            }
+        }
+        b"SEND" => {
+            let token_id = reader.read_token_id()?;
+            AlpSection {
+                token_type,
+                token_id: Some(token_id),
+                tx_type: AlpTxType::Send,
+                amounts: reader.read_amounts()?,
+                num_batons: 0,
+            }
+        }
+        b"BURN" => {
+            let token_id = reader.read_token_id()?;
+            AlpSection {
+                token_type,
+                token_id: Some(token_id),
+                tx_type: AlpTxType::Burn,
+                amounts: vec![reader.read_amount()?],
+                num_batons: 0,
+            }
+        }
+        _ => {
+            return Err(format!(
+                "Unknown ALP tx type {}",
+                String::from_utf8_lossy(tx_type)
+            ))
+        }
+    };
+    if !reader.data.is_empty() {
+        return Err("Unexpected data at the end of the ALP section".to_string());
+    }
+    Ok(section)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < len {
+            return Err("ALP section is too short".to_string());
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Bitcoin's CompactSize encoding
+    fn read_size(&mut self) -> Result<usize, String> {
+        let size = match self.read_u8()? {
+            0xfd => u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()) as u64,
+            0xfe => u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()) as u64,
+            0xff => u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()),
+            size => size as u64,
+        };
+        usize::try_from(size).map_err(|_| "ALP size too large".to_string())
+    }
+
+    fn read_var_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_size()?;
+        self.read_bytes(len)
+    }
+
+    fn read_var_string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.read_var_bytes()?).to_string())
+    }
+
+    fn read_token_id(&mut self) -> Result<String, String> {
+        Ok(to_be_hex(self.read_bytes(32)?))
+    }
+
+    /// Amounts are 48-bit little-endian integers
+    fn read_amount(&mut self) -> Result<u64, String> {
+        let mut amount = [0u8; 8];
+        amount[..6].copy_from_slice(self.read_bytes(6)?);
+        Ok(u64::from_le_bytes(amount))
+    }
+
+    fn read_amounts(&mut self) -> Result<Vec<u64>, String> {
+        let num_amounts = self.read_size()?;
+        (0..num_amounts).map(|_| self.read_amount()).collect()
+    }
+
+    fn read_mint_data(&mut self) -> Result<(Vec<u64>, u8), String> {
+        let amounts = self.read_amounts()?;
+        let num_batons = self.read_size()?;
+        let num_batons =
+            u8::try_from(num_batons).map_err(|_| "Too many mint batons".to_string())?;
+        Ok((amounts, num_batons))
+    }
+}
@@ -1,14 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use bitcoinsuite_chronik_client::proto::{Block, SlpGenesisInfo, Token, Tx, TxHistoryPage};
-use bitcoinsuite_core::CashAddress;
+use bitcoinsuite_chronik_client::proto::{
+    Block, BlockInfo, OutPoint, SlpGenesisInfo, SlpTxType, Token, Tx, Utxo,
+};
+use bitcoinsuite_chronik_client::ScriptType;
 use bitcoinsuite_error::Result;
+use chrono::{TimeZone, Utc};
+use serde_json::json;
 
 use crate::{
-    blockchain::to_be_hex,
-    server_primitives::{JsonToken, JsonTx, JsonTxStats},
+    blockchain::{
+        calculate_block_difficulty, coinbase_shares, compact_size_len, describe_lock_time,
+        describe_sequence, destination_from_script, script_type_payload, to_be_hex, CoinbaseShares,
+        Destination, BLOCKS_PER_DAY, COINBASE_MATURITY, DUST_LIMIT_SATS, MAX_OP_RETURN_SIZE,
+        MIN_RELAY_FEE_RATE,
+    },
+    coinbase::decode_coinbase_script,
+    config::Network,
+    opcodes::{opcode_description, opcode_name},
+    script_analysis::{parse_script_ops, Instruction},
+    server_primitives::{
+        CoinbaseOutputKind, JsonBlock, JsonBlockFeeStats, JsonBlockTokenSummary, JsonCoinbase,
+        JsonCoinbaseOutput, JsonCreatedToken, JsonOutputTypeCounts, JsonScript, JsonScriptOp,
+        JsonToken, JsonTokenMeta, JsonTx, JsonTxDetails, JsonTxFlow, JsonTxFlows, JsonTxInput,
+        JsonTxOutput, JsonTxSizeBreakdown, JsonTxStats, JsonTxWarning, JsonUtxo, TxWarningKind,
+    },
 };
 
+pub fn block_info_to_json(block: &BlockInfo) -> JsonBlock {
+    JsonBlock {
+        hash: to_be_hex(&block.hash),
+        height: block.height,
+        timestamp: block.timestamp,
+        difficulty: calculate_block_difficulty(block.n_bits),
+        size: block.block_size,
+        num_txs: block.num_txs,
+    }
+}
+
 pub fn tokens_to_json(tokens: &HashMap<String, Token>) -> Result<HashMap<String, JsonToken>> {
     let mut json_tokens = HashMap::new();
 
@@ -36,15 +65,71 @@ pub fn tokens_to_json(tokens: &HashMap<String, Token>) -> Result<HashMap<String,
     Ok(json_tokens)
 }
 
+/// Metadata of a token, `None` if Chronik has no genesis for it
+pub fn token_meta_to_json(token: &Token) -> Result<Option<JsonTokenMeta>> {
+    let slp_tx_data = match &token.slp_tx_data {
+        Some(slp_tx_data) => slp_tx_data,
+        None => return Ok(None),
+    };
+    let (slp_meta, genesis_info) = match (&slp_tx_data.slp_meta, &slp_tx_data.genesis_info) {
+        (Some(slp_meta), Some(genesis_info)) => (slp_meta, genesis_info),
+        _ => return Ok(None),
+    };
+    let (total_minted, total_burned) = match &token.token_stats {
+        Some(token_stats) => (
+            token_stats.total_minted.parse::<i128>()?,
+            token_stats.total_burned.parse::<i128>()?,
+        ),
+        None => (0, 0),
+    };
+    let decimals = genesis_info.decimals;
+    Ok(Some(JsonTokenMeta {
+        token_id: hex::encode(&slp_meta.token_id),
+        token_type: slp_meta.token_type as u32,
+        token_ticker: String::from_utf8_lossy(&genesis_info.token_ticker).to_string(),
+        token_name: String::from_utf8_lossy(&genesis_info.token_name).to_string(),
+        token_document_url: String::from_utf8_lossy(&genesis_info.token_document_url).to_string(),
+        token_document_hash: hex::encode(&genesis_info.token_document_hash),
+        decimals,
+        group_id: Some(hex::encode(&slp_meta.group_token_id))
+            .filter(|group_id| !group_id.is_empty()),
+        genesis_block_height: token.block.as_ref().map(|block| block.height),
+        genesis_timestamp: token
+            .block
+            .as_ref()
+            .map_or(token.time_first_seen, |block| block.timestamp),
+        total_minted: format_token_amount(total_minted, decimals),
+        total_burned: format_token_amount(total_burned, decimals),
+        circulating_supply: format_token_amount(total_minted - total_burned, decimals),
+    }))
+}
+
+/// Token amount in base units as a decimal string, e.g. 100050 with 2
+/// decimals is "1000.50"
+pub fn format_token_amount(base_amount: i128, decimals: u32) -> String {
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return base_amount.to_string();
+    }
+    let sign = if base_amount < 0 { "-" } else { "" };
+    let digits = format!(
+        "{:0width$}",
+        base_amount.unsigned_abs(),
+        width = decimals + 1
+    );
+    let (integer_part, fract_part) = digits.split_at(digits.len() - decimals);
+    format!("{}{}.{}", sign, integer_part, fract_part)
+}
+
+/// Txs in the history of the locking `script`, with the stats of the script
 pub fn tx_history_to_json(
-    address: &CashAddress,
-    address_tx_history: TxHistoryPage,
+    script: &[u8],
+    txs: &[Tx],
     json_tokens: &HashMap<String, JsonToken>,
 ) -> Result<Vec<JsonTx>> {
     let mut json_txs = Vec::new();
-    let address_bytes = address.to_script().bytecode().to_vec();
 
-    for tx in address_tx_history.txs.iter() {
+    for tx in txs.iter() {
         let (block_height, timestamp) = match &tx.block {
             Some(block) => (Some(block.height), block.timestamp),
             None => (None, tx.time_first_seen),
@@ -64,11 +149,12 @@ pub fn tx_history_to_json(
             None => (None, None),
         };
 
-        let stats = calc_tx_stats(tx, Some(&address_bytes));
+        let stats = calc_tx_stats(tx, Some(script));
 
         json_txs.push(JsonTx {
             tx_hash: to_be_hex(&tx.txid),
             block_height,
+            is_mempool: block_height.is_none(),
             timestamp,
             is_coinbase: tx.is_coinbase,
             size: tx.size as i32,
@@ -77,6 +163,7 @@ pub fn tx_history_to_json(
             stats,
             token_id,
             token,
+            price_usd: None,
         });
     }
 
@@ -139,6 +226,7 @@ pub fn block_txs_to_json(
         json_txs.push(JsonTx {
             tx_hash: to_be_hex(&tx.txid),
             block_height,
+            is_mempool: block_height.is_none(),
             timestamp,
             is_coinbase: tx.is_coinbase,
             size: tx.size as i32,
@@ -147,12 +235,440 @@ pub fn block_txs_to_json(
             stats,
             token_id,
             token,
+            price_usd: None,
         });
     }
 
     Ok(json_txs)
 }
 
+pub fn coinbase_to_json(
+    coinbase: &Tx,
+    block_info: &BlockInfo,
+    network: Network,
+    prefix: &str,
+) -> JsonCoinbase {
+    let script = &coinbase.inputs[0].input_script;
+    let coinbase_data = decode_coinbase_script(script, block_info.height);
+    JsonCoinbase {
+        script_hex: hex::encode(script),
+        height: coinbase_data.height,
+        extra_nonce: coinbase_data.extra_nonce.map(hex::encode),
+        miner_tag: coinbase_data.miner_tag,
+        outputs: calc_coinbase_split(
+            coinbase,
+            coinbase_shares(network, block_info.timestamp),
+            prefix,
+        ),
+    }
+}
+
+/// Splits the coinbase outputs into miner, miner fund and staking reward
+/// payouts. Nodes enforce a minimum share of the block reward for the latter
+/// two, and miners pay exactly that share, so outputs are identified by their
+/// amount. Zero value outputs (e.g. OP_RETURN) are skipped.
+fn calc_coinbase_split(
+    coinbase: &Tx,
+    shares: CoinbaseShares,
+    prefix: &str,
+) -> Vec<JsonCoinbaseOutput> {
+    let block_reward: i64 = coinbase.outputs.iter().map(|output| output.value).sum();
+    let miner_fund_amount = block_reward * shares.miner_fund_percent / 100;
+    let staking_reward_amount = block_reward * shares.staking_reward_percent / 100;
+
+    // Eras without the output have a share of 0, which can't match
+    let mut has_miner_fund = miner_fund_amount == 0;
+    let mut has_staking_reward = staking_reward_amount == 0;
+    let mut json_outputs = Vec::new();
+
+    for (out_idx, output) in coinbase.outputs.iter().enumerate() {
+        if output.value == 0 {
+            continue;
+        }
+
+        let kind = if out_idx > 0 && !has_miner_fund && output.value == miner_fund_amount {
+            has_miner_fund = true;
+            CoinbaseOutputKind::MinerFund
+        } else if out_idx > 0 && !has_staking_reward && output.value == staking_reward_amount {
+            has_staking_reward = true;
+            CoinbaseOutputKind::StakingReward
+        } else {
+            CoinbaseOutputKind::Miner
+        };
+
+        let address = match destination_from_script(prefix, &output.output_script) {
+            Destination::Address(address) => Some(address.as_str().to_string()),
+            _ => None,
+        };
+
+        json_outputs.push(JsonCoinbaseOutput {
+            out_idx: out_idx as u32,
+            kind,
+            address,
+            sats_amount: output.value,
+        });
+    }
+
+    json_outputs
+}
+
+/// When Chronik first saw the tx in its mempool. It keeps the time after the
+/// tx is mined, and it's 0 for txs it only saw in a block, e.g. ones mined
+/// while Chronik was syncing.
+pub fn tx_time_first_seen(tx: &Tx) -> Option<i64> {
+    Some(tx.time_first_seen).filter(|&time_first_seen| time_first_seen > 0)
+}
+
+/// Seconds from the tx being first seen until the timestamp of its block.
+/// Block timestamps are set by miners and can be earlier than when the block
+/// was found, so delays that would be negative are `None`.
+pub fn tx_confirmation_delay_secs(tx: &Tx) -> Option<i64> {
+    let block = tx.block.as_ref()?;
+    let time_first_seen = tx_time_first_seen(tx)?;
+    Some(block.timestamp - time_first_seen).filter(|&delay_secs| delay_secs >= 0)
+}
+
+pub fn tx_details_to_json(tx: &Tx, prefix: &str) -> JsonTxDetails {
+    let address = |script: &[u8]| match destination_from_script(prefix, script) {
+        Destination::Address(address) => Some(address.as_str().to_string()),
+        _ => None,
+    };
+    let sequences = tx
+        .inputs
+        .iter()
+        .map(|input| input.sequence_no)
+        .collect::<Vec<_>>();
+
+    JsonTxDetails {
+        tx_hash: to_be_hex(&tx.txid),
+        version: tx.version,
+        lock_time: tx.lock_time,
+        lock_time_description: describe_lock_time(tx.lock_time, &sequences),
+        size: tx.size,
+        is_coinbase: tx.is_coinbase,
+        block_height: tx.block.as_ref().map(|block| block.height),
+        timestamp: tx
+            .block
+            .as_ref()
+            .map_or(tx.time_first_seen, |block| block.timestamp),
+        time_first_seen: tx_time_first_seen(tx),
+        confirmation_delay_secs: tx_confirmation_delay_secs(tx),
+        stats: calc_tx_stats(tx, None),
+        coin_days_destroyed: None,
+        size_breakdown: calc_tx_size_breakdown(tx),
+        warnings: calc_tx_warnings(tx),
+        inputs: tx
+            .inputs
+            .iter()
+            .map(|input| {
+                let prev_out = input.prev_out.as_ref().expect("Impossible");
+                JsonTxInput {
+                    prev_tx_hash: to_be_hex(&prev_out.txid),
+                    prev_out_idx: prev_out.out_idx,
+                    sats_amount: input.value,
+                    address: address(&input.output_script),
+                    sequence: input.sequence_no,
+                    sequence_description: describe_sequence(input.sequence_no, tx.version),
+                }
+            })
+            .collect(),
+        outputs: tx
+            .outputs
+            .iter()
+            .map(|output| JsonTxOutput {
+                sats_amount: output.value,
+                address: address(&output.output_script),
+                output_script: hex::encode(&output.output_script),
+                spent_by_tx_hash: output
+                    .spent_by
+                    .as_ref()
+                    .map(|spent_by| to_be_hex(&spent_by.txid)),
+            })
+            .collect(),
+    }
+}
+
+/// Flags outputs and fees nodes wouldn't relay with their default policies
+pub fn calc_tx_warnings(tx: &Tx) -> Vec<JsonTxWarning> {
+    const OP_RETURN: u8 = 106;
+
+    let mut warnings = Vec::new();
+    for (out_idx, output) in tx.outputs.iter().enumerate() {
+        let out_idx = Some(out_idx as u32);
+        let script = &output.output_script;
+        if script.first() == Some(&OP_RETURN) {
+            if script.len() > MAX_OP_RETURN_SIZE {
+                warnings.push(JsonTxWarning {
+                    kind: TxWarningKind::LargeOpReturn,
+                    out_idx,
+                    message: format!(
+                        "OP_RETURN output of {} bytes, more than the {} bytes nodes relay",
+                        script.len(),
+                        MAX_OP_RETURN_SIZE,
+                    ),
+                });
+            }
+            continue;
+        }
+        if output.value < DUST_LIMIT_SATS {
+            warnings.push(JsonTxWarning {
+                kind: TxWarningKind::DustOutput,
+                out_idx,
+                message: format!(
+                    "Output of {} sats, below the dust limit of {} sats",
+                    output.value, DUST_LIMIT_SATS,
+                ),
+            });
+        }
+        // The prefix doesn't matter for the script type
+        if let Destination::Unknown(_) = destination_from_script("ecash", script) {
+            warnings.push(JsonTxWarning {
+                kind: TxWarningKind::NonStandardScript,
+                out_idx,
+                message: "Output script of no standard type".to_string(),
+            });
+        }
+    }
+
+    if !tx.is_coinbase && tx.size > 0 {
+        let stats = calc_tx_stats(tx, None);
+        let fee_rate = (stats.sats_input - stats.sats_output) * 1000 / tx.size as i64;
+        if fee_rate < MIN_RELAY_FEE_RATE {
+            warnings.push(JsonTxWarning {
+                kind: TxWarningKind::LowFeeRate,
+                out_idx: None,
+                message: format!(
+                    "Fee rate of {} sats/kB, below the minimum relay fee rate of {} sats/kB",
+                    fee_rate, MIN_RELAY_FEE_RATE,
+                ),
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Splits the size of the serialized tx into its inputs, outputs and the
+/// rest, from the scripts Chronik returns with the tx
+pub fn calc_tx_size_breakdown(tx: &Tx) -> JsonTxSizeBreakdown {
+    let script_size = |script: &[u8]| compact_size_len(script.len() as u64) + script.len() as u32;
+    // Outpoint (32 byte txid and 4 byte index), script and 4 byte sequence
+    let inputs_size = tx
+        .inputs
+        .iter()
+        .map(|input| 36 + script_size(&input.input_script) + 4)
+        .sum();
+    // 8 byte value and script
+    let outputs_size = tx
+        .outputs
+        .iter()
+        .map(|output| 8 + script_size(&output.output_script))
+        .sum();
+    // 4 byte version and lock time
+    let overhead_size =
+        8 + compact_size_len(tx.inputs.len() as u64) + compact_size_len(tx.outputs.len() as u64);
+    JsonTxSizeBreakdown {
+        inputs_size,
+        outputs_size,
+        overhead_size,
+    }
+}
+
+/// Sum of the XEC value of each input times the days since the output it
+/// spends was mined. `prev_timestamps` maps the txids of the spent outputs to
+/// the timestamps of their blocks; outputs spent from the mempool are 0 days
+/// old.
+pub fn calc_coin_days_destroyed(tx: &Tx, prev_timestamps: &HashMap<Vec<u8>, i64>) -> f64 {
+    if tx.is_coinbase {
+        return 0.0;
+    }
+    let spend_timestamp = tx
+        .block
+        .as_ref()
+        .map_or(tx.time_first_seen, |block| block.timestamp);
+
+    tx.inputs
+        .iter()
+        .map(|input| {
+            let prev_out = input.prev_out.as_ref().expect("Impossible");
+            let prev_timestamp = prev_timestamps
+                .get(&prev_out.txid)
+                .copied()
+                .unwrap_or(spend_timestamp);
+            let age_days = (spend_timestamp - prev_timestamp).max(0) as f64 / 86400.0;
+            input.value as f64 / 100.0 * age_days
+        })
+        .sum()
+}
+
+/// Mempool UTXOs have 0 confirmations. The age is estimated from the number of
+/// confirmations, as UTXOs don't carry the timestamp of their block.
+pub fn script_to_json(script: &[u8]) -> JsonScript {
+    let (instructions, is_malformed) = parse_script_ops(script);
+    let ops = instructions
+        .into_iter()
+        .map(|(opcode, instruction)| {
+            let data = match instruction {
+                Instruction::Push(data) => Some(data),
+                Instruction::Op(_) => None,
+            };
+            JsonScriptOp {
+                opcode: opcode_name(opcode),
+                description: opcode_description(opcode),
+                data_hex: data.map(hex::encode),
+                data_utf8: data
+                    .and_then(|data| std::str::from_utf8(data).ok())
+                    .filter(|text| !text.is_empty() && !text.chars().any(char::is_control))
+                    .map(str::to_string),
+            }
+        })
+        .collect::<Vec<_>>();
+    let asm = ops
+        .iter()
+        .map(|op| op.data_hex.as_ref().unwrap_or(&op.opcode).as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    JsonScript {
+        hex: hex::encode(script),
+        asm,
+        ops,
+        is_malformed,
+    }
+}
+
+pub fn utxo_to_json(utxo: &Utxo, best_height: i32) -> JsonUtxo {
+    let OutPoint { txid, out_idx } = utxo.outpoint.as_ref().expect("Impossible");
+    let confirmations = if utxo.block_height < 0 {
+        0
+    } else {
+        best_height - utxo.block_height + 1
+    };
+
+    JsonUtxo {
+        tx_hash: to_be_hex(txid),
+        out_idx: *out_idx,
+        sats_amount: utxo.value,
+        token_amount: utxo
+            .slp_token
+            .as_ref()
+            .map_or(0, |slp_token| slp_token.amount),
+        is_coinbase: utxo.is_coinbase,
+        block_height: utxo.block_height,
+        confirmations,
+        age_days: (confirmations / BLOCKS_PER_DAY) as u32,
+        is_dust: utxo.slp_token.is_none() && utxo.value < DUST_LIMIT_SATS,
+        immature_blocks_remaining: if utxo.is_coinbase && confirmations < COINBASE_MATURITY {
+            Some(COINBASE_MATURITY - confirmations)
+        } else {
+            None
+        },
+    }
+}
+
+/// Token amounts burned by the tx as (token id, amount, is invalid spend).
+/// Inputs spent in a tx that isn't valid for their token are burned entirely,
+/// and a valid tx burns whatever token input amount it doesn't send on.
+pub fn calc_token_burns(tx: &Tx) -> Vec<(String, i128, bool)> {
+    let mut burns = Vec::new();
+
+    if let Some(slp_meta) = tx
+        .slp_tx_data
+        .as_ref()
+        .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+    {
+        let stats = calc_tx_stats(tx, None);
+        if stats.token_input > stats.token_output {
+            burns.push((
+                hex::encode(&slp_meta.token_id),
+                stats.token_input - stats.token_output,
+                false,
+            ));
+        }
+    }
+
+    for slp_burn in tx.inputs.iter().filter_map(|input| input.slp_burn.as_ref()) {
+        let amount = i128::from(slp_burn.token.as_ref().map_or(0, |token| token.amount));
+        if amount == 0 {
+            continue;
+        }
+        let token_id = hex::encode(&slp_burn.token_id);
+        match burns
+            .iter_mut()
+            .find(|(burn_token_id, _, is_invalid_spend)| {
+                *burn_token_id == token_id && *is_invalid_spend
+            }) {
+            Some((_, burn_amount, _)) => *burn_amount += amount,
+            None => burns.push((token_id, amount, true)),
+        }
+    }
+
+    burns
+}
+
+/// Groups the inputs and outputs of the tx by script into the net flow of
+/// each address. Coinbase inputs and empty OP_RETURN outputs aren't flows.
+pub fn calc_tx_flows(tx: &Tx, prefix: &str) -> JsonTxFlows {
+    let mut flows: Vec<JsonTxFlow> = Vec::new();
+    let mut add_flow = |script: &[u8], sats_input: i64, sats_output: i64, delta_tokens: i128| {
+        let script_hex = hex::encode(script);
+        let flow = match flows.iter_mut().find(|flow| flow.script_hex == script_hex) {
+            Some(flow) => flow,
+            None => {
+                let address = match destination_from_script(prefix, script) {
+                    Destination::Address(address) => Some(address.as_str().to_string()),
+                    _ => None,
+                };
+                flows.push(JsonTxFlow {
+                    address,
+                    script_hex,
+                    sats_input: 0,
+                    sats_output: 0,
+                    delta_sats: 0,
+                    delta_tokens: 0,
+                });
+                flows.last_mut().expect("Impossible")
+            }
+        };
+        flow.sats_input += sats_input;
+        flow.sats_output += sats_output;
+        flow.delta_sats += sats_output - sats_input;
+        flow.delta_tokens += delta_tokens;
+    };
+
+    if !tx.is_coinbase {
+        for input in &tx.inputs {
+            let tokens = input
+                .slp_token
+                .as_ref()
+                .map_or(0, |slp| i128::from(slp.amount));
+            add_flow(&input.output_script, input.value, 0, -tokens);
+        }
+    }
+    for output in &tx.outputs {
+        if output.value == 0 && output.output_script.first() == Some(&0x6a) {
+            continue;
+        }
+        let tokens = output
+            .slp_token
+            .as_ref()
+            .map_or(0, |slp| i128::from(slp.amount));
+        add_flow(&output.output_script, 0, output.value, tokens);
+    }
+    flows.sort_by_key(|flow| flow.delta_sats);
+
+    let stats = calc_tx_stats(tx, None);
+    JsonTxFlows {
+        tx_hash: to_be_hex(&tx.txid),
+        flows,
+        fee_sats: if tx.is_coinbase {
+            0
+        } else {
+            stats.sats_input - stats.sats_output
+        },
+    }
+}
+
 pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
     let sats_input = tx.inputs.iter().map(|input| input.value).sum();
     let sats_output = tx.outputs.iter().map(|output| output.value).sum();
@@ -160,18 +676,18 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         .inputs
         .iter()
         .filter_map(|input| input.slp_token.as_ref())
-        .map(|token| token.amount as i128)
+        .map(|token| i128::from(token.amount))
         .sum();
     let token_output: i128 = tx
         .outputs
         .iter()
         .filter_map(|output| output.slp_token.as_ref())
-        .map(|token| token.amount as i128)
+        .map(|token| i128::from(token.amount))
         .sum();
     let does_burn_slp = tx.inputs.iter().any(|input| input.slp_burn.is_some());
 
     let mut delta_sats: i64 = 0;
-    let mut delta_tokens: i64 = 0;
+    let mut delta_tokens: i128 = 0;
 
     for input in &tx.inputs {
         if let Some(address_bytes) = address_bytes {
@@ -181,7 +697,7 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         }
         delta_sats -= input.value;
         if let Some(slp) = &input.slp_token {
-            delta_tokens -= slp.amount as i64;
+            delta_tokens -= i128::from(slp.amount);
         }
     }
 
@@ -193,7 +709,7 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         }
         delta_sats += output.value;
         if let Some(slp) = &output.slp_token {
-            delta_tokens += slp.amount as i64;
+            delta_tokens += i128::from(slp.amount);
         }
     }
 
@@ -207,3 +723,141 @@ pub fn calc_tx_stats(tx: &Tx, address_bytes: Option<&[u8]>) -> JsonTxStats {
         does_burn_slp,
     }
 }
+
+/// Fee rates are in sats per kB, like on the tx tables. Returns `None` if the
+/// block only has the coinbase tx.
+pub fn calc_block_fee_stats(txs: &[Tx]) -> Option<JsonBlockFeeStats> {
+    let mut total_fees = 0;
+    let mut fee_rates = Vec::with_capacity(txs.len());
+
+    for tx in txs.iter().filter(|tx| !tx.is_coinbase && tx.size > 0) {
+        let stats = calc_tx_stats(tx, None);
+        let fee = stats.sats_input - stats.sats_output;
+        total_fees += fee;
+        fee_rates.push(fee * 1000 / tx.size as i64);
+    }
+
+    fee_rates.sort_unstable();
+
+    Some(JsonBlockFeeStats {
+        total_fees,
+        min_fee_rate: *fee_rates.first()?,
+        median_fee_rate: fee_rates[fee_rates.len() / 2],
+        max_fee_rate: *fee_rates.last()?,
+    })
+}
+
+/// Counts the outputs of `txs` by the type of their locking script
+pub fn calc_output_type_counts(txs: &[Tx]) -> JsonOutputTypeCounts {
+    const OP_RETURN: u8 = 106;
+
+    let mut counts = JsonOutputTypeCounts::default();
+    for output in txs.iter().flat_map(|tx| &tx.outputs) {
+        if output.output_script.first() == Some(&OP_RETURN) {
+            counts.op_return += 1;
+            continue;
+        }
+        match script_type_payload(&output.output_script).0 {
+            ScriptType::P2pkh => counts.p2pkh += 1,
+            ScriptType::P2sh => counts.p2sh += 1,
+            ScriptType::P2pk => counts.p2pk += 1,
+            _ => counts.unknown += 1,
+        }
+    }
+    counts
+}
+
+/// Summarizes the token txs of a block. Only SLP txs are counted, as Chronik
+/// doesn't validate ALP txs yet.
+pub fn calc_block_token_summary(txs: &[Tx]) -> JsonBlockTokenSummary {
+    let mut summary = JsonBlockTokenSummary::default();
+    let mut token_ids = HashSet::new();
+    for slp_tx_data in txs.iter().filter_map(|tx| tx.slp_tx_data.as_ref()) {
+        let slp_meta = match &slp_tx_data.slp_meta {
+            Some(slp_meta) => slp_meta,
+            None => continue,
+        };
+        summary.num_token_txs += 1;
+        token_ids.insert(&slp_meta.token_id);
+        if let (SlpTxType::Genesis, Some(genesis_info)) =
+            (slp_meta.tx_type(), &slp_tx_data.genesis_info)
+        {
+            summary.created_tokens.push(JsonCreatedToken {
+                token_id: hex::encode(&slp_meta.token_id),
+                token_ticker: String::from_utf8_lossy(&genesis_info.token_ticker).to_string(),
+                token_name: String::from_utf8_lossy(&genesis_info.token_name).to_string(),
+            });
+        }
+    }
+    summary.num_tokens = token_ids.len() as u64;
+    summary
+}
+
+pub fn sum_output_type_counts(
+    counts: impl IntoIterator<Item = JsonOutputTypeCounts>,
+) -> JsonOutputTypeCounts {
+    counts
+        .into_iter()
+        .fold(JsonOutputTypeCounts::default(), |total, counts| {
+            JsonOutputTypeCounts {
+                p2pkh: total.p2pkh + counts.p2pkh,
+                p2sh: total.p2sh + counts.p2sh,
+                p2pk: total.p2pk + counts.p2pk,
+                op_return: total.op_return + counts.op_return,
+                unknown: total.unknown + counts.unknown,
+            }
+        })
+}
+
+/// schema.org JSON-LD describing a block page. The page URL is only included
+/// if the public URL of the explorer is configured.
+pub fn block_structured_data(block_info: &BlockInfo, public_url: Option<&str>) -> Result<String> {
+    let block_hash = to_be_hex(&block_info.hash);
+    let mut structured_data = json!({
+        "@context": "https://schema.org",
+        "@type": "WebPage",
+        "name": format!("eCash Block #{}", block_info.height),
+        "description": format!(
+            "eCash block {} at height {} with {} transactions",
+            block_hash, block_info.height, block_info.num_txs,
+        ),
+        "identifier": block_hash,
+        "datePublished": Utc.timestamp(block_info.timestamp, 0).to_rfc3339(),
+    });
+    if let Some(public_url) = public_url {
+        structured_data["url"] = json!(format!("{}/block/{}", public_url, block_hash));
+    }
+    to_script_json(&structured_data)
+}
+
+/// schema.org JSON-LD describing a tx page, dated by its block or when it was
+/// first seen.
+pub fn tx_structured_data(tx: &Tx, public_url: Option<&str>) -> Result<String> {
+    let txid = to_be_hex(&tx.txid);
+    let timestamp = match &tx.block {
+        Some(block) => block.timestamp,
+        None => tx.time_first_seen,
+    };
+    let mut structured_data = json!({
+        "@context": "https://schema.org",
+        "@type": "WebPage",
+        "name": format!("eCash Transaction {}", txid),
+        "description": format!(
+            "eCash transaction {} with {} inputs and {} outputs",
+            txid,
+            tx.inputs.len(),
+            tx.outputs.len(),
+        ),
+        "identifier": txid,
+        "datePublished": Utc.timestamp(timestamp, 0).to_rfc3339(),
+    });
+    if let Some(public_url) = public_url {
+        structured_data["url"] = json!(format!("{}/tx/{}", public_url, txid));
+    }
+    to_script_json(&structured_data)
+}
+
+/// JSON that is safe to embed in a <script> tag
+fn to_script_json(value: &serde_json::Value) -> Result<String> {
+    Ok(serde_json::to_string(value)?.replace("</", "<\\/"))
+}
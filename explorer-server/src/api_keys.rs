@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv6Addr},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::http::HeaderMap;
+use bitcoinsuite_error::Result;
+use eyre::WrapErr;
+use serde::Deserialize;
+
+use crate::server_primitives::JsonApiUsage;
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct QuotaConfig {
+    pub per_minute: u32,
+    pub daily: u32,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        QuotaConfig {
+            per_minute: 120,
+            daily: 20_000,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiKeyEntry {
+    name: String,
+    #[serde(flatten)]
+    quota: QuotaConfig,
+}
+
+#[derive(Deserialize)]
+struct ApiKeysFile {
+    #[serde(default)]
+    keys: HashMap<String, ApiKeyEntry>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum ApiClient {
+    Key(String),
+    /// IP address of the client, or its /64 for IPv6
+    Anonymous(IpAddr),
+}
+
+#[derive(Default)]
+struct Usage {
+    minute: u64,
+    minute_count: u32,
+    day: u64,
+    day_count: u32,
+}
+
+pub enum QuotaCheck {
+    Allowed,
+    UnknownKey,
+    Exceeded { retry_after_secs: u64 },
+}
+
+#[derive(Default)]
+struct UsageTable {
+    clients: HashMap<ApiClient, Usage>,
+    /// Minute the clients were last pruned in
+    pruned_minute: u64,
+}
+
+/// Per-minute and daily request quotas for the API. Clients sending a key
+/// from the keys file in the `X-API-Key` header get the quota of that key,
+/// everyone else shares the anonymous quota per IP address. Usage is only
+/// counted in memory, so it resets on restart.
+pub struct ApiQuotas {
+    keys: HashMap<String, ApiKeyEntry>,
+    anonymous_quota: QuotaConfig,
+    /// Header with the client's IP address, set by a reverse proxy
+    client_ip_header: Option<String>,
+    usage: Mutex<UsageTable>,
+}
+
+impl ApiQuotas {
+    pub fn load(
+        keys_path: Option<&Path>,
+        anonymous_quota: QuotaConfig,
+        client_ip_header: Option<String>,
+    ) -> Result<Self> {
+        let keys = match keys_path {
+            Some(keys_path) => {
+                let keys_string = fs::read_to_string(keys_path).wrap_err_with(|| {
+                    format!("Failed to read API keys file {}", keys_path.display())
+                })?;
+                let keys_file: ApiKeysFile = toml::from_str(&keys_string)
+                    .wrap_err_with(|| format!("Invalid API keys file {}", keys_path.display()))?;
+                keys_file.keys
+            }
+            None => HashMap::new(),
+        };
+
+        Ok(ApiQuotas {
+            keys,
+            anonymous_quota,
+            client_ip_header,
+            usage: Mutex::new(UsageTable::default()),
+        })
+    }
+
+    /// IP address of the client, taken from the `client_ip_header` if set.
+    /// Proxies append the address they got the request from to
+    /// `X-Forwarded-For`, so the last one is used. Falls back to the address
+    /// the request came from if the header is missing or invalid.
+    pub fn client_ip(&self, headers: &HeaderMap, peer_ip: IpAddr) -> IpAddr {
+        self.client_ip_header
+            .as_ref()
+            .and_then(|client_ip_header| headers.get(client_ip_header.as_str()))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer_ip)
+    }
+
+    /// Counts `cost` requests of the client and checks them against its
+    /// quota. Heavy endpoints count as several requests.
+    pub fn check(&self, api_key: Option<&str>, ip: IpAddr, cost: u32) -> QuotaCheck {
+        let (client, quota) = match self.client(api_key, ip) {
+            Some(client) => client,
+            None => return QuotaCheck::UnknownKey,
+        };
+        let now = unix_secs();
+        let (minute, day) = (now / 60, now / 86400);
+
+        let mut usage = self.usage.lock().unwrap();
+        // Forget clients that last made a request on a previous day, so
+        // anonymous IPs don't pile up
+        if usage.pruned_minute != minute {
+            usage.pruned_minute = minute;
+            usage
+                .clients
+                .retain(|_, client_usage| client_usage.day == day);
+        }
+
+        let client_usage = usage.clients.entry(client).or_default();
+        if client_usage.minute != minute {
+            client_usage.minute = minute;
+            client_usage.minute_count = 0;
+        }
+        if client_usage.day != day {
+            client_usage.day = day;
+            client_usage.day_count = 0;
+        }
+
+        if client_usage.day_count.saturating_add(cost) > quota.daily {
+            QuotaCheck::Exceeded {
+                retry_after_secs: (day + 1) * 86400 - now,
+            }
+        } else if client_usage.minute_count.saturating_add(cost) > quota.per_minute {
+            QuotaCheck::Exceeded {
+                retry_after_secs: (minute + 1) * 60 - now,
+            }
+        } else {
+            client_usage.minute_count += cost;
+            client_usage.day_count += cost;
+            QuotaCheck::Allowed
+        }
+    }
+
+    pub fn usage(&self, api_key: Option<&str>, ip: IpAddr) -> Option<JsonApiUsage> {
+        let (client, quota) = self.client(api_key, ip)?;
+        let now = unix_secs();
+        let usage = self.usage.lock().unwrap();
+        let client_usage = usage.clients.get(&client);
+
+        Some(JsonApiUsage {
+            key_name: api_key.map(|api_key| self.keys[api_key].name.clone()),
+            per_minute_limit: quota.per_minute,
+            daily_limit: quota.daily,
+            used_this_minute: client_usage
+                .filter(|client_usage| client_usage.minute == now / 60)
+                .map_or(0, |client_usage| client_usage.minute_count),
+            used_today: client_usage
+                .filter(|client_usage| client_usage.day == now / 86400)
+                .map_or(0, |client_usage| client_usage.day_count),
+        })
+    }
+
+    fn client(&self, api_key: Option<&str>, ip: IpAddr) -> Option<(ApiClient, QuotaConfig)> {
+        match api_key {
+            Some(api_key) => {
+                let entry = self.keys.get(api_key)?;
+                Some((ApiClient::Key(api_key.to_string()), entry.quota))
+            }
+            None => Some((ApiClient::Anonymous(ip_prefix(ip)), self.anonymous_quota)),
+        }
+    }
+}
+
+/// IPv6 clients usually get a whole /64, so they're counted by it. IPv4
+/// addresses mapped to IPv6 by dual-stack sockets are counted as IPv4.
+fn ip_prefix(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(ipv6) => {
+            let segments = ipv6.segments();
+            if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return IpAddr::from([a, b, c, d]);
+            }
+            IpAddr::V6(Ipv6Addr::new(
+                segments[0],
+                segments[1],
+                segments[2],
+                segments[3],
+                0,
+                0,
+                0,
+                0,
+            ))
+        }
+    }
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time before 1970")
+        .as_secs()
+}
@@ -1,6 +1,73 @@
 use bitcoinsuite_chronik_client::ScriptType;
 use bitcoinsuite_core::{AddressType, CashAddress, Hashed, Op, Script, ShaRmd160};
 use bitcoinsuite_error::Result;
+use chrono::{TimeZone, Utc};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
+use eyre::bail;
+
+use crate::{config::Network, server_error::BadRequest};
+
+/// Outputs below this value aren't relayed by nodes
+pub const DUST_LIMIT_SATS: i64 = 546;
+/// Lowest fee rate nodes relay by default, in sats per kB
+pub const MIN_RELAY_FEE_RATE: i64 = 1000;
+/// Largest OP_RETURN output script nodes relay by default
+pub const MAX_OP_RETURN_SIZE: usize = 223;
+/// Number of confirmations a coinbase output needs before it can be spent
+pub const COINBASE_MATURITY: i32 = 100;
+/// Expected number of blocks mined per day, at 10 minutes per block
+pub const BLOCKS_PER_DAY: i32 = 144;
+/// Upgrades changing the shares of the block reward the coinbase must pay,
+/// by activation time: Axion added the miner fund, Gluon raised it to 32% and
+/// Cowperthwaite added staking rewards.
+const COINBASE_SHARE_ERAS: [(i64, CoinbaseShares); 3] = [
+    (
+        1_605_441_600,
+        CoinbaseShares {
+            miner_fund_percent: 8,
+            staking_reward_percent: 0,
+        },
+    ),
+    (
+        1_652_616_000,
+        CoinbaseShares {
+            miner_fund_percent: 32,
+            staking_reward_percent: 0,
+        },
+    ),
+    (
+        1_700_049_600,
+        CoinbaseShares {
+            miner_fund_percent: 32,
+            staking_reward_percent: 10,
+        },
+    ),
+];
+
+/// Shares of the block reward the coinbase must pay to the miner fund and to
+/// the staking reward winner, 0 if there's no such output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CoinbaseShares {
+    pub miner_fund_percent: i64,
+    pub staking_reward_percent: i64,
+}
+
+/// Shares in effect for a block. Upgrades activate once the median time past
+/// reaches their activation time, which is the same on mainnet and testnet;
+/// this goes by the block's own timestamp, so the few blocks around an
+/// activation can be off. Regtest nodes only enforce the shares when enabled
+/// on the command line, so none are assumed there.
+pub fn coinbase_shares(network: Network, block_timestamp: i64) -> CoinbaseShares {
+    if network == Network::Regtest {
+        return CoinbaseShares::default();
+    }
+    COINBASE_SHARE_ERAS
+        .iter()
+        .rev()
+        .find(|(activation_time, _)| block_timestamp >= *activation_time)
+        .map(|(_, shares)| *shares)
+        .unwrap_or_default()
+}
 
 pub fn to_be_hex(slice: &[u8]) -> String {
     let mut vec = slice.to_vec();
@@ -14,6 +81,12 @@ pub fn from_be_hex(string: &str) -> Result<Vec<u8>> {
     Ok(decoded)
 }
 
+/// Reverses the byte order of a hex encoded hash, e.g. to turn a
+/// little-endian hash emitted by some tooling into the canonical form.
+pub fn reverse_hex(string: &str) -> Result<String> {
+    Ok(hex::encode(from_be_hex(string)?))
+}
+
 #[derive(Clone, Debug)]
 pub enum Destination<'a> {
     Nulldata(Vec<Op>),
@@ -22,6 +95,50 @@ pub enum Destination<'a> {
     Unknown(Vec<u8>),
 }
 
+/// Where a token's mint baton ended up, followed from a genesis or mint tx
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MintBaton {
+    /// Unspent at the output, which can mint more tokens
+    Active {
+        tx_hash: String,
+        out_idx: u32,
+        address: Option<String>,
+    },
+    /// Not passed on by the tx, so no more tokens can be minted
+    Destroyed { tx_hash: String },
+    /// Still passed on after too many mints to follow, last seen at the output
+    Untracked { tx_hash: String, out_idx: u32 },
+}
+
+/// Unconfirmed txs a mempool tx depends on or that depend on it. Miners
+/// can't include a tx before its ancestors, so a tx with a low fee parent may
+/// wait even if its own fee is high.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolChain {
+    /// Unconfirmed txs spent by the tx
+    pub parent_tx_hashes: Vec<String>,
+    pub ancestors: MempoolTxSet,
+    pub descendants: MempoolTxSet,
+    /// Set if the walk stopped before reaching the end of the chain or a tx
+    /// of the chain couldn't be looked up
+    pub is_truncated: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolTxSet {
+    pub num_txs: u32,
+    pub size: u64,
+    pub fee_sats: i64,
+}
+
+impl MempoolTxSet {
+    pub fn add(&mut self, size: u32, fee_sats: i64) {
+        self.num_txs += 1;
+        self.size += u64::from(size);
+        self.fee_sats += fee_sats;
+    }
+}
+
 pub fn destination_from_script<'a>(prefix: &'a str, script: &[u8]) -> Destination<'a> {
     const OP_RETURN: u8 = 106;
     const OP_DUP: u8 = 118;
@@ -54,7 +171,60 @@ pub fn destination_from_script<'a>(prefix: &'a str, script: &[u8]) -> Destinatio
     }
 }
 
-pub fn to_legacy_address(cash_address: &CashAddress) -> String {
+/// Script type and payload Chronik indexes a locking script under. Scripts
+/// of no standard type are indexed under their full bytes.
+pub fn script_type_payload(script: &[u8]) -> (ScriptType, Vec<u8>) {
+    // The prefix doesn't matter for the payload
+    match destination_from_script("ecash", script) {
+        Destination::Address(address) => {
+            let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+            (script_type, script_payload.to_vec())
+        }
+        Destination::P2PK(pubkey) => (ScriptType::P2pk, pubkey),
+        Destination::Nulldata(_) | Destination::Unknown(_) => (ScriptType::Other, script.to_vec()),
+    }
+}
+
+/// P2PKH address of `pubkey`. Wallets show this address for a key, even if
+/// coins were sent to the bare pubkey (P2PK).
+pub fn pubkey_address<'a>(prefix: &'a str, pubkey: &[u8]) -> CashAddress<'a> {
+    use bitcoin::hashes::{hash160, Hash};
+    let hash = hash160::Hash::hash(pubkey);
+    CashAddress::from_hash(
+        prefix,
+        AddressType::P2PKH,
+        ShaRmd160::from_slice(&hash[..]).expect("Impossible"),
+    )
+}
+
+/// Pubkey revealed by an input script spending a P2PKH output, which pushes
+/// the signature and then the pubkey
+pub fn input_script_pubkey(input_script: &[u8]) -> Option<&[u8]> {
+    [33, 65].into_iter().find_map(|pubkey_len| {
+        let push_idx = input_script.len().checked_sub(pubkey_len + 1)?;
+        let pubkey = &input_script[push_idx + 1..];
+        if usize::from(input_script[push_idx]) == pubkey_len
+            && matches!((pubkey_len, pubkey[0]), (33, 2 | 3) | (65, 4))
+        {
+            Some(pubkey)
+        } else {
+            None
+        }
+    })
+}
+
+/// Bare pubkey locking script, `<pubkey> OP_CHECKSIG`
+pub fn p2pk_script(pubkey: &[u8]) -> Vec<u8> {
+    const OP_CHECKSIG: u8 = 172;
+    let mut script = Vec::with_capacity(pubkey.len() + 2);
+    script.push(pubkey.len() as u8);
+    script.extend_from_slice(pubkey);
+    script.push(OP_CHECKSIG);
+    script
+}
+
+/// Legacy (base58) form of the address, with the version bytes of `network`
+pub fn to_legacy_address(cash_address: &CashAddress, network: Network) -> String {
     use bitcoin::{
         hashes::{hash160, Hash},
         PubkeyHash, ScriptHash,
@@ -64,11 +234,128 @@ pub fn to_legacy_address(cash_address: &CashAddress) -> String {
         AddressType::P2PKH => bitcoin::Script::new_p2pkh(&PubkeyHash::from_hash(hash)),
         AddressType::P2SH => bitcoin::Script::new_p2sh(&ScriptHash::from_hash(hash)),
     };
-    let address =
-        bitcoin::Address::from_script(&script, bitcoin::Network::Bitcoin).expect("Invalid address");
+    let legacy_network = match network {
+        Network::Mainnet => bitcoin::Network::Bitcoin,
+        Network::Testnet => bitcoin::Network::Testnet,
+        Network::Regtest => bitcoin::Network::Regtest,
+    };
+    let address = bitcoin::Address::from_script(&script, legacy_network).expect("Invalid address");
     address.to_string()
 }
 
+/// Checks the addresses of a bulk request up front, so the whole request is
+/// rejected with the offending entry instead of failing halfway through.
+pub fn check_bulk_addresses(addresses: &[String], max_addresses: usize) -> Result<()> {
+    if addresses.len() > max_addresses {
+        bail!(BadRequest(format!(
+            "Too many addresses, at most {} are allowed per request",
+            max_addresses
+        )));
+    }
+    for (idx, address) in addresses.iter().enumerate() {
+        if CashAddress::parse_cow(address.as_str().into()).is_err() {
+            bail!(BadRequest(format!(
+                "Invalid address {:?} at index {}",
+                address, idx
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Builds a BIP21-style payment URI, e.g. "ecash:qq...?amount=12.5&label=Shop".
+pub fn payment_uri(
+    address: &CashAddress,
+    amount: Option<&str>,
+    label: Option<&str>,
+) -> Result<String> {
+    let mut params = Vec::new();
+    if let Some(amount) = amount {
+        let sats = match parse_xec_amount(amount) {
+            Some(sats) if sats > 0 => sats,
+            _ => bail!(BadRequest(format!(
+                "Invalid amount {}, expected XEC with at most 2 decimals",
+                amount
+            ))),
+        };
+        params.push(format!("amount={}", format_xec_amount(sats)));
+    }
+    if let Some(label) = label {
+        params.push(format!("label={}", percent_encode(label)));
+    }
+
+    let mut uri = address.as_str().to_string();
+    if !params.is_empty() {
+        uri.push('?');
+        uri.push_str(&params.join("&"));
+    }
+    Ok(uri)
+}
+
+/// Parses a plain decimal XEC amount with at most 2 decimals, e.g. "12.5",
+/// into sats. Signs, exponents and anything else `f64` would accept are
+/// rejected.
+fn parse_xec_amount(amount: &str) -> Option<i64> {
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((_, "")) => return None,
+        Some((whole, fraction)) => (whole, fraction),
+        None => (amount, ""),
+    };
+    let is_digits = |digits: &str| digits.bytes().all(|byte| byte.is_ascii_digit());
+    if whole.is_empty() || fraction.len() > 2 || !is_digits(whole) || !is_digits(fraction) {
+        return None;
+    }
+    let whole_sats = whole.parse::<i64>().ok()?.checked_mul(100)?;
+    let fraction_sats = format!("{:0<2}", fraction).parse::<i64>().ok()?;
+    whole_sats.checked_add(fraction_sats)
+}
+
+/// Formats sats as XEC without trailing zeros, e.g. 1250 as "12.5"
+fn format_xec_amount(sats: i64) -> String {
+    let xec = format!("{}.{:02}", sats / 100, sats % 100);
+    xec.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn percent_encode(string: &str) -> String {
+    let mut encoded = String::with_capacity(string.len());
+    for byte in string.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Computes the merkle branch proving the inclusion of the tx at `tx_idx`.
+/// `txids` are in block order and in internal (little-endian) byte order, as
+/// are the returned hashes.
+pub fn merkle_branch(txids: &[[u8; 32]], mut tx_idx: usize) -> Vec<[u8; 32]> {
+    use bitcoin::hashes::{sha256d, Hash};
+
+    let mut branch = Vec::new();
+    let mut level = txids.to_vec();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            level.push(*level.last().expect("Impossible"));
+        }
+        branch.push(level[tx_idx ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let mut concat = [0; 64];
+                concat[..32].copy_from_slice(&pair[0]);
+                concat[32..].copy_from_slice(&pair[1]);
+                sha256d::Hash::hash(&concat).into_inner()
+            })
+            .collect();
+        tx_idx /= 2;
+    }
+    branch
+}
+
 pub fn calculate_block_difficulty(n_bits: u32) -> f64 {
     let max_target = 0x00ffff as f64 * 2f64.powi(8 * (0x1d - 3));
     let n_size = n_bits >> 24;
@@ -77,6 +364,91 @@ pub fn calculate_block_difficulty(n_bits: u32) -> f64 {
     max_target / (n_word * 2f64.powi(8 * (n_size as i32 - 3)))
 }
 
+/// Lock times below this are block heights, otherwise unix timestamps
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+/// Sequence number that makes an input final, disabling the tx lock time
+const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+/// If set in a sequence number, it doesn't encode a relative lock time
+const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+/// If set, the relative lock time is in units of 512 seconds, else in blocks
+const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_ffff;
+const SEQUENCE_LOCKTIME_GRANULARITY_SECS: u32 = 512;
+
+/// Explains the lock time of a tx, which is only enforced if at least one of
+/// its inputs isn't final.
+pub fn describe_lock_time(lock_time: u32, sequences: &[u32]) -> String {
+    if lock_time == 0 {
+        return "No lock time".to_string();
+    }
+    let lock = describe_lock_target(lock_time);
+    if sequences.iter().all(|&sequence| sequence == SEQUENCE_FINAL) {
+        format!(
+            "Not enforced, all inputs are final (would lock until {})",
+            lock
+        )
+    } else {
+        format!("Not valid before {}", lock)
+    }
+}
+
+/// The block height or time a lock time refers to, e.g. "block 800000".
+pub fn describe_lock_target(lock_time: u32) -> String {
+    if lock_time < LOCKTIME_THRESHOLD {
+        format!("block {}", lock_time)
+    } else {
+        let timestamp = Utc.timestamp(lock_time as i64, 0);
+        format!("{}", timestamp.format("%Y-%m-%d %H:%M:%S UTC"))
+    }
+}
+
+/// Explains the sequence number of an input, i.e. whether it enables the tx
+/// lock time and whether it encodes a relative lock time (BIP68).
+pub fn describe_sequence(sequence: u32, tx_version: i32) -> String {
+    if sequence == SEQUENCE_FINAL {
+        return "Final".to_string();
+    }
+    if tx_version < 2 || sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        if sequence < SEQUENCE_FINAL - 1 {
+            // BIP125 replace-by-fee isn't supported on eCash, but wallets may set it
+            return "Enables the tx lock time, signals replace-by-fee (BIP125)".to_string();
+        }
+        return "Enables the tx lock time".to_string();
+    }
+    let value = sequence & SEQUENCE_LOCKTIME_MASK;
+    if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+        let secs = value * SEQUENCE_LOCKTIME_GRANULARITY_SECS;
+        format!(
+            "Relative lock: spendable {} after the spent output confirmed",
+            HumanTime::from(chrono::Duration::seconds(secs as i64))
+                .to_text_en(Accuracy::Rough, Tense::Present)
+        )
+    } else {
+        format!(
+            "Relative lock: spendable {} block{} after the spent output confirmed",
+            value,
+            if value == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Bytes taken by a CompactSize integer, used to serialize counts and
+/// script lengths in txs
+pub fn compact_size_len(value: u64) -> u32 {
+    match value {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Estimates the network hashrate in hashes per second needed to mine blocks
+/// of the given difficulty every 10 minutes on average.
+pub fn estimate_hashrate(difficulty: f64) -> f64 {
+    difficulty * 0xffffffffu32 as f64 / 600.0
+}
+
 pub fn cash_addr_to_script_type_payload(addr: &CashAddress) -> (ScriptType, [u8; 20]) {
     let script_type = match addr.addr_type() {
         AddressType::P2PKH => ScriptType::P2pkh,
@@ -86,3 +458,82 @@ pub fn cash_addr_to_script_type_payload(addr: &CashAddress) -> (ScriptType, [u8;
 
     (script_type, *script_payload)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::server_error::BadRequest;
+
+    use super::{check_bulk_addresses, format_xec_amount, parse_xec_amount};
+
+    const BURN_ADDRESS: &str = "ecash:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqs7ratqfx";
+    const P2SH_ADDRESS: &str = "ecash:pqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq8m7jvrjm";
+
+    fn bad_request_message(addresses: &[&str], max_addresses: usize) -> String {
+        let addresses = addresses
+            .iter()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>();
+        let err = check_bulk_addresses(&addresses, max_addresses).unwrap_err();
+        err.downcast_ref::<BadRequest>()
+            .expect("Expected a BadRequest")
+            .0
+            .clone()
+    }
+
+    #[test]
+    fn test_check_bulk_addresses() {
+        let addresses = vec![BURN_ADDRESS.to_string(), P2SH_ADDRESS.to_string()];
+        assert!(check_bulk_addresses(&addresses, 2).is_ok());
+        assert!(check_bulk_addresses(&[], 2).is_ok());
+
+        assert_eq!(
+            bad_request_message(
+                &[
+                    BURN_ADDRESS,
+                    "ecash:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqs7ratqfy"
+                ],
+                2
+            ),
+            "Invalid address \"ecash:qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqs7ratqfy\" at index 1",
+        );
+        assert_eq!(
+            bad_request_message(&["not an address", BURN_ADDRESS], 2),
+            "Invalid address \"not an address\" at index 0",
+        );
+        assert_eq!(
+            bad_request_message(&[BURN_ADDRESS, P2SH_ADDRESS, BURN_ADDRESS], 2),
+            "Too many addresses, at most 2 are allowed per request",
+        );
+    }
+
+    #[test]
+    fn test_parse_xec_amount() {
+        assert_eq!(parse_xec_amount("12"), Some(1200));
+        assert_eq!(parse_xec_amount("12.5"), Some(1250));
+        assert_eq!(parse_xec_amount("12.05"), Some(1205));
+        assert_eq!(parse_xec_amount("0.01"), Some(1));
+        assert_eq!(parse_xec_amount("0"), Some(0));
+        assert_eq!(parse_xec_amount("007"), Some(700));
+        assert_eq!(parse_xec_amount("1.234"), None);
+        assert_eq!(parse_xec_amount("1e3"), None);
+        assert_eq!(parse_xec_amount("+1"), None);
+        assert_eq!(parse_xec_amount("-1"), None);
+        assert_eq!(parse_xec_amount("inf"), None);
+        assert_eq!(parse_xec_amount("NaN"), None);
+        assert_eq!(parse_xec_amount("1."), None);
+        assert_eq!(parse_xec_amount(".5"), None);
+        assert_eq!(parse_xec_amount(""), None);
+        assert_eq!(parse_xec_amount(" 1"), None);
+        assert_eq!(parse_xec_amount("1.2.3"), None);
+        assert_eq!(parse_xec_amount("99999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_format_xec_amount() {
+        assert_eq!(format_xec_amount(1200), "12");
+        assert_eq!(format_xec_amount(1250), "12.5");
+        assert_eq!(format_xec_amount(1205), "12.05");
+        assert_eq!(format_xec_amount(1), "0.01");
+        assert_eq!(format_xec_amount(700), "7");
+    }
+}
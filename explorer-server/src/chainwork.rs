@@ -0,0 +1,445 @@
+//! Chain work, the expected number of hashes needed to mine a chain. Nodes
+//! follow the chain with the most work, not the longest one. The work of a
+//! block is derived from its compact target (`bits`); targets are 256-bit
+//! numbers, so the arithmetic is done with a small 256-bit integer type.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use bitcoinsuite_chronik_client::ChronikClient;
+use bitcoinsuite_error::Result;
+use eyre::{bail, WrapErr};
+
+use crate::events::CONFIRMATION_DEPTH;
+
+/// Blocks fetched per Chronik request while building the index
+const SYNC_CHUNK_SIZE: i32 = 500;
+/// Blocks added per poll, so building the index doesn't hold up the poller
+const MAX_SYNC_BLOCKS_PER_POLL: i32 = 20 * SYNC_CHUNK_SIZE;
+
+/// Unsigned 256-bit integer, with the most significant limb first so the
+/// derived ordering is numeric
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct U256([u64; 4]);
+
+impl U256 {
+    pub const ZERO: U256 = U256([0; 4]);
+    pub const ONE: U256 = U256([0, 0, 0, 1]);
+
+    pub fn from_u64(value: u64) -> Self {
+        U256([0, 0, 0, value])
+    }
+
+    /// Decodes a compact target as in a block header's `bits`. Negative and
+    /// overflowing targets, which are invalid, give zero.
+    pub fn from_compact(bits: u32) -> Self {
+        let exponent = bits >> 24;
+        let mantissa = bits & 0x007f_ffff;
+        if bits & 0x0080_0000 != 0 || mantissa == 0 {
+            return U256::ZERO;
+        }
+        if exponent <= 3 {
+            return U256::from_u64(u64::from(mantissa >> (8 * (3 - exponent))));
+        }
+        let shift = 8 * (exponent - 3);
+        // Targets overflow if their highest bit is shifted past 256 bits
+        if shift + (32 - mantissa.leading_zeros()) > 256 {
+            return U256::ZERO;
+        }
+        U256::from_u64(u64::from(mantissa)).shifted_left(shift)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == U256::ZERO
+    }
+
+    pub fn wrapping_add(&self, other: &U256) -> U256 {
+        let mut result = [0; 4];
+        let mut carry = false;
+        for idx in (0..4).rev() {
+            let (sum, carry1) = self.0[idx].overflowing_add(other.0[idx]);
+            let (sum, carry2) = sum.overflowing_add(u64::from(carry));
+            result[idx] = sum;
+            carry = carry1 || carry2;
+        }
+        U256(result)
+    }
+
+    pub fn wrapping_sub(&self, other: &U256) -> U256 {
+        let mut result = [0; 4];
+        let mut borrow = false;
+        for idx in (0..4).rev() {
+            let (diff, borrow1) = self.0[idx].overflowing_sub(other.0[idx]);
+            let (diff, borrow2) = diff.overflowing_sub(u64::from(borrow));
+            result[idx] = diff;
+            borrow = borrow1 || borrow2;
+        }
+        U256(result)
+    }
+
+    pub fn complement(&self) -> U256 {
+        U256(self.0.map(|limb| !limb))
+    }
+
+    /// Shifts by whole limbs, then by the remaining bits, carrying the bits
+    /// shifted out of each limb into the next more significant one
+    pub fn shifted_left(&self, shift: u32) -> U256 {
+        let limb_shift = (shift / 64) as usize;
+        let bit_shift = shift % 64;
+        let mut result = [0; 4];
+        for (idx, limb) in result.iter_mut().enumerate() {
+            let source_idx = idx + limb_shift;
+            if source_idx >= 4 {
+                break;
+            }
+            *limb = self.0[source_idx] << bit_shift;
+            if bit_shift > 0 && source_idx + 1 < 4 {
+                *limb |= self.0[source_idx + 1] >> (64 - bit_shift);
+            }
+        }
+        U256(result)
+    }
+
+    fn bit(&self, bit: u32) -> bool {
+        self.0[3 - (bit / 64) as usize] >> (bit % 64) & 1 == 1
+    }
+
+    fn set_bit(&mut self, bit: u32) {
+        self.0[3 - (bit / 64) as usize] |= 1 << (bit % 64);
+    }
+
+    /// Long division, `None` for a zero divisor
+    pub fn checked_div(&self, divisor: &U256) -> Option<U256> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let mut quotient = U256::ZERO;
+        let mut remainder = U256::ZERO;
+        for bit in (0..256).rev() {
+            remainder = remainder.shifted_left(1);
+            if self.bit(bit) {
+                remainder.0[3] |= 1;
+            }
+            if remainder >= *divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient.set_bit(bit);
+            }
+        }
+        Some(quotient)
+    }
+
+    /// Approximate value, for display
+    pub fn to_f64(&self) -> f64 {
+        self.0
+            .iter()
+            .fold(0.0, |value, &limb| value * 2f64.powi(64) + limb as f64)
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, limb) in bytes.chunks_exact_mut(8).zip(self.0) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0; 4];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        U256(limbs)
+    }
+
+    /// 64 hex digits, as in the node's `chainwork` fields
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|limb| format!("{:016x}", limb)).collect()
+    }
+}
+
+/// Expected number of hashes to mine a block with the compact target `bits`,
+/// 2^256 / (target + 1), computed as ~target / (target + 1) + 1 so it fits in
+/// 256 bits
+pub fn block_work(bits: u32) -> U256 {
+    let target = U256::from_compact(bits);
+    if target.is_zero() {
+        return U256::ZERO;
+    }
+    match target
+        .complement()
+        .checked_div(&target.wrapping_add(&U256::ONE))
+    {
+        Some(work) => work.wrapping_add(&U256::ONE),
+        // The target is all ones, so anything is a valid hash
+        None => U256::ONE,
+    }
+}
+
+/// Cumulative chain work at each height, built from the `bits` of Chronik's
+/// block infos by the block poller, 10,000 blocks per poll. Without a file to
+/// save it to, it's only kept in memory and built again after a restart.
+#[derive(Default)]
+pub struct ChainworkIndex {
+    chainwork: Mutex<Vec<U256>>,
+    file: Option<IndexFile>,
+}
+
+/// The index saved as 32 byte big-endian chain works, one per height
+struct IndexFile {
+    path: PathBuf,
+    /// Number of chain works at the start of the file that are still valid.
+    /// Works after them are cut off on the next save.
+    num_saved: Mutex<usize>,
+}
+
+impl ChainworkIndex {
+    /// Loads the index saved at `path`, which new blocks are then appended to.
+    /// The last blocks of the file may have been reorged while the explorer
+    /// was down, so they're indexed again.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(ChainworkIndex::default()),
+        };
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| {
+                    format!("Failed to read chain work index {}", path.display())
+                })
+            }
+        };
+        let mut chainwork = bytes
+            .chunks_exact(32)
+            .map(|chunk| U256::from_be_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        chainwork.truncate(chainwork.len().saturating_sub(CONFIRMATION_DEPTH as usize));
+        Ok(ChainworkIndex {
+            file: Some(IndexFile {
+                path: path.to_path_buf(),
+                num_saved: Mutex::new(chainwork.len()),
+            }),
+            chainwork: Mutex::new(chainwork),
+        })
+    }
+
+    /// Chain work up to and including the block at `height`, if indexed yet
+    pub fn get(&self, height: i32) -> Option<U256> {
+        let chainwork = self.chainwork.lock().unwrap();
+        chainwork.get(usize::try_from(height).ok()?).copied()
+    }
+
+    /// Drops the blocks from `height` on, e.g. because they were reorged
+    pub fn truncate(&self, height: i32) {
+        let height = usize::try_from(height).unwrap_or(0);
+        self.chainwork.lock().unwrap().truncate(height);
+        if let Some(file) = &self.file {
+            let mut num_saved = file.num_saved.lock().unwrap();
+            *num_saved = (*num_saved).min(height);
+        }
+    }
+
+    /// Indexes the blocks after the last indexed one, up to `tip_height`
+    pub async fn sync(&self, chronik: &ChronikClient, tip_height: i32) -> Result<()> {
+        let num_indexed = self.chainwork.lock().unwrap().len() as i32;
+        let end_height = tip_height.min(num_indexed + MAX_SYNC_BLOCKS_PER_POLL - 1);
+        let mut start_height = num_indexed;
+        while start_height <= end_height {
+            let chunk_end = (start_height + SYNC_CHUNK_SIZE - 1).min(end_height);
+            let blocks = chronik.blocks(start_height, chunk_end).await?;
+            // A short or shifted response would put works at the wrong heights
+            let is_expected_range = blocks.len() as i32 == chunk_end - start_height + 1
+                && blocks
+                    .iter()
+                    .zip(start_height..)
+                    .all(|(block, height)| block.height == height);
+            if !is_expected_range {
+                bail!(
+                    "Chronik returned {} blocks for heights {} to {}",
+                    blocks.len(),
+                    start_height,
+                    chunk_end
+                );
+            }
+            // Computed before taking the lock, which the API handlers share
+            let works = blocks
+                .iter()
+                .map(|block| block_work(block.n_bits))
+                .collect::<Vec<_>>();
+
+            {
+                let mut chainwork = self.chainwork.lock().unwrap();
+                // Truncated by a reorg in the meantime, start over at the next poll
+                if chainwork.len() as i32 != start_height {
+                    return Ok(());
+                }
+                for work in &works {
+                    let previous = chainwork.last().copied().unwrap_or_default();
+                    chainwork.push(previous.wrapping_add(work));
+                }
+            }
+            start_height = chunk_end + 1;
+        }
+        self.save()
+    }
+
+    /// Appends the blocks indexed since the last save to the file, after
+    /// cutting off works that are no longer valid, e.g. after a reorg
+    fn save(&self) -> Result<()> {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let mut num_saved = file.num_saved.lock().unwrap();
+        let new_works = {
+            let chainwork = self.chainwork.lock().unwrap();
+            *num_saved = (*num_saved).min(chainwork.len());
+            chainwork[*num_saved..].to_vec()
+        };
+        if new_works.is_empty() {
+            return Ok(());
+        }
+
+        let mut index_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            // Cut to the valid works below instead
+            .truncate(false)
+            .open(&file.path)
+            .wrap_err_with(|| format!("Failed to open {}", file.path.display()))?;
+        index_file.set_len(*num_saved as u64 * 32)?;
+        index_file.seek(SeekFrom::End(0))?;
+        let bytes = new_works
+            .iter()
+            .flat_map(|work| work.to_be_bytes())
+            .collect::<Vec<_>>();
+        index_file.write_all(&bytes)?;
+        *num_saved += new_works.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{block_work, ChainworkIndex, CONFIRMATION_DEPTH, U256};
+
+    #[test]
+    fn test_from_compact_small_exponent() {
+        assert_eq!(U256::from_compact(0x0312_3456), U256::from_u64(0x12_3456));
+        assert_eq!(U256::from_compact(0x0212_3456), U256::from_u64(0x1234));
+        assert_eq!(U256::from_compact(0x0112_3456), U256::from_u64(0x12));
+        assert_eq!(U256::from_compact(0x0012_3456), U256::ZERO);
+    }
+
+    #[test]
+    fn test_from_compact_sign_bit() {
+        assert_eq!(U256::from_compact(0x0492_3456), U256::ZERO);
+        assert_eq!(U256::from_compact(0x1d80_ffff), U256::ZERO);
+        assert_eq!(U256::from_compact(0x1d00_0000), U256::ZERO);
+    }
+
+    #[test]
+    fn test_from_compact_overflow() {
+        // Small mantissas can be shifted further than large ones
+        assert_eq!(U256::from_compact(0x2100_0001), U256([1 << 48, 0, 0, 0]));
+        assert_eq!(U256::from_compact(0x2200_00ff), U256([0xff << 56, 0, 0, 0]));
+        assert_eq!(U256::from_compact(0x2200_0100), U256::ZERO);
+        assert_eq!(U256::from_compact(0x2201_0000), U256::ZERO);
+        assert_eq!(U256::from_compact(0xff12_3456), U256::ZERO);
+    }
+
+    #[test]
+    fn test_from_compact_genesis() {
+        assert_eq!(
+            U256::from_compact(0x1d00_ffff).to_hex(),
+            format!("00000000ffff{}", "0".repeat(52)),
+        );
+    }
+
+    #[test]
+    fn test_shifted_left() {
+        let value = U256::from_u64(u64::MAX);
+        assert_eq!(value.shifted_left(0), value);
+        assert_eq!(value.shifted_left(4), U256([0, 0, 0xf, u64::MAX << 4]));
+        assert_eq!(value.shifted_left(64), U256([0, 0, u64::MAX, 0]));
+        assert_eq!(value.shifted_left(200), U256([u64::MAX << 8, 0, 0, 0]));
+        assert_eq!(value.shifted_left(256), U256::ZERO);
+    }
+
+    #[test]
+    fn test_block_work() {
+        assert_eq!(block_work(0x1d00_ffff), U256::from_u64(0x1_0001_0001));
+        // The lowest possible target, 1, takes 2^255 hashes on average
+        assert_eq!(block_work(0x0300_0001), U256([1 << 63, 0, 0, 0]));
+        assert_eq!(block_work(0x1d80_ffff), U256::ZERO);
+    }
+
+    #[test]
+    fn test_checked_div() {
+        assert_eq!(U256::from_u64(100).checked_div(&U256::ZERO), None);
+        assert_eq!(
+            U256::from_u64(100).checked_div(&U256::from_u64(7)),
+            Some(U256::from_u64(14)),
+        );
+        assert_eq!(
+            U256::ONE
+                .shifted_left(200)
+                .checked_div(&U256::ONE.shifted_left(100)),
+            Some(U256::ONE.shifted_left(100)),
+        );
+        assert_eq!(
+            U256::from_u64(3).checked_div(&U256::from_u64(5)),
+            Some(U256::ZERO),
+        );
+    }
+
+    #[test]
+    fn test_be_bytes() {
+        let value = U256([1, 2, 3, u64::MAX]);
+        let bytes = value.to_be_bytes();
+        assert_eq!(bytes[7], 1);
+        assert_eq!(bytes[24..], [0xff; 8]);
+        assert_eq!(U256::from_be_bytes(&bytes), value);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("chainwork-test-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let index = ChainworkIndex::load(Some(&path)).unwrap();
+        assert_eq!(index.get(0), None);
+        index
+            .chainwork
+            .lock()
+            .unwrap()
+            .extend((1..=30).map(U256::from_u64));
+        index.save().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 30 * 32);
+
+        // A reorg replaces the blocks from height 25 on
+        index.truncate(25);
+        index
+            .chainwork
+            .lock()
+            .unwrap()
+            .extend((100..103).map(U256::from_u64));
+        index.save().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 28 * 32);
+
+        // The last blocks are dropped on load, as they may have been reorged
+        let index = ChainworkIndex::load(Some(&path)).unwrap();
+        let num_loaded = 28 - CONFIRMATION_DEPTH;
+        assert_eq!(
+            index.get(num_loaded - 1),
+            Some(U256::from_u64(num_loaded as u64))
+        );
+        assert_eq!(index.get(num_loaded), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+//! Decoding of the coinbase input script. Since BIP34 it starts with the
+//! block height; the rest is up to the miner and usually holds an extra nonce
+//! and the pool's name.
+
+use crate::script_analysis::{
+    decode_script_num, parse_script_ops, small_int, Instruction, OP_PUSHDATA1, OP_PUSHDATA2,
+    OP_PUSHDATA4,
+};
+
+/// Miner tags are runs of at least this many printable characters
+const MIN_TAG_LENGTH: usize = 4;
+/// Extra nonces are pushed as short binary blobs
+const EXTRA_NONCE_SIZES: std::ops::RangeInclusive<usize> = 4..=16;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoinbaseData {
+    /// Height pushed as the first item, as required by BIP34
+    pub height: Option<i64>,
+    pub extra_nonce: Option<Vec<u8>>,
+    /// Printable text left by the miner, e.g. the pool's name
+    pub miner_tag: Option<String>,
+}
+
+/// Decodes the coinbase script of the block at `block_height`. Blocks before
+/// BIP34 don't start with their height, so the first item only counts as the
+/// height if it matches.
+pub fn decode_coinbase_script(script: &[u8], block_height: i32) -> CoinbaseData {
+    let (items, unparsed) = parse_items(script);
+    let (height, items) = match items.split_first() {
+        Some((first, rest)) if item_num(first) == Some(i64::from(block_height)) => {
+            (Some(i64::from(block_height)), rest)
+        }
+        _ => (None, items.as_slice()),
+    };
+    let pushes = items
+        .iter()
+        .filter_map(|item| match item {
+            Instruction::Push(data) => Some(*data),
+            Instruction::Op(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    // Everything after the height is free-form
+    let extra_nonce = pushes
+        .iter()
+        .find(|data| EXTRA_NONCE_SIZES.contains(&data.len()) && !is_mostly_printable(data))
+        .map(|data| data.to_vec());
+
+    let tags = pushes
+        .iter()
+        .chain(std::iter::once(&unparsed))
+        .flat_map(|data| printable_runs(data))
+        .collect::<Vec<_>>();
+    let miner_tag = if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join(" "))
+    };
+
+    CoinbaseData {
+        height,
+        extra_nonce,
+        miner_tag,
+    }
+}
+
+/// Pushes and small numbers at the start of the script, and the bytes after
+/// them. Miners don't have to put valid script after the height, so parsing
+/// stops at the first other opcode or at a push running past the end.
+fn parse_items(script: &[u8]) -> (Vec<Instruction<'_>>, &[u8]) {
+    let (ops, _) = parse_script_ops(script);
+    let mut items = Vec::new();
+    let mut idx = 0;
+    for (opcode, instruction) in ops {
+        if let Instruction::Op(opcode) = instruction {
+            if small_int(opcode).is_none() {
+                break;
+            }
+        }
+        idx += encoded_len(opcode, &instruction);
+        items.push(instruction);
+    }
+    (items, &script[idx..])
+}
+
+/// Number of script bytes the instruction was parsed from
+fn encoded_len(opcode: u8, instruction: &Instruction) -> usize {
+    match instruction {
+        Instruction::Op(_) => 1,
+        Instruction::Push(data) => {
+            let len_size = match opcode {
+                OP_PUSHDATA1 => 1,
+                OP_PUSHDATA2 => 2,
+                OP_PUSHDATA4 => 4,
+                _ => 0,
+            };
+            1 + len_size + data.len()
+        }
+    }
+}
+
+/// Number pushed by a push or a small number opcode, as used for the height
+fn item_num(item: &Instruction) -> Option<i64> {
+    match *item {
+        Instruction::Push(data) => decode_script_num(data),
+        Instruction::Op(opcode) => small_int(opcode).map(i64::from),
+    }
+}
+
+fn is_printable(byte: u8) -> bool {
+    (0x20..0x7f).contains(&byte)
+}
+
+fn is_mostly_printable(data: &[u8]) -> bool {
+    data.iter().filter(|&&byte| is_printable(byte)).count() * 4 >= data.len() * 3
+}
+
+/// Runs of printable characters long enough to be text
+fn printable_runs(data: &[u8]) -> Vec<String> {
+    data.split(|&byte| !is_printable(byte))
+        .filter(|run| run.len() >= MIN_TAG_LENGTH)
+        .map(|run| String::from_utf8_lossy(run).trim().to_string())
+        .filter(|run| !run.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_coinbase_script, CoinbaseData};
+
+    #[test]
+    fn test_genesis_coinbase() {
+        let script = hex::decode(
+            "04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f\
+             6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73",
+        )
+        .unwrap();
+        assert_eq!(
+            decode_coinbase_script(&script, 0),
+            CoinbaseData {
+                height: None,
+                extra_nonce: Some(vec![0xff, 0xff, 0x00, 0x1d]),
+                miner_tag: Some(
+                    "The Times 03/Jan/2009 Chancellor on brink of second bailout for banks"
+                        .to_string()
+                ),
+            },
+        );
+    }
+
+    #[test]
+    fn test_pre_bip34_coinbase() {
+        // Block 1, whose first push is the difficulty bits, not the height
+        let script = hex::decode("04ffff001d0104").unwrap();
+        assert_eq!(
+            decode_coinbase_script(&script, 1),
+            CoinbaseData {
+                height: None,
+                extra_nonce: Some(vec![0xff, 0xff, 0x00, 0x1d]),
+                miner_tag: None,
+            },
+        );
+    }
+
+    #[test]
+    fn test_bip34_coinbase() {
+        // Height 800000, an 8 byte extra nonce and a pool tag
+        let script = hex::decode(
+            "0300350c08a1b2c3d4e5f60718152f5669614254432f4d696e656420627920616263\
+             2f",
+        )
+        .unwrap();
+        assert_eq!(
+            decode_coinbase_script(&script, 800_000),
+            CoinbaseData {
+                height: Some(800_000),
+                extra_nonce: Some(hex::decode("a1b2c3d4e5f60718").unwrap()),
+                miner_tag: Some("/ViaBTC/Mined by abc/".to_string()),
+            },
+        );
+        // The same script in another block has no height
+        assert_eq!(decode_coinbase_script(&script, 800_001).height, None);
+    }
+
+    #[test]
+    fn test_small_number_height() {
+        // OP_5, as the height of block 5 would be pushed
+        assert_eq!(decode_coinbase_script(&[0x55], 5).height, Some(5));
+    }
+
+    #[test]
+    fn test_text_after_non_push() {
+        // OP_CHECKSIG stops parsing, but text after it is still found
+        let mut script = hex::decode("0300350cac").unwrap();
+        script.extend_from_slice(b"pool.example");
+        assert_eq!(
+            decode_coinbase_script(&script, 800_000),
+            CoinbaseData {
+                height: Some(800_000),
+                extra_nonce: None,
+                miner_tag: Some("pool.example".to_string()),
+            },
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use axum::http::{header, Extensions, HeaderMap, StatusCode, Version};
+use tower_http::compression::{
+    predicate::{Predicate, SizeAbove},
+    CompressionLayer,
+};
+
+use crate::config::CompressionConfig;
+
+/// Builds a layer compressing responses with gzip or brotli, depending on the
+/// client's `Accept-Encoding`. Only responses of one of the configured content
+/// types and at least `min_size` bytes are compressed, so e.g. images and
+/// streamed CSV downloads pass through unchanged.
+pub fn compression_layer(config: &CompressionConfig) -> CompressionLayer<impl Predicate> {
+    let content_types = config.content_types.clone();
+    let has_content_type = move |_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions| {
+        let content_type = match headers.get(header::CONTENT_TYPE) {
+            Some(content_type) => content_type.to_str().unwrap_or_default(),
+            None => return false,
+        };
+        content_types
+            .iter()
+            .any(|compressed_type| content_type.starts_with(compressed_type.as_str()))
+    };
+
+    CompressionLayer::new().compress_when(SizeAbove::new(config.min_size).and(has_content_type))
+}
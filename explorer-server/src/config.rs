@@ -1,16 +1,700 @@
 use std::{net::SocketAddr, path::PathBuf};
 
+use axum::http::HeaderName;
 use bitcoinsuite_error::Result;
+use eyre::{bail, eyre, WrapErr};
 use serde::Deserialize;
 
+use crate::api_keys::QuotaConfig;
+
+/// Environment variables overriding entries of the config file
+const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("EXPLORER_HOST", "host"),
+    ("EXPLORER_CHRONIK_URL", "chronik_api_url"),
+    ("EXPLORER_BASE_DIR", "base_dir"),
+    ("EXPLORER_UPSTREAM_PROXY", "upstream_proxy"),
+    ("EXPLORER_LABELS_PATH", "labels_path"),
+    ("EXPLORER_ADMIN_TOKEN", "admin_token"),
+    ("EXPLORER_API_KEYS_PATH", "api_keys_path"),
+    ("EXPLORER_PUBLIC_URL", "public_url"),
+    ("EXPLORER_NETWORK", "network"),
+    ("EXPLORER_PRICE_HISTORY_PATH", "price_history_path"),
+    ("EXPLORER_CHAINWORK_PATH", "chainwork_path"),
+];
+
+/// Prefix of environment variables overriding nested entries, whose key parts
+/// are separated by `__`, e.g. `EXPLORER_LIMITS__MAX_PAGE_SIZE` for
+/// `limits.max_page_size`
+const NESTED_ENV_PREFIX: &str = "EXPLORER_";
+const NESTED_ENV_SEPARATOR: &str = "__";
+
 #[derive(Deserialize)]
 pub struct Config {
     pub host: SocketAddr,
     pub chronik_api_url: String,
+    /// Network the Chronik instance indexes, checked against its genesis block
+    #[serde(default)]
+    pub network: Network,
     pub base_dir: Option<PathBuf>,
+    /// URL the explorer is publicly reachable at, e.g. "https://explorer.e.cash".
+    /// Used for sitemaps and structured data, which need absolute URLs
+    pub public_url: Option<String>,
+    /// SOCKS5 proxy used to reach Chronik, e.g. "socks5h://127.0.0.1:9050" for Tor
+    pub upstream_proxy: Option<String>,
+    /// TOML file mapping addresses to labels shown in the explorer
+    pub labels_path: Option<PathBuf>,
+    /// Bearer token for the admin API. The admin API is disabled if unset
+    pub admin_token: Option<String>,
+    /// TOML file with the API keys and their quotas
+    pub api_keys_path: Option<PathBuf>,
+    /// CSV file of daily XEC/USD closing prices, for the value of txs at the time
+    pub price_history_path: Option<PathBuf>,
+    /// File the chain work index is saved to, so it isn't rebuilt from
+    /// genesis after a restart
+    pub chainwork_path: Option<PathBuf>,
+    #[serde(default)]
+    pub api_quota: ApiQuotaConfig,
+    /// Quota of API clients without a key, per IP address
+    #[serde(default)]
+    pub anonymous_quota: QuotaConfig,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub token_doc_check: TokenDocCheckConfig,
+    #[serde(default)]
+    pub token_icons: TokenIconsConfig,
+    /// Webhooks notified of activity on watched addresses
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Network upgrades shown in a countdown banner
+    #[serde(default)]
+    pub upgrades: Vec<UpgradeConfig>,
+    #[serde(default)]
+    pub xpub: XpubConfig,
+    /// Shown at the top of the homepage, e.g. to announce planned downtime
+    pub announcement: Option<String>,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    Regtest,
+}
+
+impl Network {
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "Mainnet",
+            Network::Testnet => "Testnet",
+            Network::Regtest => "Regtest",
+        }
+    }
+
+    pub fn is_mainnet(self) -> bool {
+        self == Network::Mainnet
+    }
+
+    /// CashAddress prefix of XEC addresses
+    pub fn addr_prefix(self) -> &'static str {
+        match self {
+            Network::Mainnet => "ecash",
+            Network::Testnet => "ectest",
+            Network::Regtest => "ecregtest",
+        }
+    }
+
+    /// CashAddress prefix of token addresses
+    pub fn token_addr_prefix(self) -> &'static str {
+        match self {
+            Network::Mainnet => "etoken",
+            Network::Testnet => "etokentest",
+            Network::Regtest => "etokenregtest",
+        }
+    }
+
+    /// Hash of the genesis block, big-endian hex
+    pub fn genesis_hash(self) -> &'static str {
+        match self {
+            Network::Mainnet => "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            Network::Testnet => "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943",
+            Network::Regtest => "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Maximum `take` of the paginated API endpoints
+    pub max_page_size: usize,
+    /// Maximum number of blocks requested from /api/blocks at once
+    pub max_block_range: i32,
+    /// Requests taking longer than this are aborted with status 408
+    pub request_timeout_secs: u64,
+    /// Blocks larger than this many bytes can't be downloaded raw
+    pub max_raw_block_size: u64,
+    /// Maximum number of blocks exported by /api/export/blocks.ndjson at once
+    pub max_export_range: i32,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            max_page_size: 1000,
+            max_block_range: 1000,
+            request_timeout_secs: 30,
+            max_raw_block_size: 8_000_000,
+            max_export_range: 50_000,
+        }
+    }
+}
+
+/// Verifying token documents against their hash means fetching arbitrary
+/// URLs from token genesis txs, so it's off by default.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TokenDocCheckConfig {
+    pub enabled: bool,
+    pub timeout_secs: u64,
+    /// Documents larger than this many bytes are not checked
+    pub max_size: usize,
+    /// How long check results are cached before the document is fetched again
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for TokenDocCheckConfig {
+    fn default() -> Self {
+        TokenDocCheckConfig {
+            enabled: false,
+            timeout_secs: 10,
+            max_size: 10 * 1024 * 1024,
+            cache_ttl_secs: 24 * 3600,
+        }
+    }
+}
+
+/// Sources of token icons, looked up in order: `local_dir`, then
+/// `url_template`. Tokens without an icon get an identicon.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TokenIconsConfig {
+    /// Directory with icons named by token id, e.g. "<token_id>.png"
+    pub local_dir: Option<PathBuf>,
+    /// URL of icons with `{token_id}` in place of the token id, e.g.
+    /// "https://icons.example.com/{token_id}.png"
+    pub url_template: Option<String>,
+    pub timeout_secs: u64,
+    /// Icons larger than this many bytes are not shown
+    pub max_size: usize,
+    /// How long icons are cached before they're looked up again
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for TokenIconsConfig {
+    fn default() -> Self {
+        TokenIconsConfig {
+            local_dir: None,
+            url_template: None,
+            timeout_secs: 5,
+            max_size: 256 * 1024,
+            cache_ttl_secs: 3600,
+        }
+    }
+}
+
+/// In maintenance mode, every page and API endpoint responds with 503 and a
+/// maintenance page, except /healthz and the static files
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    pub enabled: bool,
+    pub message: String,
+    /// Sent in the `Retry-After` header
+    pub retry_after_secs: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        MaintenanceConfig {
+            enabled: false,
+            message: "The explorer is down for maintenance and will be back shortly.".to_string(),
+            retry_after_secs: 300,
+        }
+    }
+}
+
+/// Quotas of the /api routes are off by default, as the explorer's own pages
+/// call the API too.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ApiQuotaConfig {
+    pub enabled: bool,
+    /// Header a reverse proxy in front of the explorer sets to the client's
+    /// IP address, e.g. "X-Forwarded-For". Otherwise clients are told apart
+    /// by the address they connect from, which would be the proxy's.
+    pub client_ip_header: Option<String>,
+}
+
+/// The /xpub page derives and looks up many addresses per request, so it's
+/// off by default.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct XpubConfig {
+    pub enabled: bool,
+    /// Scanning a chain stops after this many unused addresses in a row
+    pub gap_limit: u32,
+    /// Addresses derived per chain at most, whether used or not
+    pub max_addresses: u32,
+    /// How long wallets are cached before they're scanned again
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for XpubConfig {
+    fn default() -> Self {
+        XpubConfig {
+            enabled: false,
+            gap_limit: 20,
+            max_addresses: 200,
+            cache_ttl_secs: 600,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct WebhookConfig {
+    /// Watched address
+    pub address: String,
+    /// URL the notifications are POSTed to
+    pub url: String,
+    /// If set, the webhook is notified when the balance of the address
+    /// crosses this threshold instead of on every tx
+    #[serde(default)]
+    pub balance_threshold_sats: Option<i64>,
+}
+
+/// Scheduled network upgrade, activated either at a median time past or at a
+/// block height
+#[derive(Deserialize, Clone)]
+pub struct UpgradeConfig {
+    pub name: String,
+    /// Unix timestamp the median time past of the chain has to reach
+    #[serde(default)]
+    pub activation_time: Option<i64>,
+    #[serde(default)]
+    pub activation_height: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    /// Responses smaller than this many bytes are sent uncompressed
+    pub min_size: u16,
+    /// Content types that are compressed, matched by prefix
+    pub content_types: Vec<String>,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig {
+            enabled: true,
+            min_size: 1024,
+            content_types: vec![
+                "text/html".to_string(),
+                "application/json".to_string(),
+                "text/css".to_string(),
+                "application/javascript".to_string(),
+            ],
+        }
+    }
 }
 
+/// Parses the config file contents, applies the `EXPLORER_*` environment
+/// variable overrides and validates the result.
 pub fn load_config(config_string: &str) -> Result<Config> {
-    let config: Config = toml::from_str(config_string).unwrap();
+    // Unlike env::vars, this doesn't panic on variables that aren't UTF-8
+    let env_vars = std::env::vars_os().filter_map(|(env_var, value)| {
+        Some((env_var.into_string().ok()?, value.into_string().ok()?))
+    });
+    load_config_with_env(config_string, env_vars)
+}
+
+fn load_config_with_env(
+    config_string: &str,
+    env_vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Config> {
+    let mut table: toml::value::Table =
+        toml::from_str(config_string).wrap_err("Config file is not valid TOML")?;
+
+    for (env_var, value) in env_vars {
+        if let Some((_, key)) = ENV_OVERRIDES.iter().find(|(name, _)| *name == env_var) {
+            table.insert(key.to_string(), toml::Value::String(value));
+        } else if let Some(key_parts) = nested_env_key(&env_var) {
+            set_nested_entry(&mut table, &key_parts, parse_env_value(value))
+                .wrap_err_with(|| format!("Invalid environment variable {}", env_var))?;
+        }
+    }
+
+    let config: Config = toml::Value::Table(table).try_into().map_err(|err| {
+        eyre!(
+            "Invalid config: {}. Entries can be set in the config file or overridden with \
+             environment variables ({})",
+            err,
+            ENV_OVERRIDES
+                .iter()
+                .map(|(env_var, _)| *env_var)
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    })?;
+    config.validate()?;
+
     Ok(config)
 }
+
+/// Key parts of an environment variable overriding a nested entry, e.g.
+/// `["limits", "max_page_size"]` for `EXPLORER_LIMITS__MAX_PAGE_SIZE`
+fn nested_env_key(env_var: &str) -> Option<Vec<String>> {
+    let key = env_var.strip_prefix(NESTED_ENV_PREFIX)?;
+    if !key.contains(NESTED_ENV_SEPARATOR) {
+        return None;
+    }
+    key.split(NESTED_ENV_SEPARATOR)
+        .map(|part| (!part.is_empty()).then(|| part.to_ascii_lowercase()))
+        .collect()
+}
+
+/// Values of nested entries are parsed as TOML, so numbers and booleans can be
+/// set. Anything that isn't valid TOML is taken as a string.
+fn parse_env_value(value: String) -> toml::Value {
+    match toml::from_str::<toml::value::Table>(&format!("value = {}", value)) {
+        Ok(mut table) => table.remove("value").expect("Impossible"),
+        Err(_) => toml::Value::String(value),
+    }
+}
+
+fn set_nested_entry(
+    table: &mut toml::value::Table,
+    key_parts: &[String],
+    value: toml::Value,
+) -> Result<()> {
+    let (key, parent_parts) = key_parts.split_last().expect("Impossible");
+    let mut parent = table;
+    for part in parent_parts {
+        let entry = parent
+            .entry(part.clone())
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()));
+        parent = match entry {
+            toml::Value::Table(entry_table) => entry_table,
+            _ => bail!("{} is not a table", part),
+        };
+    }
+    parent.insert(key.clone(), value);
+    Ok(())
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if !self.chronik_api_url.starts_with("http://")
+            && !self.chronik_api_url.starts_with("https://")
+        {
+            bail!(
+                "chronik_api_url must be an http:// or https:// URL, got {}",
+                self.chronik_api_url
+            );
+        }
+
+        if let Some(public_url) = &self.public_url {
+            if !public_url.starts_with("http://") && !public_url.starts_with("https://") {
+                bail!(
+                    "public_url must be an http:// or https:// URL, got {}",
+                    public_url
+                );
+            }
+        }
+
+        if let Some(base_dir) = &self.base_dir {
+            if !base_dir.is_dir() {
+                bail!("base_dir {} is not a directory", base_dir.display());
+            }
+        }
+
+        if let Some(admin_token) = &self.admin_token {
+            if admin_token.len() < 16 {
+                bail!("admin_token must be at least 16 characters long");
+            }
+        }
+
+        if self.limits.max_page_size == 0
+            || self.limits.max_block_range <= 0
+            || self.limits.max_export_range <= 0
+            || self.limits.request_timeout_secs == 0
+        {
+            bail!("limits must all be greater than 0");
+        }
+
+        if self.token_doc_check.timeout_secs == 0 || self.token_doc_check.max_size == 0 {
+            bail!("token_doc_check timeout_secs and max_size must be greater than 0");
+        }
+
+        if let Some(local_dir) = &self.token_icons.local_dir {
+            if !local_dir.is_dir() {
+                bail!(
+                    "token_icons.local_dir {} is not a directory",
+                    local_dir.display()
+                );
+            }
+        }
+
+        if let Some(url_template) = &self.token_icons.url_template {
+            let is_http =
+                url_template.starts_with("http://") || url_template.starts_with("https://");
+            if !is_http || !url_template.contains("{token_id}") {
+                bail!(
+                    "token_icons.url_template must be an http:// or https:// URL containing \
+                     {{token_id}}, got {}",
+                    url_template
+                );
+            }
+        }
+
+        if self.token_icons.timeout_secs == 0 || self.token_icons.max_size == 0 {
+            bail!("token_icons timeout_secs and max_size must be greater than 0");
+        }
+
+        for upgrade in &self.upgrades {
+            if upgrade.activation_time.is_some() == upgrade.activation_height.is_some() {
+                bail!(
+                    "Upgrade {} must have either an activation_time or an activation_height",
+                    upgrade.name
+                );
+            }
+        }
+
+        if self.xpub.gap_limit == 0 || self.xpub.max_addresses < self.xpub.gap_limit {
+            bail!("xpub.gap_limit must be greater than 0 and at most xpub.max_addresses");
+        }
+
+        if self.anonymous_quota.per_minute == 0 || self.anonymous_quota.daily == 0 {
+            bail!("anonymous_quota must be greater than 0");
+        }
+
+        if let Some(client_ip_header) = &self.api_quota.client_ip_header {
+            if HeaderName::from_bytes(client_ip_header.as_bytes()).is_err() {
+                bail!(
+                    "api_quota.client_ip_header must be a header name, got {}",
+                    client_ip_header
+                );
+            }
+        }
+
+        if let Some(upstream_proxy) = &self.upstream_proxy {
+            if !upstream_proxy.starts_with("socks5://") && !upstream_proxy.starts_with("socks5h://")
+            {
+                bail!(
+                    "upstream_proxy must be a socks5:// or socks5h:// URL, got {}",
+                    upstream_proxy
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_config_with_env, Config, LimitsConfig};
+
+    const MINIMAL_CONFIG: &str = r#"
+        host = "127.0.0.1:3035"
+        chronik_api_url = "https://chronik.be.cash/xec"
+    "#;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(env_var, value)| (env_var.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn load(config_string: &str, vars: &[(&str, &str)]) -> Config {
+        match load_config_with_env(config_string, env(vars)) {
+            Ok(config) => config,
+            Err(err) => panic!("Expected a valid config: {}", err),
+        }
+    }
+
+    fn load_error(config_string: &str, vars: &[(&str, &str)]) -> String {
+        match load_config_with_env(config_string, env(vars)) {
+            Ok(_) => panic!("Expected an invalid config"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let config = load(MINIMAL_CONFIG, &[]);
+        assert_eq!(config.chronik_api_url, "https://chronik.be.cash/xec");
+        assert_eq!(config.admin_token, None);
+
+        // Environment variables take precedence over the file
+        let config = load(
+            MINIMAL_CONFIG,
+            &[
+                ("EXPLORER_CHRONIK_URL", "http://localhost:7123"),
+                ("EXPLORER_ADMIN_TOKEN", "0123456789abcdef"),
+                ("UNRELATED", "ignored"),
+            ],
+        );
+        assert_eq!(config.chronik_api_url, "http://localhost:7123");
+        assert_eq!(config.admin_token.as_deref(), Some("0123456789abcdef"));
+
+        // A config can come from the environment alone
+        let config = load(
+            "",
+            &[
+                ("EXPLORER_HOST", "0.0.0.0:3035"),
+                ("EXPLORER_CHRONIK_URL", "http://localhost:7123"),
+            ],
+        );
+        assert_eq!(config.host.port(), 3035);
+
+        let err = load_error("", &[("EXPLORER_HOST", "0.0.0.0:3035")]);
+        assert!(err.contains("EXPLORER_CHRONIK_URL"), "{}", err);
+    }
+
+    #[test]
+    fn test_nested_env_overrides() {
+        let config_string = format!("{}\n[limits]\nmax_page_size = 50\n", MINIMAL_CONFIG);
+        let config = load(&config_string, &[]);
+        assert_eq!(config.limits.max_page_size, 50);
+        assert_eq!(
+            config.limits.max_block_range,
+            LimitsConfig::default().max_block_range
+        );
+
+        let config = load(
+            &config_string,
+            &[
+                ("EXPLORER_LIMITS__MAX_PAGE_SIZE", "20"),
+                ("EXPLORER_XPUB__ENABLED", "true"),
+                ("EXPLORER_MAINTENANCE__MESSAGE", "Back soon"),
+                ("EXPLORER_API_QUOTA__CLIENT_IP_HEADER", "x-forwarded-for"),
+            ],
+        );
+        assert_eq!(config.limits.max_page_size, 20);
+        assert_eq!(
+            config.limits.max_block_range,
+            LimitsConfig::default().max_block_range
+        );
+        assert!(config.xpub.enabled);
+        assert_eq!(config.maintenance.message, "Back soon");
+        assert_eq!(
+            config.api_quota.client_ip_header.as_deref(),
+            Some("x-forwarded-for"),
+        );
+
+        let err = load_error(MINIMAL_CONFIG, &[("EXPLORER_HOST__PORT", "3035")]);
+        assert_eq!(err, "Invalid environment variable EXPLORER_HOST__PORT");
+        let err = load_error(
+            MINIMAL_CONFIG,
+            &[("EXPLORER_LIMITS__MAX_PAGE_SIZE", "many")],
+        );
+        assert!(err.starts_with("Invalid config:"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate() {
+        let cases = [
+            (
+                "chronik_api_url = \"ftp://chronik\"",
+                "chronik_api_url must be an http:// or https:// URL, got ftp://chronik",
+            ),
+            (
+                "public_url = \"explorer.e.cash\"",
+                "public_url must be an http:// or https:// URL, got explorer.e.cash",
+            ),
+            (
+                "base_dir = \"/nonexistent/explorer\"",
+                "base_dir /nonexistent/explorer is not a directory",
+            ),
+            (
+                "admin_token = \"short\"",
+                "admin_token must be at least 16 characters long",
+            ),
+            (
+                "[limits]\nmax_page_size = 0",
+                "limits must all be greater than 0",
+            ),
+            (
+                "[limits]\nmax_export_range = -1",
+                "limits must all be greater than 0",
+            ),
+            (
+                "[token_doc_check]\ntimeout_secs = 0",
+                "token_doc_check timeout_secs and max_size must be greater than 0",
+            ),
+            (
+                "[token_icons]\nlocal_dir = \"/nonexistent/icons\"",
+                "token_icons.local_dir /nonexistent/icons is not a directory",
+            ),
+            (
+                "[token_icons]\nurl_template = \"https://icons.example.com/icon.png\"",
+                "token_icons.url_template must be an http:// or https:// URL containing \
+                 {token_id}, got https://icons.example.com/icon.png",
+            ),
+            (
+                "[token_icons]\nmax_size = 0",
+                "token_icons timeout_secs and max_size must be greater than 0",
+            ),
+            (
+                "[[upgrades]]\nname = \"Cowperthwaite\"",
+                "Upgrade Cowperthwaite must have either an activation_time or an \
+                 activation_height",
+            ),
+            (
+                "[[upgrades]]\nname = \"Cowperthwaite\"\nactivation_time = 1\n\
+                 activation_height = 1",
+                "Upgrade Cowperthwaite must have either an activation_time or an \
+                 activation_height",
+            ),
+            (
+                "[xpub]\ngap_limit = 0",
+                "xpub.gap_limit must be greater than 0 and at most xpub.max_addresses",
+            ),
+            (
+                "[xpub]\ngap_limit = 50\nmax_addresses = 40",
+                "xpub.gap_limit must be greater than 0 and at most xpub.max_addresses",
+            ),
+            (
+                "[anonymous_quota]\nper_minute = 0\ndaily = 100",
+                "anonymous_quota must be greater than 0",
+            ),
+            (
+                "[api_quota]\nclient_ip_header = \"x forwarded for\"",
+                "api_quota.client_ip_header must be a header name, got x forwarded for",
+            ),
+            (
+                "upstream_proxy = \"http://127.0.0.1:9050\"",
+                "upstream_proxy must be a socks5:// or socks5h:// URL, got \
+                 http://127.0.0.1:9050",
+            ),
+        ];
+        for (entry, expected_err) in cases {
+            // Tables come last, so they don't swallow the required entries
+            let config_string = if entry.starts_with("chronik_api_url") {
+                format!("host = \"127.0.0.1:3035\"\n{}", entry)
+            } else {
+                format!("{}\n{}", MINIMAL_CONFIG, entry)
+            };
+            assert_eq!(load_error(&config_string, &[]), expected_err, "{}", entry);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+use bitcoinsuite_chronik_client::{
+    proto::{BlockInfo, Tx},
+    ChronikClient,
+};
+use bitcoinsuite_error::Result;
+use chrono::Utc;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{
+    api::block_info_to_json,
+    blockchain::to_be_hex,
+    chainwork::ChainworkIndex,
+    forks::ForkMonitor,
+    network_stats::NetworkStats,
+    server_primitives::{JsonBlock, JsonBlockLatency, JsonNewTx},
+};
+
+/// How often Chronik is polled for a new tip
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Confirmations after which a block can't be reorged anymore, as Bitcoin
+/// ABC rejects reorgs deeper than 10 blocks. This isn't Avalanche
+/// finalization, which usually happens much sooner.
+pub const CONFIRMATION_DEPTH: i32 = 10;
+
+/// Events waiting to be sent to a slow subscriber. Older events are dropped
+/// for that subscriber.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Blocks whose receive time is kept, about two weeks of blocks
+const MAX_RECEIVE_TIMES: usize = 2016;
+
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum ExplorerEvent {
+    NewBlock(JsonBlock),
+    NewTx(JsonNewTx),
+    BlockConfirmed(JsonBlock),
+}
+
+impl ExplorerEvent {
+    /// Name of the event as sent in the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExplorerEvent::NewBlock(_) => "new-block",
+            ExplorerEvent::NewTx(_) => "new-tx",
+            ExplorerEvent::BlockConfirmed(_) => "block-confirmed",
+        }
+    }
+}
+
+pub fn event_channel() -> broadcast::Sender<ExplorerEvent> {
+    broadcast::channel(EVENT_CHANNEL_CAPACITY).0
+}
+
+/// Payload of the `new-tx` event, without a block height for mempool txs
+pub fn new_tx_json(tx: &Tx, block_height: Option<i32>) -> JsonNewTx {
+    JsonNewTx {
+        txid: to_be_hex(&tx.txid),
+        block_height,
+        is_coinbase: tx.is_coinbase,
+        num_inputs: tx.inputs.len() as u32,
+        num_outputs: tx.outputs.len() as u32,
+        sats_output: tx.outputs.iter().map(|output| output.value).sum(),
+    }
+}
+
+/// When the poller first saw the recent blocks. Only blocks found while the
+/// explorer runs are known, accurate to the poll interval, and they're lost on
+/// restart. Blocks the poller only saw while catching up, e.g. after Chronik
+/// was unreachable, aren't recorded, as their receive time would be late.
+#[derive(Default)]
+pub struct BlockReceiveTimes {
+    blocks: Mutex<VecDeque<JsonBlockLatency>>,
+}
+
+impl BlockReceiveTimes {
+    /// Records the block, unless a block at its height was already recorded,
+    /// so a reorg doesn't add a second entry with a later receive time
+    fn record(&self, block_info: &BlockInfo) {
+        let received_at = Utc::now().timestamp();
+        let mut blocks = self.blocks.lock().unwrap();
+        if blocks.iter().any(|block| block.height == block_info.height) {
+            return;
+        }
+        if blocks.len() >= MAX_RECEIVE_TIMES {
+            blocks.pop_front();
+        }
+        blocks.push_back(JsonBlockLatency {
+            hash: to_be_hex(&block_info.hash),
+            height: block_info.height,
+            timestamp: block_info.timestamp,
+            received_at,
+            skew_secs: received_at - block_info.timestamp,
+        });
+    }
+
+    /// Receive time of the block with the (big-endian hex) hash
+    pub fn get(&self, block_hash: &str) -> Option<i64> {
+        let blocks = self.blocks.lock().unwrap();
+        blocks
+            .iter()
+            .find(|block| block.hash == block_hash)
+            .map(|block| block.received_at)
+    }
+
+    /// All known receive times, oldest first
+    pub fn latencies(&self) -> Vec<JsonBlockLatency> {
+        self.blocks.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Polls Chronik for new blocks, keeps the block indexes up to date and
+/// broadcasts the blocks' events
+pub struct Poller<'a> {
+    chronik: &'a ChronikClient,
+    events: &'a broadcast::Sender<ExplorerEvent>,
+    receive_times: &'a BlockReceiveTimes,
+    chainwork: &'a ChainworkIndex,
+    network_stats: &'a NetworkStats,
+    fork_monitor: &'a ForkMonitor,
+    /// Height and hash of the last block whose events were all sent
+    broadcast_tip: Option<(i32, Vec<u8>)>,
+    /// Whether the last poll broadcast every block up to the tip it saw
+    is_caught_up: bool,
+}
+
+impl<'a> Poller<'a> {
+    pub fn new(
+        chronik: &'a ChronikClient,
+        events: &'a broadcast::Sender<ExplorerEvent>,
+        receive_times: &'a BlockReceiveTimes,
+        chainwork: &'a ChainworkIndex,
+        network_stats: &'a NetworkStats,
+        fork_monitor: &'a ForkMonitor,
+    ) -> Self {
+        Poller {
+            chronik,
+            events,
+            receive_times,
+            chainwork,
+            network_stats,
+            fork_monitor,
+            broadcast_tip: None,
+            is_caught_up: false,
+        }
+    }
+
+    /// Polls forever. Errors are logged and the blocks not broadcast yet are
+    /// retried at the next poll.
+    pub async fn run(mut self) {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let result = self.poll_once().await;
+            self.is_caught_up = result.is_ok();
+            if let Err(err) = result {
+                eprintln!("Polling Chronik for new blocks failed: {}", err);
+            }
+        }
+    }
+
+    /// Broadcasts the blocks after the last tip and advances it past each
+    /// block once all of the block's events are sent, so a failed poll doesn't
+    /// send them again. A changed tip hash at the same height (a reorg) is
+    /// broadcast as a new block. Only the first new block after a poll that
+    /// caught up has its receive time recorded.
+    async fn poll_once(&mut self) -> Result<()> {
+        let Poller {
+            chronik,
+            events,
+            receive_times,
+            chainwork,
+            network_stats,
+            fork_monitor,
+            ..
+        } = *self;
+        let is_caught_up = self.is_caught_up;
+        let broadcast_tip = &mut self.broadcast_tip;
+        let last_tip = broadcast_tip.clone();
+        let last_tip = last_tip.as_ref();
+        let blockchain_info = chronik.blockchain_info().await?;
+        let tip = (blockchain_info.tip_height, blockchain_info.tip_hash);
+
+        // A different tip at or below the last tip's height replaced the blocks
+        // from its height on. Reorgs deeper than the tip aren't noticed here.
+        let reorged_tip = last_tip
+            .filter(|(last_height, last_hash)| tip.0 <= *last_height && tip.1 != *last_hash);
+        let reorged_chainwork =
+            reorged_tip.and_then(|(last_height, _)| chainwork.get(*last_height));
+        if reorged_tip.is_some() {
+            chainwork.truncate(tip.0);
+            network_stats.truncate(tip.0);
+        }
+        if let Err(err) = chainwork.sync(chronik, tip.0).await {
+            eprintln!("Indexing chain work failed: {}", err);
+        }
+        if let Err(err) = network_stats.sync(chronik, tip.0).await {
+            eprintln!("Updating the 24h network stats failed: {}", err);
+        }
+        if last_tip.map(|(_, last_hash)| last_hash) != Some(&tip.1) {
+            if let Err(err) = fork_monitor.update(chronik, tip.0).await {
+                eprintln!("Checking for forks failed: {}", err);
+            }
+        }
+        // Nodes only switch to a chain with more work
+        if let (Some(reorged_chainwork), Some(tip_chainwork)) =
+            (reorged_chainwork, chainwork.get(tip.0))
+        {
+            if tip_chainwork < reorged_chainwork {
+                eprintln!(
+                    "New tip {} at height {} has less chain work than the tip it replaced ({} < {})",
+                    to_be_hex(&tip.1),
+                    tip.0,
+                    tip_chainwork.to_hex(),
+                    reorged_chainwork.to_hex(),
+                );
+            }
+        }
+        let (last_height, last_hash) = match last_tip {
+            Some(last_tip) => last_tip,
+            // Nothing to broadcast when starting up
+            None => {
+                *broadcast_tip = Some(tip);
+                return Ok(());
+            }
+        };
+        if tip.0 < *last_height || (tip.0 == *last_height && tip.1 == *last_hash) {
+            *broadcast_tip = Some(tip);
+            return Ok(());
+        }
+
+        let start_height = if tip.0 == *last_height {
+            tip.0
+        } else {
+            last_height + 1
+        };
+        for height in start_height..=tip.0 {
+            let block = chronik.block_by_height(height).await?;
+            let block_info = block.block_info.as_ref().expect("Impossible");
+            let confirmed_height = height - CONFIRMATION_DEPTH + 1;
+            let confirmed_blocks = if confirmed_height >= 0 {
+                chronik.blocks(confirmed_height, confirmed_height).await?
+            } else {
+                vec![]
+            };
+
+            if is_caught_up && height == start_height {
+                receive_times.record(block_info);
+            }
+            // Sending only fails if nobody is subscribed, which is fine
+            let _ = events.send(ExplorerEvent::NewBlock(block_info_to_json(block_info)));
+            for tx in &block.txs {
+                let _ = events.send(ExplorerEvent::NewTx(new_tx_json(tx, Some(height))));
+            }
+
+            for confirmed_block in &confirmed_blocks {
+                let _ = events.send(ExplorerEvent::BlockConfirmed(block_info_to_json(
+                    confirmed_block,
+                )));
+            }
+            *broadcast_tip = Some((height, block_info.hash.clone()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! Chain splits seen by the block poller. Chronik only indexes the main
+//! chain, so stale blocks are noticed by comparing the hashes of the recent
+//! main chain blocks whenever the tip changes. Forks are kept in memory and
+//! lost on restart.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Mutex,
+};
+
+use bitcoinsuite_chronik_client::ChronikClient;
+use bitcoinsuite_error::Result;
+use chrono::Utc;
+
+use crate::{blockchain::to_be_hex, server_primitives::JsonFork};
+
+/// Recent main chain blocks compared with the new chain. Bitcoin ABC rejects
+/// reorgs deeper than 10 blocks, so deeper forks are a split of the network.
+const TRACKED_BLOCKS: i32 = 100;
+/// Forks kept for the /forks page
+const MAX_FORKS: usize = 100;
+/// Single stale blocks happen now and then when two miners find a block at
+/// the same time; deeper forks are reported as alerts
+pub const FORK_ALERT_DEPTH: u32 = 2;
+
+#[derive(Default)]
+pub struct ForkMonitor {
+    /// Hashes of the recent main chain blocks by height
+    main_chain: Mutex<BTreeMap<i32, Vec<u8>>>,
+    /// Forks seen, the latest last
+    forks: Mutex<VecDeque<JsonFork>>,
+}
+
+impl ForkMonitor {
+    /// Compares the recent blocks of the chain ending at `tip_height` with
+    /// the ones seen before and records the replaced blocks as a fork
+    pub async fn update(&self, chronik: &ChronikClient, tip_height: i32) -> Result<()> {
+        let start_height = (tip_height - TRACKED_BLOCKS + 1).max(0);
+        let block_infos = chronik.blocks(start_height, tip_height).await?;
+        let new_chain = block_infos
+            .iter()
+            .map(|block_info| (block_info.height, block_info.hash.clone()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut main_chain = self.main_chain.lock().unwrap();
+        let stale_blocks = main_chain
+            .range(start_height..)
+            .filter(|(height, hash)| new_chain.get(*height) != Some(*hash))
+            .collect::<Vec<_>>();
+        if let Some(&(&fork_height, _)) = stale_blocks.first() {
+            let fork = JsonFork {
+                detected_at: Utc::now().timestamp(),
+                fork_height,
+                depth: stale_blocks.len() as u32,
+                stale_block_hashes: stale_blocks
+                    .iter()
+                    .map(|(_, hash)| to_be_hex(hash))
+                    .collect(),
+                new_tip_height: tip_height,
+                new_tip_hash: new_chain
+                    .get(&tip_height)
+                    .map(|hash| to_be_hex(hash))
+                    .unwrap_or_default(),
+            };
+            let mut forks = self.forks.lock().unwrap();
+            if forks.len() >= MAX_FORKS {
+                forks.pop_front();
+            }
+            forks.push_back(fork);
+        }
+        *main_chain = new_chain;
+        Ok(())
+    }
+
+    /// Forks seen, the latest first
+    pub fn forks(&self) -> Vec<JsonFork> {
+        self.forks.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
@@ -0,0 +1,82 @@
+//! Blockies-style identicons: a grid of cells mirrored left to right, with
+//! the cells and their three colors derived from the SHA-256 of a seed, e.g.
+//! a token id. They make ids that only differ deep inside a long hash easy to
+//! tell apart at a glance.
+
+use bitcoin::hashes::{sha256, Hash};
+use bitcoinsuite_error::Result;
+
+const GRID_SIZE: usize = 8;
+/// Pixels per cell
+const CELL_SIZE: usize = 8;
+/// Width and height of identicons in pixels
+pub const IDENTICON_SIZE: u32 = (GRID_SIZE * CELL_SIZE) as u32;
+
+type Rgb = [u8; 3];
+
+/// Renders the identicon of `seed` as a PNG
+pub fn identicon_png(seed: &[u8]) -> Result<Vec<u8>> {
+    let hash = sha256::Hash::hash(seed);
+    let hash = &hash[..];
+    let background = color(&hash[0..3]);
+    let foreground = color(&hash[3..6]);
+    let spot = color(&hash[6..9]);
+
+    // Two bits per cell of the left half: mostly background and foreground,
+    // with the occasional spot
+    let cells = &hash[9..9 + GRID_SIZE * GRID_SIZE / 2 / 4];
+    let cell_color = |row: usize, col: usize| {
+        let col = if col < GRID_SIZE / 2 {
+            col
+        } else {
+            GRID_SIZE - 1 - col
+        };
+        let cell_idx = row * GRID_SIZE / 2 + col;
+        match cells[cell_idx / 4] >> (2 * (cell_idx % 4)) & 0b11 {
+            0 => background,
+            3 => spot,
+            _ => foreground,
+        }
+    };
+
+    let mut pixels = Vec::with_capacity((IDENTICON_SIZE * IDENTICON_SIZE * 3) as usize);
+    for y in 0..IDENTICON_SIZE as usize {
+        for x in 0..IDENTICON_SIZE as usize {
+            pixels.extend_from_slice(&cell_color(y / CELL_SIZE, x / CELL_SIZE));
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, IDENTICON_SIZE, IDENTICON_SIZE);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&pixels)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// Color with the hue, saturation and lightness picked by three bytes,
+/// avoiding washed out and very dark or light colors
+fn color(bytes: &[u8]) -> Rgb {
+    let hue = f64::from(bytes[0]) / 256.0 * 360.0;
+    let saturation = 0.4 + f64::from(bytes[1]) / 255.0 * 0.6;
+    let lightness = 0.25 + f64::from(bytes[2]) / 255.0 * 0.5;
+    hsl_to_rgb(hue, saturation, lightness)
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> Rgb {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r, g, b].map(|channel| ((channel + m) * 255.0).round() as u8)
+}
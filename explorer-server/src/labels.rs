@@ -0,0 +1,65 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::RwLock};
+
+use bitcoinsuite_core::CashAddress;
+use bitcoinsuite_error::Result;
+use eyre::{eyre, WrapErr};
+
+/// Prefix of the addresses labels are stored under, so a label applies to
+/// both the ecash: and etoken: form of an address
+const LABEL_ADDR_PREFIX: &str = "ecash";
+
+/// Labels of known addresses, e.g. exchanges or the miner fund. They are
+/// loaded from a TOML file mapping addresses to labels, and labels set at
+/// runtime are written back to that file.
+pub struct AddressLabels {
+    path: Option<PathBuf>,
+    labels: RwLock<HashMap<String, String>>,
+}
+
+impl AddressLabels {
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let mut labels = HashMap::new();
+
+        if let Some(path) = &path {
+            let labels_string = fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read labels file {}", path.display()))?;
+            let file_labels: HashMap<String, String> = toml::from_str(&labels_string)
+                .wrap_err_with(|| format!("Invalid labels file {}", path.display()))?;
+            for (address, label) in file_labels {
+                let address = CashAddress::parse_cow(address.as_str().into())
+                    .map_err(|err| eyre!("Invalid address {} in labels file: {}", address, err))?;
+                labels.insert(label_key(&address), label);
+            }
+        }
+
+        Ok(AddressLabels {
+            path,
+            labels: RwLock::new(labels),
+        })
+    }
+
+    pub fn get(&self, address: &CashAddress) -> Option<String> {
+        let labels = self.labels.read().unwrap();
+        labels.get(&label_key(address)).cloned()
+    }
+
+    /// Sets the label of `address`, or removes it if `label` is `None`.
+    pub fn set(&self, address: &CashAddress, label: Option<String>) -> Result<()> {
+        let mut labels = self.labels.write().unwrap();
+        match label {
+            Some(label) => labels.insert(label_key(address), label),
+            None => labels.remove(&label_key(address)),
+        };
+
+        if let Some(path) = &self.path {
+            fs::write(path, toml::to_string(&*labels)?)
+                .wrap_err_with(|| format!("Failed to write labels file {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn label_key(address: &CashAddress) -> String {
+    address.with_prefix(LABEL_ADDR_PREFIX).as_str().to_string()
+}
@@ -1,8 +1,33 @@
+mod admin_auth;
+mod alp;
 mod api;
+pub mod api_keys;
 mod blockchain;
+pub mod chainwork;
+mod coinbase;
+pub mod compression;
 pub mod config;
+mod events;
+mod forks;
+mod identicon;
+pub mod labels;
+pub mod locale;
+mod network_stats;
+mod opcodes;
+mod openapi;
+mod pagination;
+pub mod prices;
+mod query;
+mod script_analysis;
 pub mod server;
 pub mod server_error;
 pub mod server_http;
 pub mod server_primitives;
+mod sitemap;
+mod supply;
 mod templating;
+pub mod token_docs;
+pub mod token_icons;
+pub mod upgrades;
+pub mod webhooks;
+pub mod xpub;
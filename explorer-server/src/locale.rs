@@ -0,0 +1,237 @@
+use axum::http::{header, HeaderMap};
+use num_format::Locale;
+
+/// Locale used to format numbers and dates on server rendered pages.
+/// It's taken from the `locale` cookie set by the footer selector, or else
+/// from the browser's Accept-Language header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayLocale {
+    #[default]
+    En,
+    De,
+    Fr,
+}
+
+impl DisplayLocale {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let cookie_locale = cookie_values(headers, "locale").find_map(DisplayLocale::from_tag);
+        if let Some(locale) = cookie_locale {
+            return locale;
+        }
+
+        // Languages are tried in the order given, ignoring their q-values
+        headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|accept_language| accept_language.to_str().ok())
+            .and_then(|accept_language| {
+                accept_language
+                    .split(',')
+                    .filter_map(|language| language.split(';').next())
+                    .find_map(|language| DisplayLocale::from_tag(language.trim()))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Parses a language tag like "de" or "fr-FR".
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Some(DisplayLocale::En),
+            "de" => Some(DisplayLocale::De),
+            "fr" => Some(DisplayLocale::Fr),
+            _ => None,
+        }
+    }
+
+    pub fn num_format_locale(self) -> Locale {
+        match self {
+            DisplayLocale::En => Locale::en,
+            DisplayLocale::De => Locale::de,
+            DisplayLocale::Fr => Locale::fr,
+        }
+    }
+
+    pub fn decimal_separator(self) -> &'static str {
+        self.num_format_locale().decimal()
+    }
+
+    pub fn grouping_separator(self) -> &'static str {
+        self.num_format_locale().separator()
+    }
+
+    /// chrono format of absolute timestamps, always in UTC
+    pub fn date_format(self) -> &'static str {
+        match self {
+            DisplayLocale::En => "%Y-%m-%d %H:%M:%S UTC",
+            DisplayLocale::De => "%d.%m.%Y %H:%M:%S UTC",
+            DisplayLocale::Fr => "%d/%m/%Y %H:%M:%S UTC",
+        }
+    }
+}
+
+/// Unit that amounts are shown in on server rendered pages, taken from the
+/// `unit` query parameter, or else from the `unit` cookie set by the footer
+/// selector. JSON amounts are always in sats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Denomination {
+    #[default]
+    Xec,
+    KiloXec,
+    Sats,
+    /// 100 sats, the same amount as an XEC under its older name
+    Bits,
+}
+
+impl Denomination {
+    pub fn from_request(headers: &HeaderMap, unit: Option<&str>) -> Self {
+        unit.and_then(Denomination::from_tag)
+            .or_else(|| cookie_values(headers, "unit").find_map(Denomination::from_tag))
+            .unwrap_or_default()
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag.to_ascii_lowercase().as_str() {
+            "xec" => Some(Denomination::Xec),
+            "kxec" => Some(Denomination::KiloXec),
+            "sats" => Some(Denomination::Sats),
+            "bits" => Some(Denomination::Bits),
+            _ => None,
+        }
+    }
+
+    pub fn sats_per_unit(self) -> u64 {
+        match self {
+            Denomination::Xec | Denomination::Bits => 100,
+            Denomination::KiloXec => 100_000,
+            Denomination::Sats => 1,
+        }
+    }
+
+    /// Digits after the decimal separator, enough to show every sat
+    pub fn decimals(self) -> usize {
+        match self {
+            Denomination::Xec | Denomination::Bits => 2,
+            Denomination::KiloXec => 5,
+            Denomination::Sats => 0,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Denomination::Xec => "XEC",
+            Denomination::KiloXec => "kXEC",
+            Denomination::Sats => "sats",
+            Denomination::Bits => "bits",
+        }
+    }
+}
+
+/// Values of the cookies named `name`
+fn cookie_values<'a>(headers: &'a HeaderMap, name: &'a str) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|cookie| cookie.to_str().ok())
+        .flat_map(|cookie| cookie.split(';'))
+        .filter_map(move |pair| pair.trim().strip_prefix(name)?.strip_prefix('='))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderMap, HeaderValue};
+    use chrono::{TimeZone, Utc};
+    use num_format::ToFormattedString;
+
+    use super::*;
+
+    fn headers(entries: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.append(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(DisplayLocale::from_tag("de-DE"), Some(DisplayLocale::De));
+        assert_eq!(DisplayLocale::from_tag("fr_FR"), Some(DisplayLocale::Fr));
+        assert_eq!(DisplayLocale::from_tag("EN-us"), Some(DisplayLocale::En));
+        assert_eq!(DisplayLocale::from_tag("es-ES"), None);
+    }
+
+    #[test]
+    fn test_from_headers() {
+        assert_eq!(
+            DisplayLocale::from_headers(&headers(&[])),
+            DisplayLocale::En
+        );
+        assert_eq!(
+            DisplayLocale::from_headers(&headers(&[(
+                header::ACCEPT_LANGUAGE,
+                "es-ES,fr-FR;q=0.9,de;q=0.8",
+            )])),
+            DisplayLocale::Fr,
+        );
+        // The cookie set by the selector wins over the browser's languages
+        assert_eq!(
+            DisplayLocale::from_headers(&headers(&[
+                (header::ACCEPT_LANGUAGE, "fr-FR"),
+                (header::COOKIE, "unit=sats; locale=de-DE"),
+            ])),
+            DisplayLocale::De,
+        );
+    }
+
+    #[test]
+    fn test_de_formatting() {
+        let locale = DisplayLocale::from_tag("de-DE").unwrap();
+        assert_eq!(locale.decimal_separator(), ",");
+        assert_eq!(locale.grouping_separator(), ".");
+        assert_eq!(
+            1_234_567u64.to_formatted_string(&locale.num_format_locale()),
+            "1.234.567",
+        );
+        let timestamp = Utc.timestamp(1_700_049_600, 0);
+        assert_eq!(
+            timestamp.format(locale.date_format()).to_string(),
+            "15.11.2023 12:00:00 UTC",
+        );
+    }
+
+    #[test]
+    fn test_fr_formatting() {
+        let locale = DisplayLocale::from_tag("fr-FR").unwrap();
+        assert_eq!(locale.decimal_separator(), ",");
+        // French groups digits with a (narrow) no-break space
+        let separator = locale.grouping_separator();
+        assert!(!separator.is_empty() && separator.chars().all(char::is_whitespace));
+        assert_eq!(
+            1_234_567u64.to_formatted_string(&locale.num_format_locale()),
+            format!("1{0}234{0}567", separator),
+        );
+        let timestamp = Utc.timestamp(1_700_049_600, 0);
+        assert_eq!(
+            timestamp.format(locale.date_format()).to_string(),
+            "15/11/2023 12:00:00 UTC",
+        );
+    }
+
+    #[test]
+    fn test_denomination_from_request() {
+        assert_eq!(
+            Denomination::from_request(&headers(&[]), None),
+            Denomination::Xec,
+        );
+        let cookie_headers = headers(&[(header::COOKIE, "unit=kxec")]);
+        assert_eq!(
+            Denomination::from_request(&cookie_headers, None),
+            Denomination::KiloXec,
+        );
+        // The query parameter wins over the cookie
+        assert_eq!(
+            Denomination::from_request(&cookie_headers, Some("SATS")),
+            Denomination::Sats,
+        );
+    }
+}
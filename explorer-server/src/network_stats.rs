@@ -0,0 +1,122 @@
+//! Network activity over the last 24 hours, kept up to date by the block
+//! poller. The totals of each recent block are kept in memory, so the window
+//! is fetched from Chronik again after a restart.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use bitcoinsuite_chronik_client::ChronikClient;
+use bitcoinsuite_error::Result;
+use chrono::Utc;
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    api::calc_block_fee_stats, blockchain::BLOCKS_PER_DAY, server_primitives::JsonNetworkStats,
+};
+
+const WINDOW_SECS: i64 = 24 * 3600;
+/// Blocks searched for the start of the window on startup. Blocks come
+/// faster than every 10 minutes at times, so this has some slack.
+const MAX_WINDOW_BLOCKS: i32 = 2 * BLOCKS_PER_DAY;
+const FETCH_CONCURRENCY: usize = 8;
+
+struct BlockTotals {
+    height: i32,
+    timestamp: i64,
+    num_txs: u64,
+    /// Sats sent by the non-coinbase txs, including change
+    sats_transferred: i64,
+    fees_sats: i64,
+}
+
+/// Totals of the blocks mined in the last 24 hours, oldest first
+#[derive(Default)]
+pub struct NetworkStats {
+    blocks: Mutex<VecDeque<BlockTotals>>,
+}
+
+impl NetworkStats {
+    /// Drops the blocks from `height` on, e.g. because they were reorged
+    pub fn truncate(&self, height: i32) {
+        let mut blocks = self.blocks.lock().unwrap();
+        while blocks.back().map_or(false, |block| block.height >= height) {
+            blocks.pop_back();
+        }
+    }
+
+    /// Adds the blocks after the last one added, up to `tip_height`, and
+    /// drops blocks that left the window
+    pub async fn sync(&self, chronik: &ChronikClient, tip_height: i32) -> Result<()> {
+        let window_start = Utc::now().timestamp() - WINDOW_SECS;
+        let last_height = self.blocks.lock().unwrap().back().map(|block| block.height);
+        let start_height = match last_height {
+            Some(last_height) => last_height + 1,
+            None => {
+                let first_height = (tip_height - MAX_WINDOW_BLOCKS + 1).max(0);
+                let block_infos = chronik.blocks(first_height, tip_height).await?;
+                match block_infos
+                    .iter()
+                    .find(|block_info| block_info.timestamp >= window_start)
+                {
+                    Some(block_info) => block_info.height,
+                    None => tip_height + 1,
+                }
+            }
+        };
+
+        let new_blocks = stream::iter(start_height..=tip_height)
+            .map(|height| chronik.block_by_height(height))
+            .buffered(FETCH_CONCURRENCY)
+            .map_ok(|block| {
+                let block_info = block.block_info.expect("Impossible");
+                let sats_transferred = block
+                    .txs
+                    .iter()
+                    .filter(|tx| !tx.is_coinbase)
+                    .flat_map(|tx| &tx.outputs)
+                    .map(|output| output.value)
+                    .sum();
+                BlockTotals {
+                    height: block_info.height,
+                    timestamp: block_info.timestamp,
+                    num_txs: block_info.num_txs,
+                    sats_transferred,
+                    fees_sats: calc_block_fee_stats(&block.txs)
+                        .map_or(0, |fee_stats| fee_stats.total_fees),
+                }
+            })
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut blocks = self.blocks.lock().unwrap();
+        // Truncated by a reorg in the meantime, start over at the next poll
+        if blocks.back().map(|block| block.height) != last_height {
+            return Ok(());
+        }
+        blocks.extend(new_blocks);
+        while blocks
+            .front()
+            .map_or(false, |block| block.timestamp < window_start)
+        {
+            blocks.pop_front();
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> JsonNetworkStats {
+        let blocks = self.blocks.lock().unwrap();
+        let avg_block_interval_secs = match (blocks.front(), blocks.back()) {
+            (Some(first), Some(last)) if blocks.len() > 1 => {
+                Some((last.timestamp - first.timestamp) as f64 / (blocks.len() - 1) as f64)
+            }
+            _ => None,
+        };
+        JsonNetworkStats {
+            num_blocks: blocks.len() as u32,
+            num_txs: blocks.iter().map(|block| block.num_txs).sum(),
+            sats_transferred: blocks.iter().map(|block| block.sats_transferred).sum(),
+            fees_sats: blocks.iter().map(|block| block.fees_sats).sum(),
+            avg_block_interval_secs,
+            tip_height: blocks.back().map(|block| block.height),
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Names and short descriptions of eCash script opcodes, for annotating
+//! scripts on the tx page.
+
+/// Name of the opcode as in the node's `ScriptToAsmStr`, e.g. "OP_DUP".
+/// Direct pushes (0x01-0x4b) are named after their length.
+pub fn opcode_name(opcode: u8) -> String {
+    let name = match opcode {
+        0x00 => "OP_0",
+        0x01..=0x4b => return format!("OP_PUSHBYTES_{}", opcode),
+        0x4c => "OP_PUSHDATA1",
+        0x4d => "OP_PUSHDATA2",
+        0x4e => "OP_PUSHDATA4",
+        0x4f => "OP_1NEGATE",
+        0x50 => "OP_RESERVED",
+        0x51..=0x60 => return format!("OP_{}", opcode - 0x50),
+        0x61 => "OP_NOP",
+        0x62 => "OP_VER",
+        0x63 => "OP_IF",
+        0x64 => "OP_NOTIF",
+        0x65 => "OP_VERIF",
+        0x66 => "OP_VERNOTIF",
+        0x67 => "OP_ELSE",
+        0x68 => "OP_ENDIF",
+        0x69 => "OP_VERIFY",
+        0x6a => "OP_RETURN",
+        0x6b => "OP_TOALTSTACK",
+        0x6c => "OP_FROMALTSTACK",
+        0x6d => "OP_2DROP",
+        0x6e => "OP_2DUP",
+        0x6f => "OP_3DUP",
+        0x70 => "OP_2OVER",
+        0x71 => "OP_2ROT",
+        0x72 => "OP_2SWAP",
+        0x73 => "OP_IFDUP",
+        0x74 => "OP_DEPTH",
+        0x75 => "OP_DROP",
+        0x76 => "OP_DUP",
+        0x77 => "OP_NIP",
+        0x78 => "OP_OVER",
+        0x79 => "OP_PICK",
+        0x7a => "OP_ROLL",
+        0x7b => "OP_ROT",
+        0x7c => "OP_SWAP",
+        0x7d => "OP_TUCK",
+        0x7e => "OP_CAT",
+        0x7f => "OP_SPLIT",
+        0x80 => "OP_NUM2BIN",
+        0x81 => "OP_BIN2NUM",
+        0x82 => "OP_SIZE",
+        0x83 => "OP_INVERT",
+        0x84 => "OP_AND",
+        0x85 => "OP_OR",
+        0x86 => "OP_XOR",
+        0x87 => "OP_EQUAL",
+        0x88 => "OP_EQUALVERIFY",
+        0x89 => "OP_RESERVED1",
+        0x8a => "OP_RESERVED2",
+        0x8b => "OP_1ADD",
+        0x8c => "OP_1SUB",
+        0x8d => "OP_2MUL",
+        0x8e => "OP_2DIV",
+        0x8f => "OP_NEGATE",
+        0x90 => "OP_ABS",
+        0x91 => "OP_NOT",
+        0x92 => "OP_0NOTEQUAL",
+        0x93 => "OP_ADD",
+        0x94 => "OP_SUB",
+        0x95 => "OP_MUL",
+        0x96 => "OP_DIV",
+        0x97 => "OP_MOD",
+        0x98 => "OP_LSHIFT",
+        0x99 => "OP_RSHIFT",
+        0x9a => "OP_BOOLAND",
+        0x9b => "OP_BOOLOR",
+        0x9c => "OP_NUMEQUAL",
+        0x9d => "OP_NUMEQUALVERIFY",
+        0x9e => "OP_NUMNOTEQUAL",
+        0x9f => "OP_LESSTHAN",
+        0xa0 => "OP_GREATERTHAN",
+        0xa1 => "OP_LESSTHANOREQUAL",
+        0xa2 => "OP_GREATERTHANOREQUAL",
+        0xa3 => "OP_MIN",
+        0xa4 => "OP_MAX",
+        0xa5 => "OP_WITHIN",
+        0xa6 => "OP_RIPEMD160",
+        0xa7 => "OP_SHA1",
+        0xa8 => "OP_SHA256",
+        0xa9 => "OP_HASH160",
+        0xaa => "OP_HASH256",
+        0xab => "OP_CODESEPARATOR",
+        0xac => "OP_CHECKSIG",
+        0xad => "OP_CHECKSIGVERIFY",
+        0xae => "OP_CHECKMULTISIG",
+        0xaf => "OP_CHECKMULTISIGVERIFY",
+        0xb0 => "OP_NOP1",
+        0xb1 => "OP_CHECKLOCKTIMEVERIFY",
+        0xb2 => "OP_CHECKSEQUENCEVERIFY",
+        0xb3..=0xb9 => return format!("OP_NOP{}", opcode - 0xaf),
+        0xba => "OP_CHECKDATASIG",
+        0xbb => "OP_CHECKDATASIGVERIFY",
+        0xbc => "OP_REVERSEBYTES",
+        _ => return format!("OP_UNKNOWN_{:02x}", opcode),
+    };
+    name.to_string()
+}
+
+/// One-line description of what the opcode does, shown as a tooltip. Empty
+/// for opcodes that are disabled or have no effect.
+pub fn opcode_description(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "Pushes an empty byte vector",
+        0x01..=0x4e => "Pushes the following bytes",
+        0x4f => "Pushes the number -1",
+        0x51..=0x60 => "Pushes the number",
+        0x63 => "Runs the following statements if the top stack item is true",
+        0x64 => "Runs the following statements if the top stack item is false",
+        0x67 => "Runs the following statements if the preceding branch wasn't run",
+        0x68 => "Ends an if/else block",
+        0x69 => "Fails the script unless the top stack item is true",
+        0x6a => "Marks the output as unspendable, usually to carry data",
+        0x6b => "Moves the top item to the alt stack",
+        0x6c => "Moves the top item of the alt stack back",
+        0x6d => "Removes the top two items",
+        0x6e => "Duplicates the top two items",
+        0x6f => "Duplicates the top three items",
+        0x73 => "Duplicates the top item if it's not zero",
+        0x74 => "Pushes the number of stack items",
+        0x75 => "Removes the top item",
+        0x76 => "Duplicates the top item",
+        0x77 => "Removes the second item",
+        0x78 => "Copies the second item to the top",
+        0x79 => "Copies the nth item to the top",
+        0x7a => "Moves the nth item to the top",
+        0x7b => "Rotates the top three items",
+        0x7c => "Swaps the top two items",
+        0x7d => "Copies the top item below the second item",
+        0x7e => "Concatenates the top two items",
+        0x7f => "Splits an item at a position",
+        0x80 => "Converts a number to a byte vector of the given size",
+        0x81 => "Converts a byte vector to a minimally encoded number",
+        0x82 => "Pushes the size of the top item",
+        0x84 => "Bitwise AND of the top two items",
+        0x85 => "Bitwise OR of the top two items",
+        0x86 => "Bitwise XOR of the top two items",
+        0x87 => "Pushes true if the top two items are equal",
+        0x88 => "Fails the script unless the top two items are equal",
+        0x8b => "Adds 1",
+        0x8c => "Subtracts 1",
+        0x8f => "Negates the number",
+        0x90 => "Absolute value of the number",
+        0x91 => "Pushes true if the number is 0",
+        0x92 => "Pushes true if the number isn't 0",
+        0x93 => "Adds the top two numbers",
+        0x94 => "Subtracts the top number from the second",
+        0x96 => "Divides the second number by the top number",
+        0x97 => "Remainder of dividing the second number by the top number",
+        0x9a => "Pushes true if both numbers aren't 0",
+        0x9b => "Pushes true if either number isn't 0",
+        0x9c => "Pushes true if the top two numbers are equal",
+        0x9d => "Fails the script unless the top two numbers are equal",
+        0x9e => "Pushes true if the top two numbers aren't equal",
+        0x9f => "Pushes true if the second number is less than the top number",
+        0xa0 => "Pushes true if the second number is greater than the top number",
+        0xa1 => "Pushes true if the second number is at most the top number",
+        0xa2 => "Pushes true if the second number is at least the top number",
+        0xa3 => "Pushes the smaller of the top two numbers",
+        0xa4 => "Pushes the larger of the top two numbers",
+        0xa5 => "Pushes true if a number is within a range",
+        0xa6 => "RIPEMD-160 hash of the top item",
+        0xa7 => "SHA-1 hash of the top item",
+        0xa8 => "SHA-256 hash of the top item",
+        0xa9 => "RIPEMD-160 of SHA-256 of the top item",
+        0xaa => "Double SHA-256 hash of the top item",
+        0xab => "Signatures only cover the script after this opcode",
+        0xac => "Pushes true if the signature is valid for the public key",
+        0xad => "Fails the script unless the signature is valid for the public key",
+        0xae => "Pushes true if m of the n signatures are valid",
+        0xaf => "Fails the script unless m of the n signatures are valid",
+        0xb1 => "Fails the script unless the tx's lock time is at least the top item",
+        0xb2 => "Fails the script unless the input's relative lock time is at least the top item",
+        0xba => "Pushes true if the signature is valid for the message and public key",
+        0xbb => "Fails the script unless the signature is valid for the message and public key",
+        0xbc => "Reverses the bytes of the top item",
+        _ => "",
+    }
+}
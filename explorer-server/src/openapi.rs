@@ -0,0 +1,528 @@
+//! OpenAPI description of the JSON API, served at /api/openapi.json. The
+//! schemas are derived from the types in server_primitives.rs, so they change
+//! with the responses; new endpoints have to be added to [`ENDPOINTS`], which
+//! a test checks against the `/api` routes of the router.
+
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use crate::server_primitives::{
+    JsonAddressBalancesResponse, JsonAddressLabelRequest, JsonAddressSummary, JsonAddressesRequest,
+    JsonApiUsage, JsonBatchQuery, JsonBatchResponse, JsonBlockLatencyResponse, JsonBlockResponse,
+    JsonBlocksResponse, JsonDifficultyChartResponse, JsonFeeEstimate, JsonMerkleProof,
+    JsonNetworkStats, JsonOutputTypesChartResponse, JsonSearchSuggestionsResponse, JsonStatus,
+    JsonSupply, JsonTokenMeta, JsonTokensRequest, JsonTokensResponse, JsonTxConfirmations,
+    JsonTxDetails, JsonTxFlows, JsonTxGraph, JsonTxPackageRequest, JsonTxPackageResponse,
+    JsonTxScripts, JsonTxsResponse, JsonUpgradeStatus, JsonUtxosResponse, JsonWebhook,
+    JsonWebhookRequest,
+};
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+enum Response {
+    Json(SchemaFn),
+    /// A body that isn't JSON, described as a string
+    Other {
+        content_type: &'static str,
+        description: &'static str,
+    },
+    NoContent,
+}
+
+use Response::*;
+
+struct Endpoint {
+    method: &'static str,
+    /// Path in OpenAPI format; parameters in braces are path parameters
+    path: &'static str,
+    summary: &'static str,
+    /// Query parameters with their description
+    query: &'static [(&'static str, &'static str)],
+    request: Option<SchemaFn>,
+    response: Response,
+}
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        method: "get",
+        path: "/api/blocks/{start_height}/{end_height}",
+        summary: "Block infos for a height range",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonBlocksResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/export/blocks.ndjson",
+        summary: "Block infos for a height range as newline delimited JSON",
+        query: &[
+            (
+                "from",
+                "First height, default: the last max_export_range blocks",
+            ),
+            ("to", "Last height, default: the tip"),
+        ],
+        request: None,
+        response: Other {
+            content_type: "application/x-ndjson",
+            description: "One block info per line",
+        },
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/block/{hash}",
+        summary: "Block info, fee stats and the decoded coinbase",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonBlockResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/block/{hash}/raw",
+        summary: "The serialized block as downloaded by nodes",
+        query: &[],
+        request: None,
+        response: Other {
+            content_type: "application/octet-stream",
+            description: "The serialized block",
+        },
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/block/{hash}/transactions",
+        summary: "Txs of a block",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxsResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/block-height/{height}/txs",
+        summary: "Txs of the main chain block at a height",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxsResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/charts/difficulty",
+        summary: "Average difficulty and estimated hashrate per day",
+        query: &[("days", "Number of days, default 90, at most 730")],
+        request: None,
+        response: Json(schema::<JsonDifficultyChartResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/status",
+        summary: "Chain tip and alerts for forks seen in the last 24 hours",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonStatus>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/upgrade",
+        summary: "Network upgrades scheduled by the explorer's operator and whether they activated",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonUpgradeStatus>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/stats/24h",
+        summary: "Txs, XEC transferred, fees and block interval of the last 24 hours",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonNetworkStats>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/supply",
+        summary: "Circulating and maximum supply and the next halving",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonSupply>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/supply/circulating",
+        summary: "Circulating supply in XEC as a plain number",
+        query: &[],
+        request: None,
+        response: Other {
+            content_type: "text/plain",
+            description: "Supply in XEC, e.g. 19600000000000.00",
+        },
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/supply/max",
+        summary: "Maximum supply in XEC as a plain number",
+        query: &[],
+        request: None,
+        response: Other {
+            content_type: "text/plain",
+            description: "Supply in XEC, e.g. 21000000000000.00",
+        },
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/charts/output-types",
+        summary: "Outputs by script type per day",
+        query: &[("days", "Number of days, default 7, at most 30")],
+        request: None,
+        response: Json(schema::<JsonOutputTypesChartResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/charts/block-latency",
+        summary: "Miner timestamps and receive times of recent blocks",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonBlockLatencyResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/fees/estimate",
+        summary: "Recommended fee rates in sats per kB",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonFeeEstimate>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/address/{address}/transactions",
+        summary: "Tx history of an address",
+        query: &[
+            ("page", "Page number, starting at 0"),
+            ("take", "Txs per page"),
+            ("token", "Only txs of the token with this id"),
+            ("from", "Only txs from this date on, as YYYY-MM-DD"),
+            ("to", "Only txs until this date, as YYYY-MM-DD"),
+        ],
+        request: None,
+        response: Json(schema::<JsonTxsResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/address/{address}/utxos",
+        summary: "UTXOs of an address",
+        query: &[
+            ("page", "Page number, starting at 0"),
+            ("take", "UTXOs per page"),
+            ("sort", "value or age"),
+        ],
+        request: None,
+        response: Json(schema::<JsonUtxosResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/address/{address}/summary",
+        summary: "First seen, last activity, tx count and totals of an address",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonAddressSummary>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/addresses/balances",
+        summary: "Balances and last activity of up to 100 addresses",
+        query: &[],
+        request: Some(schema::<JsonAddressesRequest>),
+        response: Json(schema::<JsonAddressBalancesResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/token/{token_id}",
+        summary: "Metadata and supply of a token, with an ETag",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTokenMeta>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/tokens",
+        summary: "Metadata and supply of up to 100 tokens, with an ETag",
+        query: &[],
+        request: Some(schema::<JsonTokensRequest>),
+        response: Json(schema::<JsonTokensResponse>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/batch",
+        summary: "Up to 100 queries in one request",
+        query: &[],
+        request: Some(schema::<Vec<JsonBatchQuery>>),
+        response: Json(schema::<JsonBatchResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}",
+        summary: "A tx with its inputs and outputs",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxDetails>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}/scripts",
+        summary: "Input and output scripts of a tx decoded into opcodes",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxScripts>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}/flows",
+        summary: "Net flow of each address in a tx",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxFlows>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}/merkle-proof",
+        summary: "Merkle proof of the inclusion of a mined tx",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonMerkleProof>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}/confirmations",
+        summary: "Block and confirmations of a tx",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonTxConfirmations>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/tx/{hash}/graph",
+        summary: "Ancestor and descendant txs of a tx",
+        query: &[("depth", "Spends away from the tx, default 2, at most 4")],
+        request: None,
+        response: Json(schema::<JsonTxGraph>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/tx/package",
+        summary: "Broadcasts up to 50 dependent raw txs in order",
+        query: &[],
+        request: Some(schema::<JsonTxPackageRequest>),
+        response: Json(schema::<JsonTxPackageResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/search/suggest",
+        summary: "Typed suggestions for a block height, hash or address",
+        query: &[("q", "Search query")],
+        request: None,
+        response: Json(schema::<JsonSearchSuggestionsResponse>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/me/usage",
+        summary: "Quota and usage of the API client",
+        query: &[],
+        request: None,
+        response: Json(schema::<JsonApiUsage>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/openapi.json",
+        summary: "This OpenAPI document",
+        query: &[],
+        request: None,
+        response: Json(schema::<Value>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/admin/labels",
+        summary: "Sets or removes the label of an address",
+        query: &[],
+        request: Some(schema::<JsonAddressLabelRequest>),
+        response: Json(schema::<JsonAddressLabelRequest>),
+    },
+    Endpoint {
+        method: "get",
+        path: "/api/admin/webhooks",
+        summary: "Webhooks of address activity",
+        query: &[],
+        request: None,
+        response: Json(schema::<Vec<JsonWebhook>>),
+    },
+    Endpoint {
+        method: "post",
+        path: "/api/admin/webhooks",
+        summary: "Adds a webhook, which is lost on restart",
+        query: &[],
+        request: Some(schema::<JsonWebhookRequest>),
+        response: Json(schema::<JsonWebhook>),
+    },
+    Endpoint {
+        method: "delete",
+        path: "/api/admin/webhooks/{id}",
+        summary: "Removes a webhook",
+        query: &[],
+        request: None,
+        response: NoContent,
+    },
+];
+
+/// Admin endpoints require the `admin_token` as a bearer token
+const ADMIN_PATH_PREFIX: &str = "/api/admin/";
+
+/// The OpenAPI 3 document, with the explorer at `server_url`
+pub fn openapi_spec(server_url: &str) -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    for endpoint in ENDPOINTS {
+        let path_params = endpoint
+            .path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}));
+        let query_params = endpoint.query.iter().map(|(name, description)| {
+            json!({"name": name, "in": "query", "description": description, "schema": {"type": "string"}})
+        });
+
+        let (status, response) = match &endpoint.response {
+            Json(schema) => (
+                "200",
+                json!({
+                    "description": "OK",
+                    "content": {"application/json": {"schema": schema(&mut gen)}},
+                }),
+            ),
+            Other {
+                content_type,
+                description,
+            } => (
+                "200",
+                json!({
+                    "description": description,
+                    "content": {*content_type: {"schema": {"type": "string"}}},
+                }),
+            ),
+            NoContent => ("204", json!({"description": "No Content"})),
+        };
+
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "parameters": path_params.chain(query_params).collect::<Vec<_>>(),
+            "responses": {
+                status: response,
+                "400": {"description": "Invalid request"},
+                "429": {"description": "API quota exceeded"},
+            },
+        });
+        if endpoint.path.starts_with(ADMIN_PATH_PREFIX) {
+            operation["security"] = json!([{"adminToken": []}]);
+            operation["responses"]["401"] = json!({"description": "Missing or wrong admin token"});
+        }
+        if let Some(request) = endpoint.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": {"application/json": {"schema": request(&mut gen)}},
+            });
+        }
+
+        let path_item = paths
+            .entry(endpoint.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path_item[endpoint.method] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "eCash Explorer API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{"url": server_url}],
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {"adminToken": {"type": "http", "scheme": "bearer"}},
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{openapi_spec, ENDPOINTS};
+
+    /// The path with its parameters replaced by `*`, as the router and the
+    /// spec name them differently
+    fn path_shape(path: &str) -> String {
+        path.split('/')
+            .map(|segment| {
+                if segment.starts_with(':') || segment.starts_with('{') {
+                    "*"
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn test_all_api_routes_documented() {
+        let server_source = include_str!("server.rs");
+        let router = &server_source[server_source.find("pub fn router(").unwrap()..];
+        let router = &router[..router.find(".fallback(").unwrap()];
+        let api_routes = router
+            .split('"')
+            .filter(|literal| literal.starts_with("/api/"))
+            .collect::<Vec<_>>();
+        assert!(!api_routes.is_empty());
+
+        let documented = ENDPOINTS
+            .iter()
+            .map(|endpoint| path_shape(endpoint.path))
+            .collect::<HashSet<_>>();
+        for route in api_routes {
+            assert!(
+                documented.contains(&path_shape(route)),
+                "{} is missing from ENDPOINTS",
+                route,
+            );
+        }
+    }
+
+    #[test]
+    fn test_openapi_spec() {
+        let spec = openapi_spec("https://explorer.e.cash");
+        let paths = spec["paths"].as_object().unwrap();
+        let num_paths = ENDPOINTS
+            .iter()
+            .map(|endpoint| endpoint.path)
+            .collect::<HashSet<_>>()
+            .len();
+        assert_eq!(paths.len(), num_paths);
+
+        let webhooks = &paths["/api/admin/webhooks"];
+        assert!(webhooks["get"].is_object());
+        assert!(webhooks["post"]["requestBody"].is_object());
+        assert_eq!(
+            webhooks["post"]["security"][0]["adminToken"],
+            serde_json::json!([])
+        );
+        let remove_webhook = &paths["/api/admin/webhooks/{id}"]["delete"];
+        assert!(remove_webhook["responses"]["204"].is_object());
+        let raw_block = &paths["/api/block/{hash}/raw"]["get"];
+        assert!(raw_block["responses"]["200"]["content"]["application/octet-stream"].is_object());
+        assert!(paths["/api/tx/{hash}"]["get"]["security"].is_null());
+    }
+}
@@ -0,0 +1,198 @@
+//! Page links of paginated listings. They're rendered by the server so
+//! listings can be paged without JavaScript; common.js re-renders them to fit
+//! the screen width.
+
+use std::collections::BTreeSet;
+
+/// Rows per page if the `rows` query parameter isn't set, as in common.js
+pub const DEFAULT_ROWS_PER_PAGE: u32 = 100;
+/// Number of page links around the current page
+pub const DEFAULT_PAGE_SLOTS: usize = 8;
+
+/// Offsets from the current page that can be linked, nearest first. Pages
+/// far from the current page are rounded, so they link to round page numbers.
+const PAGE_OFFSETS: &[u32] = &[1, 2, 10, 50, 100, 500, 1000, 2000, 4000];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pagination {
+    /// 1-based, like the `page` query parameter
+    pub current_page: u32,
+    pub num_pages: u32,
+    /// Linked pages in ascending order, including the first and last page
+    pub pages: Vec<u32>,
+    /// URL of the listing with all query parameters except `page`
+    base_url: String,
+}
+
+impl Pagination {
+    /// Pagination of `num_entries` entries with `rows` per page. Page links
+    /// keep all params of `raw_query` except `page`.
+    pub fn from_query(
+        path: &str,
+        raw_query: Option<&str>,
+        current_page: Option<u32>,
+        rows: Option<u32>,
+        num_entries: u32,
+    ) -> Self {
+        let current_page = current_page.map_or(1, |page| page.max(1));
+        let rows = rows.map_or(DEFAULT_ROWS_PER_PAGE, |rows| rows.max(1));
+
+        let mut params = raw_query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| !param.is_empty() && param.split('=').next() != Some("page"))
+            .map(|param| param.to_string())
+            .collect::<Vec<_>>();
+        params.sort();
+        params.push(String::new());
+        let base_url = format!("{}?{}", path, params.join("&"));
+
+        Pagination::new(current_page, num_entries, rows, base_url)
+    }
+
+    pub fn new(current_page: u32, num_entries: u32, rows: u32, base_url: String) -> Self {
+        let rows = u64::from(rows.max(1));
+        let num_pages = ((u64::from(num_entries) + rows - 1) / rows) as u32;
+        let current_page = current_page.clamp(1, num_pages.max(1));
+        Pagination {
+            current_page,
+            num_pages,
+            pages: page_window(current_page, num_pages, DEFAULT_PAGE_SLOTS),
+            base_url,
+        }
+    }
+
+    pub fn page_url(&self, page: u32) -> String {
+        format!("{}page={}", self.base_url, page)
+    }
+}
+
+/// Pages to link for `current_page` out of `num_pages`, with about `slots`
+/// pages around the current page. All pages are linked if they fit, and
+/// none if there's only one page.
+pub fn page_window(current_page: u32, num_pages: u32, slots: usize) -> Vec<u32> {
+    if num_pages <= 1 {
+        return Vec::new();
+    }
+    if num_pages as usize <= slots + 2 {
+        return (1..=num_pages).collect();
+    }
+
+    let mut pages = BTreeSet::new();
+    pages.insert(1);
+    pages.insert(current_page);
+    pages.insert(num_pages);
+    for offset in page_offsets(num_pages, slots / 2) {
+        if let Some(page) = current_page.checked_sub(offset) {
+            pages.insert(round_page(page, offset));
+        }
+        if let Some(page) = current_page.checked_add(offset) {
+            pages.insert(round_page(page, offset));
+        }
+    }
+    pages
+        .into_iter()
+        .filter(|&page| page >= 1 && page <= num_pages)
+        .collect()
+}
+
+/// Up to `count` offsets to link on each side of the current page. Offsets
+/// that would always leave the listing are skipped, and if more remain than
+/// fit, the nearest and the farthest are kept with the others spread between
+/// them, so large listings can still be crossed in a few clicks.
+fn page_offsets(num_pages: u32, count: usize) -> Vec<u32> {
+    let usable = PAGE_OFFSETS
+        .iter()
+        .copied()
+        .filter(|&offset| offset < num_pages)
+        .collect::<Vec<_>>();
+    if usable.len() <= count {
+        return usable;
+    }
+    if count <= 1 {
+        return usable[..count].to_vec();
+    }
+    (0..count)
+        .map(|idx| usable[idx * (usable.len() - 1) / (count - 1)])
+        .collect()
+}
+
+/// Rounds `page` down to the order of magnitude of `offset`, e.g. 1234 with an
+/// offset of 100 to 1200. Near pages are kept as they are.
+fn round_page(page: u32, offset: u32) -> u32 {
+    if offset < 10 {
+        return page;
+    }
+    let mut magnitude = 1;
+    while magnitude * 10 <= offset {
+        magnitude *= 10;
+    }
+    page - page % magnitude
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{page_offsets, page_window, Pagination, DEFAULT_PAGE_SLOTS};
+
+    #[test]
+    fn test_single_page() {
+        assert_eq!(page_window(1, 0, DEFAULT_PAGE_SLOTS), Vec::<u32>::new());
+        assert_eq!(page_window(1, 1, DEFAULT_PAGE_SLOTS), Vec::<u32>::new());
+        let pagination = Pagination::new(3, 40, 100, "/blocks?".to_string());
+        assert_eq!(pagination.current_page, 1);
+        assert_eq!(pagination.num_pages, 1);
+        assert!(pagination.pages.is_empty());
+    }
+
+    #[test]
+    fn test_all_pages_fit() {
+        assert_eq!(
+            page_window(3, 10, DEFAULT_PAGE_SLOTS),
+            (1..=10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_first_page() {
+        assert_eq!(
+            page_window(1, 1000, DEFAULT_PAGE_SLOTS),
+            vec![1, 2, 3, 50, 500, 1000],
+        );
+    }
+
+    #[test]
+    fn test_last_page() {
+        assert_eq!(
+            page_window(1000, 1000, DEFAULT_PAGE_SLOTS),
+            vec![1, 500, 950, 998, 999, 1000],
+        );
+        let pagination = Pagination::new(20, 250, 100, "/blocks?".to_string());
+        assert_eq!(pagination.current_page, 3);
+        assert_eq!(pagination.pages, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_far_pages() {
+        assert_eq!(page_offsets(100_000, 4), vec![1, 10, 500, 4000]);
+        assert_eq!(
+            page_window(50_000, 100_000, DEFAULT_PAGE_SLOTS),
+            vec![1, 46000, 49500, 49990, 49999, 50000, 50001, 50010, 50500, 54000, 100_000],
+        );
+    }
+
+    #[test]
+    fn test_page_url() {
+        let pagination = Pagination::from_query(
+            "/address/ecash:qq",
+            Some("page=3&rows=50&b=1"),
+            Some(3),
+            Some(50),
+            1000,
+        );
+        assert_eq!(pagination.num_pages, 20);
+        assert_eq!(
+            pagination.page_url(2),
+            "/address/ecash:qq?b=1&rows=50&page=2"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use bitcoinsuite_error::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use eyre::{eyre, WrapErr};
+
+/// Days without a close, e.g. missing from the file, fall back to the last
+/// close before them, but only this many days back, so a file that wasn't
+/// updated doesn't price new txs
+const MAX_PRICE_AGE_DAYS: i64 = 7;
+
+/// Daily XEC/USD closing prices, used to show what a tx was worth at the
+/// time. They're loaded from a CSV file with lines of `YYYY-MM-DD,close`,
+/// optionally with a header line.
+#[derive(Debug, Default)]
+pub struct PriceHistory {
+    closes: BTreeMap<NaiveDate, f64>,
+}
+
+impl PriceHistory {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(PriceHistory::default()),
+        };
+        let csv = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read price history file {}", path.display()))?;
+        PriceHistory::parse(&csv)
+    }
+
+    /// Parses lines of `YYYY-MM-DD,close`. A first line starting with a letter
+    /// is taken as the header and skipped.
+    pub fn parse(csv: &str) -> Result<Self> {
+        let mut closes = BTreeMap::new();
+        for (line_idx, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || (line_idx == 0 && line.starts_with(char::is_alphabetic)) {
+                continue;
+            }
+            let (date, close) = line.split_once(',').ok_or_else(|| {
+                eyre!(
+                    "Line {} of the price history file has no comma",
+                    line_idx + 1
+                )
+            })?;
+            let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").wrap_err_with(|| {
+                format!(
+                    "Invalid date on line {} of the price history file",
+                    line_idx + 1
+                )
+            })?;
+            let close = close.trim().parse::<f64>().wrap_err_with(|| {
+                format!(
+                    "Invalid price on line {} of the price history file",
+                    line_idx + 1
+                )
+            })?;
+            closes.insert(date, close);
+        }
+
+        Ok(PriceHistory { closes })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.closes.is_empty()
+    }
+
+    /// Closing price in USD per XEC of the UTC day of `timestamp`, or of the
+    /// nearest earlier day with a close
+    pub fn close_at(&self, timestamp: i64) -> Option<f64> {
+        let date = Utc.timestamp(timestamp, 0).date().naive_utc();
+        let (close_date, close) = self.closes.range(..=date).next_back()?;
+        if (date - *close_date).num_days() > MAX_PRICE_AGE_DAYS {
+            return None;
+        }
+        Some(*close)
+    }
+
+    /// USD value of `sats` at the closing price of the day of `timestamp`
+    pub fn value_at(&self, sats: i64, timestamp: i64) -> Option<f64> {
+        Some(sats as f64 / 100.0 * self.close_at(timestamp)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PriceHistory;
+
+    /// 2021-07-01T00:00:00Z
+    const JULY_1: i64 = 1_625_097_600;
+    const DAY: i64 = 24 * 3600;
+
+    #[test]
+    fn test_parse_valid_rows() {
+        let prices =
+            PriceHistory::parse("date,close\n2021-07-01,0.00004\n\n 2021-07-02 , 0.00005 \n")
+                .unwrap();
+        assert!(!prices.is_empty());
+        assert_eq!(prices.close_at(JULY_1), Some(0.00004));
+        assert_eq!(prices.close_at(JULY_1 + DAY + 3600), Some(0.00005));
+        assert_eq!(prices.value_at(100_000_000, JULY_1), Some(40.0));
+
+        let prices = PriceHistory::parse("2021-07-01,0.00004\n").unwrap();
+        assert_eq!(prices.close_at(JULY_1), Some(0.00004));
+        assert!(PriceHistory::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_bad_header() {
+        // Only a first line starting with a letter is a header
+        assert!(PriceHistory::parse("\"date\",\"close\"\n2021-07-01,0.00004\n").is_err());
+        assert!(PriceHistory::parse("2021-07-01,0.00004\ndate,close\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_bad_rows() {
+        let err = PriceHistory::parse("date,close\n2021-07-01 0.00004\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 2 of the price history file has no comma",
+        );
+        let err = PriceHistory::parse("2021-07-01,0.00004\n2021-13-01,0.00004\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid date on line 2 of the price history file",
+        );
+        let err = PriceHistory::parse("2021-07-01,cheap\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid price on line 1 of the price history file",
+        );
+    }
+
+    #[test]
+    fn test_close_at_nearest_earlier_day() {
+        let prices = PriceHistory::parse("2021-07-01,0.00004\n2021-07-05,0.00005\n").unwrap();
+        // Before the first close
+        assert_eq!(prices.close_at(JULY_1 - 1), None);
+        // Days missing from the file use the last close before them
+        assert_eq!(prices.close_at(JULY_1 + 3 * DAY), Some(0.00004));
+        assert_eq!(prices.close_at(JULY_1 + 4 * DAY), Some(0.00005));
+        // Up to a week after the last close
+        assert_eq!(prices.close_at(JULY_1 + 11 * DAY), Some(0.00005));
+        assert_eq!(prices.close_at(JULY_1 + 12 * DAY), None);
+    }
+}
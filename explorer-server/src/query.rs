@@ -0,0 +1,258 @@
+//! Typed query parameters. Each route deserializes its params into a struct
+//! with [`ValidQuery`], which rejects invalid params with status 400 and a
+//! message naming the param. Optional params are `None` if missing or empty.
+
+use std::fmt;
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Query, RequestParts},
+};
+use serde::{
+    de::{self, value::MapDeserializer, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+
+use crate::server_error::{to_server_error, BadRequest, ServerError};
+
+/// Extracts the query params as `T`
+pub struct ValidQuery<T>(pub T);
+
+#[async_trait]
+impl<T, B> FromRequest<B> for ValidQuery<T>
+where
+    T: de::DeserializeOwned,
+    B: Send,
+{
+    type Rejection = ServerError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<Vec<(String, String)>>::from_request(req)
+            .await
+            .map_err(|err| to_server_error(BadRequest(err.to_string())))?;
+        T::deserialize(ParamsDeserializer { params: &params })
+            .map(ValidQuery)
+            .map_err(|err| to_server_error(BadRequest(err.to_string())))
+    }
+}
+
+/// Params of pages that only take the display unit
+#[derive(Deserialize)]
+pub struct UnitQuery {
+    pub unit: Option<String>,
+}
+
+/// Params of paginated pages. `page` is 1-based, `rows` is the page size.
+#[derive(Deserialize)]
+pub struct PageQuery {
+    pub unit: Option<String>,
+    pub page: Option<u32>,
+    pub rows: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct AddressQuery {
+    pub unit: Option<String>,
+    pub page: Option<u32>,
+    pub rows: Option<u32>,
+    /// Only count txs of this token, as hex
+    pub token: Option<String>,
+    /// Date as YYYY-MM-DD, inclusive
+    pub from: Option<String>,
+    /// Date as YYYY-MM-DD, inclusive
+    pub to: Option<String>,
+}
+
+/// Params of the tx history API. `page` is 0-based, `take` is the page size.
+#[derive(Deserialize)]
+pub struct AddressTxsQuery {
+    pub page: Option<usize>,
+    pub take: Option<usize>,
+    pub token: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UtxosQuery {
+    pub page: Option<usize>,
+    pub take: Option<usize>,
+    pub sort: Option<UtxoSort>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum UtxoSort {
+    /// Largest first
+    Value,
+    /// Oldest first
+    Age,
+}
+
+#[derive(Deserialize)]
+pub struct WidgetQuery {
+    pub unit: Option<String>,
+    pub theme: Option<WidgetTheme>,
+    /// Hex color, e.g. `0074c2`
+    pub accent: Option<String>,
+    /// Address whose received amount the tx widget shows
+    pub address: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum WidgetTheme {
+    Light,
+    Dark,
+}
+
+#[derive(Deserialize)]
+pub struct QrQuery {
+    pub amount: Option<String>,
+    pub label: Option<String>,
+    pub size: Option<usize>,
+    pub ecc: Option<QrEcc>,
+    pub format: Option<QrFormat>,
+}
+
+/// Error correction level of QR codes
+#[derive(Deserialize, Clone, Copy)]
+pub enum QrEcc {
+    L,
+    M,
+    Q,
+    H,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    Png,
+    Svg,
+}
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChartQuery {
+    pub days: Option<i32>,
+}
+
+/// Inclusive range of block heights
+#[derive(Deserialize)]
+pub struct BlockRangeQuery {
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct TxGraphQuery {
+    pub depth: Option<i32>,
+}
+
+/// Deserializes a struct from query params. Unlike deserializing them with
+/// axum's `Query`, errors name the param they're about.
+struct ParamsDeserializer<'a> {
+    params: &'a [(String, String)],
+}
+
+impl<'de, 'a> Deserializer<'de> for ParamsDeserializer<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(MapDeserializer::new(self.params.iter().map(
+            |(key, value)| {
+                let param = ParamDeserializer { key, value };
+                (key.as_str(), param)
+            },
+        )))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Deserializes the value of a single param, which is parsed from its string
+#[derive(Clone, Copy)]
+struct ParamDeserializer<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+
+impl<'a> ParamDeserializer<'a> {
+    fn error(self, err: impl fmt::Display) -> de::value::Error {
+        de::Error::custom(format!("Invalid query parameter {}: {}", self.key, err))
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, de::value::Error> for ParamDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match self.value.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(err) => Err(self.error(err)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for ParamDeserializer<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.value).map_err(|err| self.error(err))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let variant: de::value::StrDeserializer<de::value::Error> = self.value.into_deserializer();
+        visitor.visit_enum(variant).map_err(|err| self.error(err))
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
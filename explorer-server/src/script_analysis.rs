@@ -0,0 +1,390 @@
+//! Recognizes common redeem script templates of P2SH inputs, so the tx page can
+//! show what kind of contract an input spends.
+
+use crate::blockchain::{describe_lock_target, describe_sequence};
+
+const OP_0: u8 = 0x00;
+pub(crate) const OP_PUSHDATA1: u8 = 0x4c;
+pub(crate) const OP_PUSHDATA2: u8 = 0x4d;
+pub(crate) const OP_PUSHDATA4: u8 = 0x4e;
+const OP_1NEGATE: u8 = 0x4f;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_EQUAL: u8 = 0x87;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    Push(&'a [u8]),
+    Op(u8),
+}
+
+/// Splits a script into pushes and other opcodes, or returns `None` if a push
+/// runs past the end of the script.
+pub fn parse_script(script: &[u8]) -> Option<Vec<Instruction<'_>>> {
+    let (ops, is_malformed) = parse_script_ops(script);
+    if is_malformed {
+        return None;
+    }
+    Some(
+        ops.into_iter()
+            .map(|(_, instruction)| instruction)
+            .collect(),
+    )
+}
+
+/// Like [`parse_script`], but also returns the opcode of pushes. Stops at a
+/// push running past the end of the script, which is flagged as malformed.
+pub fn parse_script_ops(script: &[u8]) -> (Vec<(u8, Instruction<'_>)>, bool) {
+    let mut ops = Vec::new();
+    let mut idx = 0;
+    while idx < script.len() {
+        let opcode = script[idx];
+        idx += 1;
+        let push_len = match opcode {
+            0x01..=0x4b => Some(opcode as usize),
+            OP_PUSHDATA1 => read_len(script, &mut idx, 1),
+            OP_PUSHDATA2 => read_len(script, &mut idx, 2),
+            OP_PUSHDATA4 => read_len(script, &mut idx, 4),
+            _ => {
+                ops.push((opcode, Instruction::Op(opcode)));
+                continue;
+            }
+        };
+        let data = push_len.and_then(|push_len| script.get(idx..idx.checked_add(push_len)?));
+        match data {
+            Some(data) => {
+                ops.push((opcode, Instruction::Push(data)));
+                idx += data.len();
+            }
+            None => return (ops, true),
+        }
+    }
+    (ops, false)
+}
+
+fn read_len(script: &[u8], idx: &mut usize, num_bytes: usize) -> Option<usize> {
+    let bytes = script.get(*idx..*idx + num_bytes)?;
+    *idx += num_bytes;
+    Some(
+        bytes
+            .iter()
+            .rev()
+            .fold(0usize, |len, &byte| (len << 8) | byte as usize),
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Timelock {
+    /// OP_CHECKLOCKTIMEVERIFY, a block height or timestamp
+    Absolute(u32),
+    /// OP_CHECKSEQUENCEVERIFY, a BIP68 relative lock time
+    Relative(u32),
+}
+
+impl Timelock {
+    pub fn describe(&self) -> String {
+        match *self {
+            Timelock::Absolute(lock_time) => {
+                format!("Not spendable before {}", describe_lock_target(lock_time))
+            }
+            Timelock::Relative(sequence) => describe_sequence(sequence, 2),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RedeemScriptKind {
+    /// m-of-n OP_CHECKMULTISIG
+    Multisig {
+        required: usize,
+        total: usize,
+    },
+    /// Any script containing OP_CHECKLOCKTIMEVERIFY or OP_CHECKSEQUENCEVERIFY,
+    /// e.g. an escrow that can be refunded after a timeout
+    Timelocked,
+    /// A script of only a hash lock, e.g. an atomic swap leg
+    HashLocked,
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedeemScriptAnalysis {
+    pub kind: RedeemScriptKind,
+    pub redeem_script: Vec<u8>,
+    /// Public keys the script checks signatures against, in script order
+    pub pubkeys: Vec<Vec<u8>>,
+    pub timelocks: Vec<Timelock>,
+}
+
+impl RedeemScriptAnalysis {
+    pub fn describe(&self) -> String {
+        match self.kind {
+            RedeemScriptKind::Multisig { required, total } if self.timelocks.is_empty() => {
+                format!("{}-of-{} multisig", required, total)
+            }
+            RedeemScriptKind::Multisig { required, total } => {
+                format!("Timelocked {}-of-{} multisig", required, total)
+            }
+            RedeemScriptKind::Timelocked => "Timelocked script".to_string(),
+            RedeemScriptKind::HashLocked => "Hash locked script".to_string(),
+            RedeemScriptKind::Unknown => "Custom script".to_string(),
+        }
+    }
+
+    pub fn timelock_descriptions(&self) -> Vec<String> {
+        self.timelocks.iter().map(Timelock::describe).collect()
+    }
+}
+
+/// Analyzes the redeem script of an input spending a P2SH output, which is the
+/// last push of its input script. Returns `None` for other inputs.
+pub fn analyze_p2sh_input(
+    output_script: &[u8],
+    input_script: &[u8],
+) -> Option<RedeemScriptAnalysis> {
+    if !matches!(output_script, [OP_HASH160, 20, .., OP_EQUAL] if output_script.len() == 23) {
+        return None;
+    }
+    let redeem_script = match parse_script(input_script)?.last()? {
+        Instruction::Push(redeem_script) => *redeem_script,
+        Instruction::Op(_) => return None,
+    };
+    Some(analyze_redeem_script(redeem_script))
+}
+
+pub fn analyze_redeem_script(redeem_script: &[u8]) -> RedeemScriptAnalysis {
+    let instructions = match parse_script(redeem_script) {
+        Some(instructions) => instructions,
+        None => {
+            return RedeemScriptAnalysis {
+                kind: RedeemScriptKind::Unknown,
+                redeem_script: redeem_script.to_vec(),
+                pubkeys: vec![],
+                timelocks: vec![],
+            }
+        }
+    };
+
+    let pubkeys = instructions
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Push(data) if is_pubkey(data) => Some(data.to_vec()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut timelocks = Vec::new();
+    for window in instructions.windows(2) {
+        let lock = match window[0] {
+            Instruction::Push(data) => decode_script_num(data),
+            Instruction::Op(opcode) => small_int(opcode).map(|int| int as i64),
+        };
+        match (lock, window[1]) {
+            (Some(lock), Instruction::Op(OP_CHECKLOCKTIMEVERIFY)) if lock >= 0 => {
+                timelocks.push(Timelock::Absolute(lock as u32))
+            }
+            (Some(lock), Instruction::Op(OP_CHECKSEQUENCEVERIFY)) if lock >= 0 => {
+                timelocks.push(Timelock::Relative(lock as u32))
+            }
+            _ => {}
+        }
+    }
+
+    let kind = if let Some((required, total)) = find_multisig(&instructions) {
+        RedeemScriptKind::Multisig { required, total }
+    } else if !timelocks.is_empty() {
+        RedeemScriptKind::Timelocked
+    } else if instructions.contains(&Instruction::Op(OP_HASH160)) && pubkeys.is_empty() {
+        RedeemScriptKind::HashLocked
+    } else {
+        RedeemScriptKind::Unknown
+    };
+
+    RedeemScriptAnalysis {
+        kind,
+        redeem_script: redeem_script.to_vec(),
+        pubkeys,
+        timelocks,
+    }
+}
+
+/// Finds `OP_m <pubkey>... OP_n OP_CHECKMULTISIG(VERIFY)` in the script.
+fn find_multisig(instructions: &[Instruction]) -> Option<(usize, usize)> {
+    let checkmultisig_idx = instructions.iter().position(|instruction| {
+        matches!(
+            instruction,
+            Instruction::Op(OP_CHECKMULTISIG) | Instruction::Op(OP_CHECKMULTISIGVERIFY)
+        )
+    })?;
+    let total = match instructions.get(checkmultisig_idx.checked_sub(1)?)? {
+        Instruction::Op(opcode) => small_int(*opcode)? as usize,
+        Instruction::Push(_) => return None,
+    };
+    let pubkeys_start = checkmultisig_idx.checked_sub(1 + total)?;
+    let pubkeys = &instructions[pubkeys_start..checkmultisig_idx - 1];
+    if !pubkeys
+        .iter()
+        .all(|instruction| matches!(instruction, Instruction::Push(data) if is_pubkey(data)))
+    {
+        return None;
+    }
+    let required = match instructions.get(pubkeys_start.checked_sub(1)?)? {
+        Instruction::Op(opcode) => small_int(*opcode)? as usize,
+        Instruction::Push(_) => return None,
+    };
+    if required == 0 || required > total {
+        return None;
+    }
+    Some((required, total))
+}
+
+fn is_pubkey(data: &[u8]) -> bool {
+    matches!(
+        (data.len(), data.first()),
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04))
+    )
+}
+
+/// Value of OP_0, OP_1NEGATE and OP_1 to OP_16
+pub(crate) fn small_int(opcode: u8) -> Option<i32> {
+    match opcode {
+        OP_0 => Some(0),
+        OP_1NEGATE => Some(-1),
+        OP_1..=OP_16 => Some((opcode - OP_1 + 1) as i32),
+        _ => None,
+    }
+}
+
+/// Decodes a minimally encoded script number of up to 5 bytes, as used for
+/// lock times and coinbase heights.
+pub(crate) fn decode_script_num(data: &[u8]) -> Option<i64> {
+    if data.is_empty() || data.len() > 5 {
+        return None;
+    }
+    let mut num = data
+        .iter()
+        .rev()
+        .fold(0i64, |num, &byte| (num << 8) | byte as i64);
+    let sign_bit = 0x80i64 << (8 * (data.len() - 1));
+    if num & sign_bit != 0 {
+        num = -(num & !sign_bit);
+    }
+    Some(num)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script_pushes_and_ops() {
+        let script = [OP_0, 0x02, 0xaa, 0xbb, OP_1, OP_HASH160];
+        assert_eq!(
+            parse_script(&script),
+            Some(vec![
+                Instruction::Op(OP_0),
+                Instruction::Push(&[0xaa, 0xbb]),
+                Instruction::Op(OP_1),
+                Instruction::Op(OP_HASH160),
+            ]),
+        );
+        assert_eq!(parse_script(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_parse_script_pushdata() {
+        let mut script = vec![OP_PUSHDATA1, 0x03, 1, 2, 3];
+        script.extend([OP_PUSHDATA2, 0x02, 0x00, 4, 5]);
+        script.extend([OP_PUSHDATA4, 0x01, 0x00, 0x00, 0x00, 6]);
+        assert_eq!(
+            parse_script_ops(&script),
+            (
+                vec![
+                    (OP_PUSHDATA1, Instruction::Push(&[1, 2, 3])),
+                    (OP_PUSHDATA2, Instruction::Push(&[4, 5])),
+                    (OP_PUSHDATA4, Instruction::Push(&[6])),
+                ],
+                false,
+            ),
+        );
+
+        // Lengths are little-endian
+        let mut script = vec![OP_PUSHDATA2, 0x00, 0x01];
+        script.extend([0xcc; 256]);
+        assert_eq!(
+            parse_script(&script),
+            Some(vec![Instruction::Push(&[0xcc; 256])])
+        );
+    }
+
+    #[test]
+    fn test_parse_script_truncated() {
+        // Push longer than the rest of the script
+        assert_eq!(
+            parse_script_ops(&[OP_1, 0x03, 1, 2]),
+            (vec![(OP_1, Instruction::Op(OP_1))], true),
+        );
+        assert_eq!(parse_script(&[0x03, 1, 2]), None);
+        // Length of the push cut off
+        assert_eq!(parse_script(&[OP_PUSHDATA1]), None);
+        assert_eq!(parse_script(&[OP_PUSHDATA2, 0x01]), None);
+        assert_eq!(parse_script(&[OP_PUSHDATA4, 0x01, 0x00, 0x00]), None);
+        // Length overflowing the script
+        assert_eq!(parse_script(&[OP_PUSHDATA4, 0xff, 0xff, 0xff, 0xff]), None);
+    }
+
+    #[test]
+    fn test_decode_script_num() {
+        assert_eq!(decode_script_num(&[]), None);
+        assert_eq!(decode_script_num(&[0x01]), Some(1));
+        assert_eq!(decode_script_num(&[0x7f]), Some(127));
+        assert_eq!(decode_script_num(&[0x80, 0x00]), Some(128));
+        assert_eq!(decode_script_num(&[0x81]), Some(-1));
+        assert_eq!(decode_script_num(&[0x80, 0x80]), Some(-128));
+        assert_eq!(
+            decode_script_num(&[0xff, 0xff, 0xff, 0x7f]),
+            Some(0x7fff_ffff)
+        );
+        // Lock times above 2^31 need 5 bytes
+        assert_eq!(
+            decode_script_num(&[0x00, 0x00, 0x00, 0x80, 0x00]),
+            Some(0x8000_0000),
+        );
+        assert_eq!(decode_script_num(&[0x00; 6]), None);
+    }
+
+    #[test]
+    fn test_small_int() {
+        assert_eq!(small_int(OP_0), Some(0));
+        assert_eq!(small_int(OP_1NEGATE), Some(-1));
+        assert_eq!(small_int(OP_1), Some(1));
+        assert_eq!(small_int(OP_16), Some(16));
+        assert_eq!(small_int(OP_HASH160), None);
+    }
+
+    #[test]
+    fn test_analyze_redeem_script_multisig() {
+        let pubkey = [0x02; 33];
+        let mut script = vec![0x52];
+        for _ in 0..3 {
+            script.push(33);
+            script.extend(pubkey);
+        }
+        script.extend([0x53, OP_CHECKMULTISIG]);
+        let analysis = analyze_redeem_script(&script);
+        assert_eq!(
+            analysis.kind,
+            RedeemScriptKind::Multisig {
+                required: 2,
+                total: 3,
+            },
+        );
+        assert_eq!(analysis.pubkeys, vec![pubkey.to_vec(); 3]);
+        assert_eq!(analysis.describe(), "2-of-3 multisig");
+    }
+}
@@ -1,51 +1,333 @@
 use askama::Template;
-use axum::{response::Redirect, routing::get, Router};
-use bitcoinsuite_chronik_client::proto::{SlpTokenType, SlpTxType, Token, Utxo};
-use bitcoinsuite_chronik_client::{proto::OutPoint, ChronikClient};
-use bitcoinsuite_core::{CashAddress, Hashed, Sha256d};
+use axum::{
+    body::Bytes,
+    http::HeaderMap,
+    response::Redirect,
+    routing::{delete, get, post},
+    Router,
+};
+use bitcoinsuite_chronik_client::proto::{
+    Block, BlockInfo, SlpTokenType, SlpTxType, Token, Tx, Utxo,
+};
+use bitcoinsuite_chronik_client::{ChronikClient, ScriptType};
+use bitcoinsuite_core::{AddressType, CashAddress, Hashed, Sha256d};
 use bitcoinsuite_error::Result;
-use chrono::{TimeZone, Utc};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
 use eyre::{bail, eyre};
-use futures::future;
-use std::path::PathBuf;
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use serde_json::json;
 use std::{
     borrow::Cow,
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+};
+use std::{
+    future::Future,
+    net::IpAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
 };
+use tokio::sync::{broadcast, watch};
 
 use crate::{
-    api::{block_txs_to_json, calc_tx_stats, tokens_to_json, tx_history_to_json},
+    admin_auth::{AdminAuth, AdminCheck},
+    alp::parse_alp_sections,
+    api::{
+        block_info_to_json, block_structured_data, block_txs_to_json, calc_block_fee_stats,
+        calc_block_token_summary, calc_output_type_counts, calc_token_burns, calc_tx_flows,
+        calc_tx_size_breakdown, calc_tx_stats, calc_tx_warnings, coinbase_to_json, script_to_json,
+        sum_output_type_counts, token_meta_to_json, tokens_to_json, tx_confirmation_delay_secs,
+        tx_details_to_json, tx_history_to_json, tx_structured_data, tx_time_first_seen,
+    },
+    api_keys::{ApiQuotas, QuotaCheck},
     blockchain::{
-        calculate_block_difficulty, cash_addr_to_script_type_payload, from_be_hex, to_be_hex,
-        to_legacy_address,
+        calculate_block_difficulty, cash_addr_to_script_type_payload, check_bulk_addresses,
+        describe_lock_time, destination_from_script, estimate_hashrate, from_be_hex,
+        input_script_pubkey, merkle_branch, p2pk_script, payment_uri, pubkey_address, reverse_hex,
+        script_type_payload, to_be_hex, to_legacy_address, Destination, MempoolChain, MintBaton,
+        BLOCKS_PER_DAY, MIN_RELAY_FEE_RATE,
+    },
+    chainwork::{block_work, ChainworkIndex},
+    config::{LimitsConfig, MaintenanceConfig, Network},
+    events::{
+        event_channel, new_tx_json, BlockReceiveTimes, ExplorerEvent, Poller, CONFIRMATION_DEPTH,
+    },
+    forks::{ForkMonitor, FORK_ALERT_DEPTH},
+    identicon::identicon_png,
+    labels::{label_key, AddressLabels},
+    locale::{Denomination, DisplayLocale},
+    network_stats::NetworkStats,
+    pagination::{Pagination, DEFAULT_ROWS_PER_PAGE},
+    prices::PriceHistory,
+    query::{
+        AddressQuery, AddressTxsQuery, BlockRangeQuery, ChartQuery, PageQuery, QrEcc, QrFormat,
+        QrQuery, SearchQuery, TxGraphQuery, UtxoSort, UtxosQuery, WidgetQuery, WidgetTheme,
     },
+    server_error::BadRequest,
     server_http::{
-        address, address_qr, block, block_height, blocks, data_address_txs, data_block_txs,
-        data_blocks, homepage, search, serve_files, tx,
+        address, address_identicon, address_qr, address_utxos_csv, address_utxos_json,
+        address_widget, block, block_height, block_sitemap, blocks, data_address_balances,
+        data_address_summary, data_address_txs, data_address_utxos, data_admin_add_webhook,
+        data_admin_remove_webhook, data_admin_set_label, data_admin_webhooks, data_batch,
+        data_block, data_block_height_txs, data_block_latency_chart, data_block_raw,
+        data_block_txs, data_blocks, data_difficulty_chart, data_fee_estimate, data_network_stats,
+        data_openapi, data_output_types_chart, data_search_suggestions, data_status, data_supply,
+        data_supply_circulating, data_supply_max, data_token, data_tokens, data_tx,
+        data_tx_confirmations, data_tx_flows, data_tx_graph, data_tx_merkle_proof, data_tx_package,
+        data_tx_scripts, data_upgrade, difficulty_chart, events, forks, healthz, homepage,
+        not_found, opensearch, script, search, serve_files, sitemap_index, supply, token_icon, tx,
+        tx_widget, xpub,
     },
-    server_primitives::{JsonBalance, JsonBlock, JsonBlocksResponse, JsonTxsResponse, JsonUtxo},
+    server_primitives::{
+        JsonAddressBalance, JsonAddressBalancesResponse, JsonAddressLabelRequest,
+        JsonAddressSummary, JsonApiUsage, JsonBalance, JsonBatchQuery, JsonBatchResponse,
+        JsonBatchResult, JsonBlockLatencyResponse, JsonBlockResponse, JsonBlocksResponse,
+        JsonDifficultyChartResponse, JsonDifficultyPoint, JsonFeeEstimate, JsonHealth,
+        JsonMerkleProof, JsonNetworkStats, JsonOutputTypeCounts, JsonOutputTypesChartResponse,
+        JsonOutputTypesPoint, JsonSearchSuggestion, JsonSearchSuggestionsResponse, JsonStatus,
+        JsonSupply, JsonTokenBalance, JsonTokenBurn, JsonTokenMeta, JsonTokensResponse,
+        JsonTxConfirmations, JsonTxDetails, JsonTxFlows, JsonTxGraph, JsonTxGraphEdge,
+        JsonTxGraphNode, JsonTxPackageResponse, JsonTxScripts, JsonTxsResponse, JsonUpgradeStatus,
+        JsonUtxoExport, JsonUtxosResponse, JsonWebhook, JsonWebhookRequest, SearchSuggestionKind,
+    },
+    sitemap::{block_sitemap_range, num_block_sitemaps, SitemapCache, SitemapUrl},
+    supply::{block_subsidy, max_supply, next_halving_height, supply_at_height, HALVING_INTERVAL},
     templating::{
-        AddressTemplate, BlockTemplate, BlocksTemplate, HomepageTemplate, TransactionTemplate,
+        AddressTemplate, AddressWidgetTemplate, BlockTemplate, BlocksTemplate,
+        DifficultyChartTemplate, ErrorTemplate, ForksTemplate, HomepageTemplate,
+        MaintenanceTemplate, NotFoundTemplate, OpenSearchTemplate, ScriptTemplate,
+        SitemapIndexTemplate, SitemapTemplate, SupplyTemplate, TransactionTemplate,
+        TxWidgetTemplate, WidgetStyle, XpubTemplate,
     },
+    token_docs::TokenDocChecker,
+    token_icons::{TokenIcon, TokenIcons},
+    upgrades::Upgrades,
+    webhooks::Webhooks,
+    xpub::XpubScanner,
 };
 
+const MAX_BULK_ADDRESSES: usize = 100;
+const MAX_BULK_TOKENS: usize = 100;
+/// Page size of the JSON history and UTXO listings if `take` isn't set
+const DEFAULT_API_PAGE_SIZE: usize = 200;
+/// Around 300 bytes each
+const MAX_CACHED_IDENTICONS: usize = 10_000;
+const MAX_TX_PACKAGE_SIZE: usize = 50;
+const MAX_BATCH_QUERIES: usize = 100;
+const TOKEN_FILTER_PAGE_SIZE: usize = 200;
+const TOKEN_FILTER_MAX_PAGES: usize = 25;
+/// Filtered histories are up to 5,000 txs each, so only few are kept
+const MAX_FILTERED_HISTORY_CACHE_SIZE: usize = 50;
+/// Block timestamps only have to be above the median of the last 11 blocks,
+/// so history isn't strictly ordered by time; allow this much overlap.
+const BLOCK_TIME_DRIFT_SECS: i64 = 2 * 3600;
+const DEFAULT_TX_GRAPH_DEPTH: i32 = 2;
+const MAX_TX_GRAPH_DEPTH: i32 = 4;
+const MAX_TX_GRAPH_NODES: usize = 50;
+const MAX_MEMPOOL_CHAIN_LOOKUPS: usize = 50;
+const MEMPOOL_CHAIN_FETCH_CONCURRENCY: usize = 8;
+const DEFAULT_CHART_DAYS: i32 = 90;
+const MAX_CHART_DAYS: i32 = 730;
+const CHART_FETCH_CONCURRENCY: usize = 8;
+/// Counting output types needs the txs of every block, so the chart is
+/// limited to a shorter range than the difficulty chart
+const DEFAULT_OUTPUT_TYPE_CHART_DAYS: i32 = 7;
+const MAX_OUTPUT_TYPE_CHART_DAYS: i32 = 30;
+const MAX_OUTPUT_TYPE_CACHE_SIZE: usize = (MAX_OUTPUT_TYPE_CHART_DAYS * BLOCKS_PER_DAY) as usize;
+const MAX_CDD_PREV_TXS: usize = 100;
+const CDD_FETCH_CONCURRENCY: usize = 8;
+const MAX_CACHED_CDD: usize = 10_000;
+/// Fee estimates are based on the last hour of blocks
+const FEE_ESTIMATE_BLOCKS: i32 = 6;
+const BLOCK_INTERVAL_SECS: i64 = 600;
+const SUPPLY_CHART_HALVINGS: i32 = 10;
+const SUPPLY_CHART_POINTS_PER_HALVING: i32 = 10;
+const EXPORT_CHUNK_SIZE: i32 = 500;
+const ADDRESS_SUMMARY_PAGE_SIZE: usize = 200;
+const MAX_ADDRESS_SUMMARY_TXS: u32 = 2000;
+const MAX_ADDRESS_SUMMARY_CACHE_SIZE: usize = 10_000;
+/// Pages of unconfirmed txs scanned for the unconfirmed balance
+const MAX_UNCONFIRMED_PAGES: usize = 5;
+const COSPEND_SCAN_TXS: usize = 200;
+const MAX_COSPENT_ADDRESSES: usize = 20;
+const PUBKEY_SCAN_TXS: usize = 50;
+const RAW_BLOCK_CACHE_SIZE: usize = 8;
+/// Mints followed to find a mint baton; the tx page of the last one continues
+const MAX_MINT_BATON_HOPS: usize = 20;
+const WIDGET_REFRESH_SECS: u32 = 60;
+const DEFAULT_WIDGET_ACCENT: &str = "0074c2";
+const MAX_QR_LABEL_LENGTH: usize = 100;
+const DEFAULT_QR_SIZE: usize = 140;
+const MAX_QR_SIZE: usize = 1000;
+
 pub struct Server {
     chronik: ChronikClient,
     base_dir: PathBuf,
+    labels: AddressLabels,
+    admin_auth: Option<AdminAuth>,
+    limits: LimitsConfig,
+    /// Quotas of the /api routes, which are unlimited if unset
+    api_quotas: Option<ApiQuotas>,
+    /// Base URL the explorer is publicly reachable at, without a trailing slash
+    public_url: Option<String>,
+    sitemaps: SitemapCache,
+    events: broadcast::Sender<ExplorerEvent>,
+    /// Set once the server shuts down, which ends the event streams
+    shutdown: watch::Sender<bool>,
+    block_receive_times: BlockReceiveTimes,
+    chainwork: ChainworkIndex,
+    network_stats: NetworkStats,
+    fork_monitor: ForkMonitor,
+    /// Fee estimate with the tip hash it was computed at
+    fee_estimate: Mutex<Option<(Vec<u8>, JsonFeeEstimate)>>,
+    /// Summaries by address script, with the txid of the address' latest tx
+    address_summaries: Mutex<HashMap<Vec<u8>, (Vec<u8>, JsonAddressSummary)>>,
+    /// Filtered histories by address script and filter, with the txid of the
+    /// address' latest tx and its number of txs
+    filtered_histories: Mutex<FilteredHistories>,
+    /// Coin days destroyed by txid, for mined txs spending mined outputs
+    coin_days_destroyed: Mutex<HashMap<Vec<u8>, f64>>,
+    /// Difficulty chart points by day (height / 144), once the day is
+    /// confirmed
+    difficulty_days: Mutex<HashMap<i32, JsonDifficultyPoint>>,
+    /// Output type counts by block height, with the hash of the block counted
+    output_type_counts: Mutex<BTreeMap<i32, (Vec<u8>, JsonOutputTypeCounts)>>,
+    /// Most recently downloaded raw blocks by hash, the latest last
+    raw_blocks: Mutex<VecDeque<(Sha256d, Bytes)>>,
+    prices: PriceHistory,
+    /// Checks token documents against their hash, if enabled in the config
+    token_doc_checker: Option<Arc<TokenDocChecker>>,
+    /// Looks up token icons, falling back to identicons if unset
+    token_icons: Option<TokenIcons>,
+    /// Identicon PNGs by address, with the mainnet prefix
+    address_identicons: Mutex<HashMap<String, Bytes>>,
+    webhooks: Webhooks,
+    /// Network upgrades scheduled in the config
+    upgrades: Upgrades,
+    /// Scans wallets for the /xpub page, which is disabled if unset
+    xpub_scanner: Option<XpubScanner>,
+    announcement: Option<String>,
+    /// Every page responds with a maintenance page if set
+    maintenance: Option<MaintenanceConfig>,
+    network: Network,
     satoshi_addr_prefix: &'static str,
     tokens_addr_prefix: &'static str,
 }
 
 impl Server {
-    pub async fn setup(chronik: ChronikClient, base_dir: PathBuf) -> Result<Self> {
+    pub async fn setup(
+        chronik: ChronikClient,
+        base_dir: PathBuf,
+        labels: AddressLabels,
+        admin_token: Option<String>,
+        limits: LimitsConfig,
+        public_url: Option<String>,
+    ) -> Result<Self> {
         Ok(Server {
             chronik,
             base_dir,
+            labels,
+            admin_auth: admin_token.as_deref().map(AdminAuth::new),
+            limits,
+            api_quotas: None,
+            public_url,
+            sitemaps: SitemapCache::default(),
+            events: event_channel(),
+            shutdown: watch::channel(false).0,
+            block_receive_times: BlockReceiveTimes::default(),
+            chainwork: ChainworkIndex::default(),
+            network_stats: NetworkStats::default(),
+            fork_monitor: ForkMonitor::default(),
+            fee_estimate: Mutex::new(None),
+            address_summaries: Mutex::new(HashMap::new()),
+            filtered_histories: Mutex::new(HashMap::new()),
+            coin_days_destroyed: Mutex::new(HashMap::new()),
+            difficulty_days: Mutex::new(HashMap::new()),
+            output_type_counts: Mutex::new(BTreeMap::new()),
+            raw_blocks: Mutex::new(VecDeque::new()),
+            prices: PriceHistory::default(),
+            token_doc_checker: None,
+            token_icons: None,
+            address_identicons: Mutex::new(HashMap::new()),
+            webhooks: Webhooks::new(&[])?,
+            upgrades: Upgrades::default(),
+            xpub_scanner: None,
+            announcement: None,
+            maintenance: None,
+            network: Network::Mainnet,
             satoshi_addr_prefix: "ecash",
             tokens_addr_prefix: "etoken",
         })
     }
 
+    /// Switches address prefixes to the network's, after checking that
+    /// Chronik indexes that network.
+    pub async fn with_network(mut self, network: Network) -> Result<Self> {
+        let genesis = self.chronik.block_by_height(0).await?;
+        let genesis_hash = to_be_hex(&genesis.block_info.expect("Impossible").hash);
+        if genesis_hash != network.genesis_hash() {
+            bail!(
+                "Chronik's genesis block {} is not the genesis block of {}, check the network \
+                 config entry",
+                genesis_hash,
+                network.name(),
+            );
+        }
+        self.network = network;
+        self.satoshi_addr_prefix = network.addr_prefix();
+        self.tokens_addr_prefix = network.token_addr_prefix();
+        Ok(self)
+    }
+
+    pub fn with_chainwork_index(mut self, chainwork: ChainworkIndex) -> Self {
+        self.chainwork = chainwork;
+        self
+    }
+
+    pub fn with_price_history(mut self, prices: PriceHistory) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    pub fn with_api_quotas(mut self, api_quotas: ApiQuotas) -> Self {
+        self.api_quotas = Some(api_quotas);
+        self
+    }
+
+    pub fn with_token_doc_checker(mut self, token_doc_checker: TokenDocChecker) -> Self {
+        self.token_doc_checker = Some(Arc::new(token_doc_checker));
+        self
+    }
+
+    pub fn with_token_icons(mut self, token_icons: TokenIcons) -> Self {
+        self.token_icons = Some(token_icons);
+        self
+    }
+
+    pub fn with_webhooks(mut self, webhooks: Webhooks) -> Self {
+        self.webhooks = webhooks;
+        self
+    }
+
+    pub fn with_upgrades(mut self, upgrades: Upgrades) -> Self {
+        self.upgrades = upgrades;
+        self
+    }
+
+    pub fn with_xpub_scanner(mut self, xpub_scanner: XpubScanner) -> Self {
+        self.xpub_scanner = Some(xpub_scanner);
+        self
+    }
+
+    pub fn with_announcement(mut self, announcement: String) -> Self {
+        self.announcement = Some(announcement);
+        self
+    }
+
+    pub fn with_maintenance(mut self, maintenance: MaintenanceConfig) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
     pub fn router(&self) -> Router {
         Router::new()
             .route("/", get(homepage))
@@ -54,32 +336,314 @@ impl Server {
             .route("/block/:hash", get(block))
             .route("/block-height/:height", get(block_height))
             .route("/address/:hash", get(address))
+            .route("/address/:hash/utxos.json", get(address_utxos_json))
+            .route("/address/:hash/utxos.csv", get(address_utxos_csv))
             .route("/address-qr/:hash", get(address_qr))
+            .route("/script/:hex", get(script))
+            .route("/xpub/:xpub", get(xpub))
+            .route("/widget/tx/:hash", get(tx_widget))
+            .route("/widget/address/:hash", get(address_widget))
             .route("/search/:query", get(search))
+            .route("/charts/difficulty", get(difficulty_chart))
+            .route("/supply", get(supply))
+            .route("/forks", get(forks))
+            .route("/token-icon/:token_id", get(token_icon))
+            .route("/address-identicon/:file", get(address_identicon))
+            .route("/opensearch.xml", get(opensearch))
+            .route("/events", get(events))
+            .route("/healthz", get(healthz))
+            .route("/sitemap.xml", get(sitemap_index))
+            .route("/sitemaps/blocks/:page", get(block_sitemap))
+            .route("/api/openapi.json", get(data_openapi))
+            .route("/api/search/suggest", get(data_search_suggestions))
             .route("/api/blocks/:start_height/:end_height", get(data_blocks))
+            .route("/api/export/blocks.ndjson", get(data_export_blocks))
+            .route("/api/charts/difficulty", get(data_difficulty_chart))
+            .route("/api/charts/output-types", get(data_output_types_chart))
+            .route("/api/charts/block-latency", get(data_block_latency_chart))
+            .route("/api/fees/estimate", get(data_fee_estimate))
+            .route("/api/stats/24h", get(data_network_stats))
+            .route("/api/status", get(data_status))
+            .route("/api/upgrade", get(data_upgrade))
+            .route("/api/supply", get(data_supply))
+            .route("/api/supply/circulating", get(data_supply_circulating))
+            .route("/api/supply/max", get(data_supply_max))
+            .route("/api/block/:hash", get(data_block))
             .route("/api/block/:hash/transactions", get(data_block_txs))
+            .route("/api/block/:hash/raw", get(data_block_raw))
+            .route("/api/block-height/:height/txs", get(data_block_height_txs))
             .route("/api/address/:hash/transactions", get(data_address_txs))
+            .route("/api/address/:hash/utxos", get(data_address_utxos))
+            .route("/api/address/:hash/summary", get(data_address_summary))
+            .route("/api/addresses/balances", post(data_address_balances))
+            .route("/api/token/:id", get(data_token))
+            .route("/api/tokens", post(data_tokens))
+            .route("/api/tx/package", post(data_tx_package))
+            .route("/api/batch", post(data_batch))
+            .route("/api/admin/labels", post(data_admin_set_label))
+            .route(
+                "/api/admin/webhooks",
+                get(data_admin_webhooks).post(data_admin_add_webhook),
+            )
+            .route("/api/admin/webhooks/:id", delete(data_admin_remove_webhook))
+            .route("/api/me/usage", get(data_api_usage))
+            .route("/api/tx/:hash", get(data_tx))
+            .route("/api/tx/:hash/merkle-proof", get(data_tx_merkle_proof))
+            .route("/api/tx/:hash/confirmations", get(data_tx_confirmations))
+            .route("/api/tx/:hash/graph", get(data_tx_graph))
+            .route("/api/tx/:hash/scripts", get(data_tx_scripts))
+            .route("/api/tx/:hash/flows", get(data_tx_flows))
             .nest("/code", serve_files(&self.base_dir.join("code")))
             .nest("/assets", serve_files(&self.base_dir.join("assets")))
             .nest("/favicon.ico", serve_files(&self.base_dir.join("assets").join("favicon.png")))
+            .fallback(get(not_found))
     }
 }
 
 impl Server {
-    pub async fn homepage(&self) -> Result<String> {
-        let homepage = HomepageTemplate {};
+    pub async fn homepage(&self, locale: DisplayLocale, unit: Denomination) -> Result<String> {
+        let homepage = HomepageTemplate {
+            stats: self.network_stats.stats(),
+            announcement: self.announcement.clone(),
+            locale,
+            unit,
+            network: self.network,
+        };
         Ok(homepage.render().unwrap())
     }
 
+    /// Maintenance page with the seconds clients should retry after, `None`
+    /// if not in maintenance mode
+    pub fn maintenance_page(&self) -> Option<(String, u64)> {
+        let maintenance = self.maintenance.as_ref()?;
+        let maintenance_template = MaintenanceTemplate {
+            message: &maintenance.message,
+            network: self.network,
+        };
+        Some((
+            maintenance_template.render().unwrap(),
+            maintenance.retry_after_secs,
+        ))
+    }
+
+    pub fn error_page(&self, message: &str) -> String {
+        let error_template = ErrorTemplate {
+            message,
+            network: self.network,
+        };
+        error_template.render().unwrap()
+    }
+
+    pub async fn opensearch(&self, base_url: &str) -> Result<String> {
+        let opensearch = OpenSearchTemplate { base_url };
+        Ok(opensearch.render().unwrap())
+    }
+
+    /// Polls Chronik for new blocks to send to the /events subscribers. Runs
+    /// until the server shuts down.
+    pub async fn poll_events(self: Arc<Self>) {
+        let poll = Poller::new(
+            &self.chronik,
+            &self.events,
+            &self.block_receive_times,
+            &self.chainwork,
+            &self.network_stats,
+            &self.fork_monitor,
+        )
+        .run();
+        // Subscribed before polling starts, so no block is missed
+        let check_webhooks = self
+            .webhooks
+            .check_forever(&self.chronik, self.subscribe_events());
+        future::join3(poll, check_webhooks, self.webhooks.deliver_forever()).await;
+    }
+
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ExplorerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Ends the event streams, which would otherwise keep a graceful shutdown
+    /// waiting forever
+    pub fn shut_down(&self) {
+        self.shutdown.send_replace(true);
+    }
+
+    /// Completes once the server shuts down
+    pub fn shutdown_signal(&self) -> impl Future<Output = ()> {
+        let mut shutdown = self.shutdown.subscribe();
+        async move {
+            while !*shutdown.borrow() {
+                if shutdown.changed().await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn public_url(&self) -> Option<&str> {
+        self.public_url.as_deref()
+    }
+
+    /// Sitemaps list URLs under the `public_url`, so they're only served if
+    /// it's set; `None` otherwise.
+    pub async fn sitemap_index(&self) -> Result<Option<String>> {
+        let base_url = match self.public_url() {
+            Some(base_url) => base_url,
+            None => return Ok(None),
+        };
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let sitemap_urls = (0..num_block_sitemaps(blockchain_info.tip_height))
+            .map(|page| format!("{}/sitemaps/blocks/{}", base_url, page))
+            .collect();
+        let sitemap_index = SitemapIndexTemplate { sitemap_urls };
+        Ok(Some(sitemap_index.render().unwrap()))
+    }
+
+    /// Sitemap of the block pages of `page`, see `BLOCKS_PER_SITEMAP`, or
+    /// `None` if the `public_url` isn't set
+    pub async fn block_sitemap(&self, page: i32) -> Result<Option<String>> {
+        let base_url = match self.public_url() {
+            Some(base_url) => base_url,
+            None => return Ok(None),
+        };
+        if let Some(sitemap) = self.sitemaps.get(page) {
+            return Ok(Some(sitemap));
+        }
+
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let (start_height, end_height) = match block_sitemap_range(page, blockchain_info.tip_height)
+        {
+            Some(range) => range,
+            None => bail!(BadRequest(format!("No block sitemap {}", page))),
+        };
+
+        let chunks = (start_height..=end_height)
+            .step_by(EXPORT_CHUNK_SIZE as usize)
+            .map(|chunk_start| {
+                let chunk_end = (chunk_start + EXPORT_CHUNK_SIZE - 1).min(end_height);
+                self.chronik.blocks(chunk_start, chunk_end)
+            });
+        let urls = stream::iter(chunks)
+            .buffered(CHART_FETCH_CONCURRENCY)
+            .map_ok(|blocks| {
+                stream::iter(blocks.into_iter().map(|block| {
+                    Ok(SitemapUrl {
+                        loc: format!("{}/block/{}", base_url, to_be_hex(&block.hash)),
+                        lastmod: Some(
+                            Utc.timestamp(block.timestamp, 0)
+                                .format("%Y-%m-%d")
+                                .to_string(),
+                        ),
+                    })
+                }))
+            })
+            .try_flatten()
+            .try_collect()
+            .await?;
+
+        let sitemap = SitemapTemplate { urls }.render().unwrap();
+        self.sitemaps
+            .insert(page, end_height, blockchain_info.tip_height, &sitemap);
+        Ok(Some(sitemap))
+    }
+
     pub async fn blocks(&self) -> Result<String> {
         let blockchain_info = self.chronik.blockchain_info().await?;
+        let tip_height = blockchain_info.tip_height;
+
+        // Render the first page on the server, the script only fetches
+        // further pages
+        let start_height = (tip_height - DEFAULT_ROWS_PER_PAGE as i32 + 1).max(0);
+        let blocks = self.chronik.blocks(start_height, tip_height).await?;
+        let blocks = blocks
+            .iter()
+            .rev()
+            .map(block_info_to_json)
+            .collect::<Vec<_>>();
+        let encoded_blocks = serde_json::to_string(&blocks)?.replace("</", r"<\/");
 
         let blocks_template = BlocksTemplate {
-            last_block_height: blockchain_info.tip_height as u32,
+            last_block_height: tip_height as u32,
+            blocks,
+            encoded_blocks,
+            pagination: Pagination::new(
+                1,
+                tip_height as u32,
+                DEFAULT_ROWS_PER_PAGE,
+                "/blocks?".to_string(),
+            ),
+            network: self.network,
         };
 
         Ok(blocks_template.render().unwrap())
     }
+
+    pub async fn difficulty_chart(&self) -> Result<String> {
+        let difficulty_chart = DifficultyChartTemplate {
+            network: self.network,
+        };
+        Ok(difficulty_chart.render().unwrap())
+    }
+
+    pub async fn forks(&self, locale: DisplayLocale) -> Result<String> {
+        let forks_template = ForksTemplate {
+            forks: self.fork_monitor.forks(),
+            alert_depth: FORK_ALERT_DEPTH,
+            locale,
+            network: self.network,
+        };
+        Ok(forks_template.render().unwrap())
+    }
+
+    /// Wallet page of the xpub, `None` if the page is disabled
+    pub async fn xpub(
+        &self,
+        xpub: &str,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<Option<String>> {
+        let xpub_scanner = match &self.xpub_scanner {
+            Some(xpub_scanner) => xpub_scanner,
+            None => return Ok(None),
+        };
+        let wallet = xpub_scanner
+            .wallet(&self.chronik, self.satoshi_addr_prefix, xpub)
+            .await?;
+        let xpub_template = XpubTemplate {
+            xpub,
+            wallet,
+            locale,
+            unit,
+            network: self.network,
+        };
+        Ok(Some(xpub_template.render().unwrap()))
+    }
+
+    pub async fn supply(&self, locale: DisplayLocale, unit: Denomination) -> Result<String> {
+        let supply = self.data_supply().await?;
+        // The schedule up to the 10th halving, when 99.9% will be emitted
+        let chart_data = (0..=SUPPLY_CHART_HALVINGS * SUPPLY_CHART_POINTS_PER_HALVING)
+            .map(|point| {
+                let height = point * HALVING_INTERVAL / SUPPLY_CHART_POINTS_PER_HALVING;
+                json!({
+                    "height": height,
+                    "supply": supply_at_height(height) / 100,
+                    "maxSupply": supply.max_supply_sats / 100,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let supply_template = SupplyTemplate {
+            percent_emitted: supply.circulating_sats as f64 / supply.max_supply_sats as f64 * 100.0,
+            next_halving_at: Utc.timestamp(supply.next_halving_timestamp, 0),
+            chart_data: serde_json::to_string(&chart_data)?,
+            supply,
+            locale,
+            unit,
+            network: self.network,
+        };
+        Ok(supply_template.render().unwrap())
+    }
 }
 
 impl Server {
@@ -88,27 +652,349 @@ impl Server {
         start_height: i32,
         end_height: i32,
     ) -> Result<JsonBlocksResponse> {
+        if end_height < start_height || end_height - start_height >= self.limits.max_block_range {
+            bail!(BadRequest(format!(
+                "Block range must contain between 1 and {} blocks",
+                self.limits.max_block_range
+            )));
+        }
         let blocks = self.chronik.blocks(start_height, end_height).await?;
 
         let mut json_blocks = Vec::with_capacity(blocks.len());
-        for block in blocks.into_iter().rev() {
-            json_blocks.push(JsonBlock {
-                hash: to_be_hex(&block.hash),
-                height: block.height,
-                timestamp: block.timestamp,
-                difficulty: calculate_block_difficulty(block.n_bits),
-                size: block.block_size,
-                num_txs: block.num_txs,
-            });
+        for block in blocks.iter().rev() {
+            json_blocks.push(block_info_to_json(block));
         }
 
         Ok(JsonBlocksResponse { data: json_blocks })
     }
 
+    /// Daily difficulty and hashrate for the last `days` days, where a day is
+    /// the 144 blocks from a multiple of 144 on, so the last day only has the
+    /// blocks mined since then.
+    pub async fn data_difficulty_chart(
+        &self,
+        query: ChartQuery,
+    ) -> Result<JsonDifficultyChartResponse> {
+        let days = query.days.unwrap_or(DEFAULT_CHART_DAYS);
+        if !(1..=MAX_CHART_DAYS).contains(&days) {
+            bail!(BadRequest(format!(
+                "days must be between 1 and {}",
+                MAX_CHART_DAYS
+            )));
+        }
+
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let tip_height = blockchain_info.tip_height;
+        let last_day = tip_height / BLOCKS_PER_DAY;
+        let first_day = (last_day - days + 1).max(0);
+
+        let data = stream::iter(first_day..=last_day)
+            .map(|day| self.difficulty_day(day, tip_height))
+            .buffered(CHART_FETCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        self.difficulty_days
+            .lock()
+            .unwrap()
+            .retain(|&day, _| day > last_day - MAX_CHART_DAYS);
+
+        Ok(JsonDifficultyChartResponse { data })
+    }
+
+    /// Average difficulty of the blocks of `day`, cached once all its blocks
+    /// have `CONFIRMATION_DEPTH` confirmations
+    async fn difficulty_day(&self, day: i32, tip_height: i32) -> Result<JsonDifficultyPoint> {
+        if let Some(point) = self.difficulty_days.lock().unwrap().get(&day) {
+            return Ok(point.clone());
+        }
+
+        let start_height = day * BLOCKS_PER_DAY;
+        let end_height = (start_height + BLOCKS_PER_DAY - 1).min(tip_height);
+        let blocks = self.chronik.blocks(start_height, end_height).await?;
+        let last_block = match blocks.last() {
+            Some(last_block) => last_block,
+            None => bail!(
+                "Chronik returned no blocks from {} to {}",
+                start_height,
+                end_height
+            ),
+        };
+        let difficulty = blocks
+            .iter()
+            .map(|block| calculate_block_difficulty(block.n_bits))
+            .sum::<f64>()
+            / blocks.len() as f64;
+        let point = JsonDifficultyPoint {
+            timestamp: last_block.timestamp,
+            height: last_block.height,
+            difficulty,
+            hashrate: estimate_hashrate(difficulty),
+        };
+
+        let is_complete = blocks.len() == BLOCKS_PER_DAY as usize;
+        if is_complete && end_height + CONFIRMATION_DEPTH <= tip_height {
+            self.difficulty_days
+                .lock()
+                .unwrap()
+                .insert(day, point.clone());
+        }
+
+        Ok(point)
+    }
+
+    /// Outputs by script type per day (144 blocks)
+    pub async fn data_output_types_chart(
+        &self,
+        query: ChartQuery,
+    ) -> Result<JsonOutputTypesChartResponse> {
+        let days = query.days.unwrap_or(DEFAULT_OUTPUT_TYPE_CHART_DAYS);
+        if !(1..=MAX_OUTPUT_TYPE_CHART_DAYS).contains(&days) {
+            bail!(BadRequest(format!(
+                "days must be between 1 and {}",
+                MAX_OUTPUT_TYPE_CHART_DAYS
+            )));
+        }
+
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let mut data = Vec::with_capacity(days as usize);
+        for day in (0..days).rev() {
+            let end_height = blockchain_info.tip_height - day * BLOCKS_PER_DAY;
+            let start_height = end_height - BLOCKS_PER_DAY + 1;
+            if start_height < 0 {
+                continue;
+            }
+            let blocks = self.chronik.blocks(start_height, end_height).await?;
+            let last_block = match blocks.last() {
+                Some(last_block) => last_block,
+                None => bail!(
+                    "Chronik returned no blocks from {} to {}",
+                    start_height,
+                    end_height
+                ),
+            };
+            let counts = stream::iter(&blocks)
+                .map(|block_info| self.block_output_type_counts(block_info))
+                .buffered(CHART_FETCH_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+            data.push(JsonOutputTypesPoint {
+                timestamp: last_block.timestamp,
+                height: last_block.height,
+                outputs: sum_output_type_counts(counts),
+            });
+        }
+
+        Ok(JsonOutputTypesChartResponse { data })
+    }
+
+    /// Output type counts of a block, from the cache if the block at its
+    /// height hasn't changed since
+    async fn block_output_type_counts(
+        &self,
+        block_info: &BlockInfo,
+    ) -> Result<JsonOutputTypeCounts> {
+        if let Some((block_hash, counts)) = self
+            .output_type_counts
+            .lock()
+            .unwrap()
+            .get(&block_info.height)
+        {
+            if *block_hash == block_info.hash {
+                return Ok(*counts);
+            }
+        }
+
+        let block = self
+            .chronik
+            .block_by_hash(&Sha256d::from_slice(&block_info.hash)?)
+            .await?;
+        let counts = calc_output_type_counts(&block.txs);
+
+        let mut cache = self.output_type_counts.lock().unwrap();
+        cache.insert(block_info.height, (block_info.hash.clone(), counts));
+        if cache.len() > MAX_OUTPUT_TYPE_CACHE_SIZE {
+            let lowest_height = *cache.keys().next().expect("Impossible");
+            cache.remove(&lowest_height);
+        }
+        Ok(counts)
+    }
+
+    pub async fn data_supply(&self) -> Result<JsonSupply> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let height = blockchain_info.tip_height;
+        let next_halving_height = next_halving_height(height);
+        Ok(JsonSupply {
+            height,
+            circulating_sats: supply_at_height(height),
+            max_supply_sats: max_supply(),
+            block_subsidy_sats: block_subsidy(height + 1),
+            next_halving_height,
+            next_halving_timestamp: Utc::now().timestamp()
+                + i64::from(next_halving_height - height) * BLOCK_INTERVAL_SECS,
+        })
+    }
+
+    pub async fn data_status(&self) -> Result<JsonStatus> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let alerts_since = Utc::now().timestamp() - 24 * 3600;
+        Ok(JsonStatus {
+            tip_height: blockchain_info.tip_height,
+            tip_hash: to_be_hex(&blockchain_info.tip_hash),
+            fork_alerts: self
+                .fork_monitor
+                .forks()
+                .into_iter()
+                .filter(|fork| fork.depth >= FORK_ALERT_DEPTH && fork.detected_at >= alerts_since)
+                .collect(),
+        })
+    }
+
+    pub async fn data_health(&self) -> JsonHealth {
+        let tip = async {
+            let blockchain_info = self.chronik.blockchain_info().await?;
+            let tip_height = blockchain_info.tip_height;
+            let tip_blocks = self.chronik.blocks(tip_height, tip_height).await?;
+            Ok::<_, eyre::Report>((tip_height, tip_blocks.first().map(|block| block.timestamp)))
+        };
+        let maintenance = self.maintenance.is_some();
+        match tip.await {
+            Ok((tip_height, tip_timestamp)) => JsonHealth {
+                healthy: true,
+                maintenance,
+                tip_height: Some(tip_height),
+                tip_timestamp,
+                error: None,
+            },
+            Err(err) => JsonHealth {
+                healthy: false,
+                maintenance,
+                tip_height: None,
+                tip_timestamp: None,
+                error: Some(err.to_string()),
+            },
+        }
+    }
+
+    pub fn data_network_stats(&self) -> JsonNetworkStats {
+        self.network_stats.stats()
+    }
+
+    pub async fn data_upgrade(&self) -> Result<JsonUpgradeStatus> {
+        self.upgrades.status(&self.chronik).await
+    }
+
+    pub fn data_block_latency_chart(&self) -> JsonBlockLatencyResponse {
+        JsonBlockLatencyResponse {
+            data: self.block_receive_times.latencies(),
+        }
+    }
+
+    /// Chronik doesn't expose the mempool, so fee rates are estimated from the
+    /// lowest fee rate each of the last blocks included. Percentiles of those
+    /// are used, so a single block with only high fee txs doesn't set the
+    /// estimate. Rates never go below the minimum relay fee rate, which is
+    /// enough while blocks aren't full.
+    pub async fn data_fee_estimate(&self) -> Result<JsonFeeEstimate> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        if let Some((tip_hash, fee_estimate)) = &*self.fee_estimate.lock().unwrap() {
+            if *tip_hash == blockchain_info.tip_hash {
+                return Ok(fee_estimate.clone());
+            }
+        }
+
+        let start_height = (blockchain_info.tip_height - FEE_ESTIMATE_BLOCKS + 1).max(0);
+        let blocks = stream::iter(start_height..=blockchain_info.tip_height)
+            .map(|height| self.chronik.block_by_height(height))
+            .buffered(CHART_FETCH_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut min_fee_rates = blocks
+            .iter()
+            .map(|block| {
+                calc_block_fee_stats(&block.txs).map_or(MIN_RELAY_FEE_RATE, |fee_stats| {
+                    fee_stats.min_fee_rate.max(MIN_RELAY_FEE_RATE)
+                })
+            })
+            .collect::<Vec<_>>();
+        min_fee_rates.sort_unstable();
+
+        let fee_estimate = JsonFeeEstimate {
+            next_block: fee_rate_percentile(&min_fee_rates, 75),
+            hour: fee_rate_percentile(&min_fee_rates, 50),
+            economic: MIN_RELAY_FEE_RATE,
+            tip_height: blockchain_info.tip_height,
+            num_blocks: min_fee_rates.len() as u32,
+        };
+        *self.fee_estimate.lock().unwrap() = Some((blockchain_info.tip_hash, fee_estimate.clone()));
+
+        Ok(fee_estimate)
+    }
+
+    /// Streams the blocks from `from` to `to` (inclusive) as NDJSON, fetching
+    /// them from Chronik one chunk at a time as the client reads the response.
+    /// Also returns the number of chunks, which the API quota is charged for.
+    /// Without `from`, the last `max_export_range` blocks up to `to` are
+    /// exported.
+    pub async fn export_blocks(
+        self: Arc<Self>,
+        query: BlockRangeQuery,
+    ) -> Result<(u32, impl Stream<Item = Result<String>>)> {
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let max_export_range = self.limits.max_export_range;
+        let to = query.to.unwrap_or(blockchain_info.tip_height);
+        let from = query
+            .from
+            .unwrap_or_else(|| (to - max_export_range + 1).max(0));
+        if from < 0 || from > to || to > blockchain_info.tip_height {
+            bail!(BadRequest(format!(
+                "Invalid range, from and to must be between 0 and {} and from must be at most to",
+                blockchain_info.tip_height
+            )));
+        }
+        if to - from >= max_export_range {
+            bail!(BadRequest(format!(
+                "At most {} blocks can be exported at once",
+                max_export_range
+            )));
+        }
+
+        let chunks = (from..=to)
+            .step_by(EXPORT_CHUNK_SIZE as usize)
+            .map(move |start| (start, (start + EXPORT_CHUNK_SIZE - 1).min(to)));
+        let num_chunks = ((to - from) / EXPORT_CHUNK_SIZE + 1) as u32;
+
+        Ok((
+            num_chunks,
+            stream::iter(chunks).then(move |(start_height, end_height)| {
+                let server = Arc::clone(&self);
+                async move {
+                    let blocks = server.chronik.blocks(start_height, end_height).await?;
+                    let mut ndjson = String::new();
+                    for block in &blocks {
+                        ndjson.push_str(&serde_json::to_string(&block_info_to_json(block))?);
+                        ndjson.push('\n');
+                    }
+                    Ok(ndjson)
+                }
+            }),
+        ))
+    }
+
     pub async fn data_block_txs(&self, block_hex: &str) -> Result<JsonTxsResponse> {
         let block_hash = Sha256d::from_hex_be(block_hex)?;
         let block = self.chronik.block_by_hash(&block_hash).await?;
+        self.block_txs_response(block).await
+    }
 
+    /// Txs of the main chain block at `height`, without looking up its hash
+    /// first
+    pub async fn data_block_height_txs(&self, height: u32) -> Result<JsonTxsResponse> {
+        let block = self.chronik.block_by_height(height as i32).await?;
+        self.block_txs_response(block).await
+    }
+
+    async fn block_txs_response(&self, block: Block) -> Result<JsonTxsResponse> {
         let token_ids = block
             .txs
             .iter()
@@ -128,26 +1014,34 @@ impl Server {
     pub async fn data_address_txs(
         &self,
         address: &str,
-        query: HashMap<String, String>,
+        query: AddressTxsQuery,
     ) -> Result<JsonTxsResponse> {
         let address = CashAddress::parse_cow(address.into())?;
         let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
         let script_endpoint = self.chronik.script(script_type, &script_payload);
 
-        let page: usize = query
-            .get("page")
-            .map(|s| s.as_str())
-            .unwrap_or("0")
-            .parse()?;
-        let take: usize = query
-            .get("take")
-            .map(|s| s.as_str())
-            .unwrap_or("200")
-            .parse()?;
-        let address_tx_history = script_endpoint.history_with_page_size(page, take).await?;
-
-        let token_ids = address_tx_history
-            .txs
+        let page = query.page.unwrap_or(0);
+        let take = query.take.unwrap_or(DEFAULT_API_PAGE_SIZE);
+        self.check_page_size(take)?;
+        let filter = TxHistoryFilter::new(query.token, query.from.as_deref(), query.to.as_deref())?;
+        let txs = match &filter {
+            Some(filter) => self
+                .filtered_tx_history(&address, filter)
+                .await?
+                .iter()
+                .skip(page.saturating_mul(take))
+                .take(take)
+                .cloned()
+                .collect(),
+            None => {
+                script_endpoint
+                    .history_with_page_size(page, take)
+                    .await?
+                    .txs
+            }
+        };
+
+        let token_ids = txs
             .iter()
             .filter_map(|tx| {
                 let slp_tx_data = tx.slp_tx_data.as_ref()?;
@@ -158,14 +1052,891 @@ impl Server {
 
         let tokens = self.batch_get_chronik_tokens(token_ids).await?;
         let json_tokens = tokens_to_json(&tokens)?;
-        let json_txs = tx_history_to_json(&address, address_tx_history, &json_tokens)?;
+        let address_script = address.to_script().bytecode().to_vec();
+        let mut json_txs = tx_history_to_json(&address_script, &txs, &json_tokens)?;
+        for json_tx in &mut json_txs {
+            json_tx.price_usd = self.prices.close_at(json_tx.timestamp);
+        }
 
         Ok(JsonTxsResponse { data: json_txs })
     }
+
+    pub async fn data_tx(&self, tx_hex: &str) -> Result<JsonTxDetails> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        let mut json_tx = tx_details_to_json(&tx, self.satoshi_addr_prefix);
+        json_tx.coin_days_destroyed = self.coin_days_destroyed(&tx).await;
+        Ok(json_tx)
+    }
+
+    pub async fn data_block(&self, block_hex: &str) -> Result<JsonBlockResponse> {
+        let block_hash = Sha256d::from_hex_be(block_hex)?;
+        let block = self.chronik.block_by_hash(&block_hash).await?;
+        let block_info = block.block_info.ok_or_else(|| eyre!("Block has no info"))?;
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        Ok(JsonBlockResponse {
+            block: block_info_to_json(&block_info),
+            fee_stats: calc_block_fee_stats(&block.txs),
+            coinbase: coinbase_to_json(
+                &block.txs[0],
+                &block_info,
+                self.network,
+                self.satoshi_addr_prefix,
+            ),
+            output_types: calc_output_type_counts(&block.txs),
+            token_summary: calc_block_token_summary(&block.txs),
+            chainwork: self
+                .chainwork
+                .get(block_info.height)
+                .map(|chainwork| chainwork.to_hex()),
+            prev_block_hash: prev_block_hash(&block_info),
+            next_block_hash: self
+                .next_block_hash(&block_info, blockchain_info.tip_height)
+                .await?,
+        })
+    }
+
+    /// Hash of the block after `block_info` on the main chain. Stale blocks
+    /// have none, as the block at the next height doesn't build on them.
+    async fn next_block_hash(
+        &self,
+        block_info: &BlockInfo,
+        tip_height: i32,
+    ) -> Result<Option<String>> {
+        if block_info.height >= tip_height {
+            return Ok(None);
+        }
+        let next_height = block_info.height + 1;
+        let next_blocks = self.chronik.blocks(next_height, next_height).await?;
+        Ok(next_blocks
+            .into_iter()
+            .find(|next_block| next_block.prev_hash == block_info.hash)
+            .map(|next_block| to_be_hex(&next_block.hash)))
+    }
+
+    /// Serializes a block as in the P2P protocol: the header, the number of
+    /// txs and the raw txs. Chronik has no raw block endpoint, so the raw txs
+    /// are fetched one by one, which is why the size is limited.
+    pub async fn data_block_raw(&self, block_hex: &str) -> Result<Bytes> {
+        let block_hash = Sha256d::from_hex_be(block_hex)?;
+        {
+            let mut raw_blocks = self.raw_blocks.lock().unwrap();
+            if let Some(idx) = raw_blocks.iter().position(|(hash, _)| *hash == block_hash) {
+                let cached = raw_blocks.remove(idx).expect("Impossible");
+                raw_blocks.push_back(cached.clone());
+                return Ok(cached.1);
+            }
+        }
+
+        let block = self.chronik.block_by_hash(&block_hash).await?;
+        let block_info = block.block_info.ok_or_else(|| eyre!("Block has no info"))?;
+        if block_info.block_size > self.limits.max_raw_block_size {
+            bail!(BadRequest(format!(
+                "Block is {} bytes, only blocks up to {} bytes can be downloaded",
+                block_info.block_size, self.limits.max_raw_block_size
+            )));
+        }
+
+        let raw_txs = stream::iter(&block.txs)
+            .map(|tx| async move { self.chronik.raw_tx(&Sha256d::from_slice(&tx.txid)?).await })
+            .buffered(CHART_FETCH_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let mut raw_block = Vec::with_capacity(block_info.block_size as usize);
+        raw_block.extend_from_slice(&block.raw_header);
+        raw_block.extend(bitcoin::consensus::serialize(
+            &bitcoin::consensus::encode::VarInt(raw_txs.len() as u64),
+        ));
+        for raw_tx in &raw_txs {
+            raw_block.extend_from_slice(raw_tx.as_ref());
+        }
+        let raw_block = Bytes::from(raw_block);
+
+        let mut raw_blocks = self.raw_blocks.lock().unwrap();
+        if raw_blocks.len() >= RAW_BLOCK_CACHE_SIZE {
+            raw_blocks.pop_front();
+        }
+        raw_blocks.push_back((block_hash, raw_block.clone()));
+
+        Ok(raw_block)
+    }
+
+    pub async fn data_tx_flows(&self, tx_hex: &str) -> Result<JsonTxFlows> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        Ok(calc_tx_flows(&tx, self.satoshi_addr_prefix))
+    }
+
+    pub async fn data_tx_scripts(&self, tx_hex: &str) -> Result<JsonTxScripts> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        Ok(JsonTxScripts {
+            tx_hash: tx_hash.to_hex_be(),
+            input_scripts: tx
+                .inputs
+                .iter()
+                .map(|input| script_to_json(&input.input_script))
+                .collect(),
+            output_scripts: tx
+                .outputs
+                .iter()
+                .map(|output| script_to_json(&output.output_script))
+                .collect(),
+        })
+    }
+
+    /// The branch is recomputed from the txs of the block. Hashes are hex in
+    /// internal byte order, except for the tx, block and merkle root hashes,
+    /// which are displayed big-endian as everywhere else.
+    pub async fn data_tx_merkle_proof(&self, tx_hex: &str) -> Result<JsonMerkleProof> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        let block_meta = tx.block.ok_or_else(|| eyre!("Tx is not mined yet"))?;
+
+        let block_hash = Sha256d::from_slice(&block_meta.hash)?;
+        let block = self.chronik.block_by_hash(&block_hash).await?;
+        let block_details = block
+            .block_details
+            .ok_or_else(|| eyre!("Block has no details"))?;
+
+        let txids = block
+            .txs
+            .iter()
+            .map(|block_tx| <[u8; 32]>::try_from(block_tx.txid.as_slice()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let position = txids
+            .iter()
+            .position(|txid| txid[..] == tx.txid[..])
+            .ok_or_else(|| eyre!("Tx not found in its block"))?;
+        let branch = merkle_branch(&txids, position);
+
+        Ok(JsonMerkleProof {
+            tx_hash: to_be_hex(&tx.txid),
+            block_hash: to_be_hex(&block_meta.hash),
+            block_height: block_meta.height,
+            merkle_root: to_be_hex(&block_details.merkle_root),
+            position,
+            branch: branch.iter().map(hex::encode).collect(),
+        })
+    }
+
+    pub async fn data_tx_confirmations(&self, tx_hex: &str) -> Result<JsonTxConfirmations> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        let confirmations = match &tx.block {
+            Some(block_meta) => {
+                let blockchain_info = self.chronik.blockchain_info().await?;
+                blockchain_info.tip_height - block_meta.height + 1
+            }
+            None => 0,
+        };
+        Ok(JsonTxConfirmations {
+            tx_hash: tx_hash.to_hex_be(),
+            block_hash: tx
+                .block
+                .as_ref()
+                .map(|block_meta| to_be_hex(&block_meta.hash)),
+            block_height: tx.block.as_ref().map(|block_meta| block_meta.height),
+            confirmations,
+            is_confirmed: confirmations >= CONFIRMATION_DEPTH,
+        })
+    }
+
+    fn check_page_size(&self, take: usize) -> Result<()> {
+        if take == 0 || take > self.limits.max_page_size {
+            bail!(BadRequest(format!(
+                "take must be between 1 and {}",
+                self.limits.max_page_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Walks the spends of the tx up to `depth` txs back (via the input
+    /// outpoints) and forward (via the outputs' spending txs). Each tx in the
+    /// graph is fetched from Chronik, so the walk stops at `MAX_TX_GRAPH_NODES`.
+    pub async fn data_tx_graph(&self, tx_hex: &str, query: TxGraphQuery) -> Result<JsonTxGraph> {
+        let max_depth = query.depth.unwrap_or(DEFAULT_TX_GRAPH_DEPTH);
+        if !(1..=MAX_TX_GRAPH_DEPTH).contains(&max_depth) {
+            bail!(BadRequest(format!(
+                "depth must be between 1 and {}",
+                MAX_TX_GRAPH_DEPTH
+            )));
+        }
+
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let mut queue = VecDeque::from([(tx_hash, 0)]);
+        let mut seen_txids = HashSet::new();
+        let mut nodes = Vec::new();
+        let mut edges = HashSet::new();
+        let mut is_truncated = false;
+
+        while let Some((tx_hash, depth)) = queue.pop_front() {
+            if !seen_txids.insert(tx_hash.clone()) {
+                continue;
+            }
+            if nodes.len() >= MAX_TX_GRAPH_NODES {
+                is_truncated = true;
+                break;
+            }
+
+            let tx = self.chronik.tx(&tx_hash).await?;
+            let tx_hash_hex = to_be_hex(&tx.txid);
+
+            // Ancestors are only walked further back, descendants only forward
+            if depth <= 0 && depth > -max_depth && !tx.is_coinbase {
+                for (input_idx, input) in tx.inputs.iter().enumerate() {
+                    let prev_out = input.prev_out.as_ref().expect("Impossible");
+                    edges.insert(JsonTxGraphEdge {
+                        from_tx_hash: to_be_hex(&prev_out.txid),
+                        out_idx: prev_out.out_idx,
+                        to_tx_hash: tx_hash_hex.clone(),
+                        input_idx: input_idx as u32,
+                        sats_amount: input.value,
+                    });
+                    queue.push_back((Sha256d::from_slice(&prev_out.txid)?, depth - 1));
+                }
+            }
+            if depth >= 0 && depth < max_depth {
+                for (out_idx, output) in tx.outputs.iter().enumerate() {
+                    if let Some(spent_by) = &output.spent_by {
+                        edges.insert(JsonTxGraphEdge {
+                            from_tx_hash: tx_hash_hex.clone(),
+                            out_idx: out_idx as u32,
+                            to_tx_hash: to_be_hex(&spent_by.txid),
+                            input_idx: spent_by.out_idx,
+                            sats_amount: output.value,
+                        });
+                        queue.push_back((Sha256d::from_slice(&spent_by.txid)?, depth + 1));
+                    }
+                }
+            }
+
+            nodes.push(JsonTxGraphNode {
+                tx_hash: tx_hash_hex,
+                depth,
+                block_height: tx.block.as_ref().map(|block| block.height),
+                is_coinbase: tx.is_coinbase,
+                sats_output: tx.outputs.iter().map(|output| output.value).sum(),
+            });
+        }
+
+        // Drop edges to txs that weren't fetched because of the node limit
+        let node_hashes = nodes
+            .iter()
+            .map(|node| node.tx_hash.as_str())
+            .collect::<HashSet<_>>();
+        let edges = edges
+            .into_iter()
+            .filter(|edge| {
+                node_hashes.contains(edge.from_tx_hash.as_str())
+                    && node_hashes.contains(edge.to_tx_hash.as_str())
+            })
+            .collect();
+
+        Ok(JsonTxGraph {
+            nodes,
+            edges,
+            is_truncated,
+        })
+    }
+
+    pub async fn data_address_summary(&self, address: &str) -> Result<JsonAddressSummary> {
+        let address = CashAddress::parse_cow(address.into())?;
+        self.address_summary(&address).await
+    }
+
+    /// Totals need the whole history of the address, so they're only computed
+    /// for addresses with at most MAX_ADDRESS_SUMMARY_TXS txs. Summaries are
+    /// cached until the address gets a new tx.
+    async fn address_summary(&self, address: &CashAddress<'_>) -> Result<JsonAddressSummary> {
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+        let address_bytes = address.to_script().bytecode().to_vec();
+
+        let page_size = 1; // Set to minimum so that num_pages == total existing tx's
+        let latest_history = script_endpoint.history_with_page_size(0, page_size).await?;
+        let num_txs = latest_history.num_pages;
+        let latest_tx = match latest_history.txs.first() {
+            Some(latest_tx) => latest_tx,
+            None => {
+                return Ok(JsonAddressSummary {
+                    num_txs: 0,
+                    first_seen: None,
+                    last_activity: None,
+                    total_received: Some(0),
+                    total_sent: Some(0),
+                })
+            }
+        };
+
+        if let Some((latest_txid, summary)) =
+            self.address_summaries.lock().unwrap().get(&address_bytes)
+        {
+            if *latest_txid == latest_tx.txid && summary.num_txs == num_txs {
+                return Ok(summary.clone());
+            }
+        }
+
+        let (first_tx, totals) = if num_txs <= MAX_ADDRESS_SUMMARY_TXS {
+            let num_pages =
+                (num_txs as usize + ADDRESS_SUMMARY_PAGE_SIZE - 1) / ADDRESS_SUMMARY_PAGE_SIZE;
+            let txs = stream::iter(0..num_pages)
+                .map(|page| script_endpoint.history_with_page_size(page, ADDRESS_SUMMARY_PAGE_SIZE))
+                .buffered(CHART_FETCH_CONCURRENCY)
+                .map_ok(|history| stream::iter(history.txs.into_iter().map(Ok)))
+                .try_flatten()
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            let total_received = txs
+                .iter()
+                .flat_map(|tx| &tx.outputs)
+                .filter(|output| output.output_script == address_bytes)
+                .map(|output| output.value)
+                .sum::<i64>();
+            let total_sent = txs
+                .iter()
+                .flat_map(|tx| &tx.inputs)
+                .filter(|input| input.output_script == address_bytes)
+                .map(|input| input.value)
+                .sum::<i64>();
+            (txs.last().cloned(), Some((total_received, total_sent)))
+        } else {
+            let oldest_history = script_endpoint
+                .history_with_page_size(num_txs as usize - 1, page_size)
+                .await?;
+            (oldest_history.txs.into_iter().next(), None)
+        };
+
+        let tx_timestamp = |tx: &Tx| match &tx.block {
+            Some(block) => block.timestamp,
+            None => tx.time_first_seen,
+        };
+        let summary = JsonAddressSummary {
+            num_txs,
+            first_seen: first_tx.as_ref().map(tx_timestamp),
+            last_activity: Some(tx_timestamp(latest_tx)),
+            total_received: totals.map(|(total_received, _)| total_received),
+            total_sent: totals.map(|(_, total_sent)| total_sent),
+        };
+
+        let mut address_summaries = self.address_summaries.lock().unwrap();
+        if address_summaries.len() >= MAX_ADDRESS_SUMMARY_CACHE_SIZE {
+            address_summaries.clear();
+        }
+        address_summaries.insert(address_bytes, (latest_tx.txid.clone(), summary.clone()));
+
+        Ok(summary)
+    }
+
+    /// Unconfirmed txs of the address. They come first in the history, so
+    /// the history is read until the first confirmed tx.
+    async fn unconfirmed_txs(&self, address: &CashAddress<'_>) -> Result<Vec<Tx>> {
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+
+        let mut unconfirmed_txs = Vec::new();
+        for page in 0..MAX_UNCONFIRMED_PAGES {
+            let history = script_endpoint
+                .history_with_page_size(page, ADDRESS_SUMMARY_PAGE_SIZE)
+                .await?;
+            let num_txs = history.txs.len();
+            let num_unconfirmed = history
+                .txs
+                .iter()
+                .take_while(|tx| tx.block.is_none())
+                .count();
+            unconfirmed_txs.extend(history.txs.into_iter().take(num_unconfirmed));
+            if num_unconfirmed < num_txs || page + 1 >= history.num_pages as usize {
+                break;
+            }
+        }
+        Ok(unconfirmed_txs)
+    }
+
+    /// Coins sent to the bare pubkey (P2PK) of a P2PKH address are indexed
+    /// under the pubkey, not the address. The pubkey is only known once the
+    /// address spent coins, so the PUBKEY_SCAN_TXS most recent txs are
+    /// searched for it. Returns the P2PK script as hex and its number of txs.
+    async fn address_p2pk_history(
+        &self,
+        address: &CashAddress<'_>,
+    ) -> Result<Option<(String, u32)>> {
+        if address.addr_type() != AddressType::P2PKH {
+            return Ok(None);
+        }
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+        let address_bytes = address.to_script().bytecode().to_vec();
+        let address_tx_history = script_endpoint
+            .history_with_page_size(0, PUBKEY_SCAN_TXS)
+            .await?;
+
+        let pubkey = address_tx_history
+            .txs
+            .iter()
+            .flat_map(|tx| &tx.inputs)
+            .filter(|input| input.output_script == address_bytes)
+            .find_map(|input| input_script_pubkey(&input.input_script));
+        let pubkey = match pubkey {
+            Some(pubkey) => pubkey,
+            None => return Ok(None),
+        };
+
+        let p2pk_history = self
+            .chronik
+            .script(ScriptType::P2pk, pubkey)
+            .history_with_page_size(0, 1)
+            .await?;
+        if p2pk_history.num_pages == 0 {
+            return Ok(None);
+        }
+        Ok(Some((
+            hex::encode(p2pk_script(pubkey)),
+            p2pk_history.num_pages,
+        )))
+    }
+
+    /// Addresses that were spent together with `address` as inputs of the same
+    /// tx, with the number of such txs, most frequent first. Wallets usually
+    /// only co-spend coins they own, so these likely share an owner. Only the
+    /// COSPEND_SCAN_TXS most recent txs of the address are scanned.
+    async fn cospent_addresses(&self, address: &CashAddress<'_>) -> Result<Vec<(String, u32)>> {
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+        let address_bytes = address.to_script().bytecode().to_vec();
+        let address_tx_history = script_endpoint
+            .history_with_page_size(0, COSPEND_SCAN_TXS)
+            .await?;
+
+        let mut cospent_counts: HashMap<String, u32> = HashMap::new();
+        for tx in &address_tx_history.txs {
+            if !tx
+                .inputs
+                .iter()
+                .any(|input| input.output_script == address_bytes)
+            {
+                continue;
+            }
+            let cospent_addresses = tx
+                .inputs
+                .iter()
+                .filter(|input| input.output_script != address_bytes)
+                .filter_map(|input| {
+                    match destination_from_script(self.satoshi_addr_prefix, &input.output_script) {
+                        Destination::Address(address) => Some(address.as_str().to_string()),
+                        _ => None,
+                    }
+                })
+                .collect::<HashSet<_>>();
+            for cospent_address in cospent_addresses {
+                *cospent_counts.entry(cospent_address).or_default() += 1;
+            }
+        }
+
+        let mut cospent_addresses = cospent_counts.into_iter().collect::<Vec<_>>();
+        cospent_addresses.sort_by(|(address_a, count_a), (address_b, count_b)| {
+            count_b.cmp(count_a).then_with(|| address_a.cmp(address_b))
+        });
+        cospent_addresses.truncate(MAX_COSPENT_ADDRESSES);
+        Ok(cospent_addresses)
+    }
+
+    /// Chronik can't filter a history by token, so this scans the most recent
+    /// txs of the address for those involving `token_id`.
+    /// History is newest first, so scanning stops at the first page that
+    /// reaches below the `from` date. The result is cached until the address
+    /// gets a new tx, as the address page and its tx table both need it.
+    async fn filtered_tx_history(
+        &self,
+        address: &CashAddress<'_>,
+        filter: &TxHistoryFilter,
+    ) -> Result<Arc<Vec<Tx>>> {
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+        let cache_key = (address.to_script().bytecode().to_vec(), filter.clone());
+
+        let latest_history = script_endpoint.history_with_page_size(0, 1).await?;
+        let num_txs = latest_history.num_pages;
+        let latest_txid = match latest_history.txs.first() {
+            Some(latest_tx) => latest_tx.txid.clone(),
+            None => return Ok(Arc::new(Vec::new())),
+        };
+        if let Some((cached_txid, cached_num_txs, txs)) =
+            self.filtered_histories.lock().unwrap().get(&cache_key)
+        {
+            if *cached_txid == latest_txid && *cached_num_txs == num_txs {
+                return Ok(Arc::clone(txs));
+            }
+        }
+
+        let mut filtered_txs = Vec::new();
+        for page in 0..TOKEN_FILTER_MAX_PAGES {
+            let address_tx_history = script_endpoint
+                .history_with_page_size(page, TOKEN_FILTER_PAGE_SIZE)
+                .await?;
+            let num_pages = address_tx_history.num_pages as usize;
+            let reached_from = address_tx_history
+                .txs
+                .last()
+                .map_or(false, |tx| filter.is_before_range(tx));
+
+            filtered_txs.extend(
+                address_tx_history
+                    .txs
+                    .into_iter()
+                    .filter(|tx| filter.matches(tx)),
+            );
+
+            if reached_from || page + 1 >= num_pages {
+                break;
+            }
+        }
+
+        let filtered_txs = Arc::new(filtered_txs);
+        let mut filtered_histories = self.filtered_histories.lock().unwrap();
+        if filtered_histories.len() >= MAX_FILTERED_HISTORY_CACHE_SIZE {
+            filtered_histories.clear();
+        }
+        filtered_histories.insert(cache_key, (latest_txid, num_txs, Arc::clone(&filtered_txs)));
+
+        Ok(filtered_txs)
+    }
+
+    /// UTXOs can be sorted by `value` (largest first) or `age` (oldest first),
+    /// otherwise they're returned in Chronik's order.
+    pub async fn data_address_utxos(
+        &self,
+        address: &str,
+        query: UtxosQuery,
+    ) -> Result<JsonUtxosResponse> {
+        let address = CashAddress::parse_cow(address.into())?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+
+        let page = query.page.unwrap_or(0);
+        let take = query.take.unwrap_or(DEFAULT_API_PAGE_SIZE);
+        self.check_page_size(take)?;
+
+        let utxos = script_endpoint.utxos().await?;
+        let blockchain_info = self.chronik.blockchain_info().await?;
+
+        let mut json_utxos = utxos
+            .iter()
+            .flat_map(|utxo_script| utxo_script.utxos.iter())
+            .map(|utxo| utxo_to_json(utxo, blockchain_info.tip_height))
+            .collect::<Vec<_>>();
+
+        match query.sort {
+            Some(UtxoSort::Value) => json_utxos.sort_by(|a, b| b.sats_amount.cmp(&a.sats_amount)),
+            Some(UtxoSort::Age) => json_utxos.sort_by(|a, b| b.confirmations.cmp(&a.confirmations)),
+            None => {}
+        }
+
+        let num_utxos = json_utxos.len();
+        let json_utxos = json_utxos
+            .into_iter()
+            .skip(page.saturating_mul(take))
+            .take(take)
+            .collect();
+        let unconfirmed_txs = self.unconfirmed_txs(&address).await?;
+        let (unconfirmed_received_sats, unconfirmed_sent_sats) =
+            unconfirmed_flows(&unconfirmed_txs, &address.to_script().bytecode().to_vec());
+
+        Ok(JsonUtxosResponse {
+            data: json_utxos,
+            num_utxos,
+            unconfirmed_received_sats,
+            unconfirmed_sent_sats,
+        })
+    }
+
+    /// XEC UTXOs of an address for importing into a wallet. Token UTXOs are
+    /// left out, as wallets without token support would burn the tokens.
+    pub async fn address_utxos_export(&self, address: &str) -> Result<Vec<JsonUtxoExport>> {
+        let address = CashAddress::parse_cow(address.into())?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let utxos = self
+            .chronik
+            .script(script_type, &script_payload)
+            .utxos()
+            .await?;
+
+        let mut exports = Vec::new();
+        for script_utxos in &utxos {
+            for utxo in &script_utxos.utxos {
+                if utxo.slp_token.is_some() {
+                    continue;
+                }
+                let outpoint = utxo.outpoint.as_ref().expect("Impossible");
+                exports.push(JsonUtxoExport {
+                    txid: to_be_hex(&outpoint.txid),
+                    vout: outpoint.out_idx,
+                    value: utxo.value,
+                    height: utxo.block_height.max(0),
+                    script_pub_key: hex::encode(&script_utxos.output_script),
+                });
+            }
+        }
+        Ok(exports)
+    }
+
+    pub async fn address_utxos_csv(&self, address: &str) -> Result<String> {
+        let mut csv = String::from("txid,vout,value,height,scriptPubKey\n");
+        for utxo in self.address_utxos_export(address).await? {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                utxo.txid, utxo.vout, utxo.value, utxo.height, utxo.script_pub_key
+            ));
+        }
+        Ok(csv)
+    }
+
+    pub async fn data_address_balances(
+        &self,
+        addresses: Vec<String>,
+    ) -> Result<JsonAddressBalancesResponse> {
+        check_bulk_addresses(&addresses, MAX_BULK_ADDRESSES)?;
+
+        let balance_calls = addresses
+            .iter()
+            .map(|address| Box::pin(self.address_balance(address)));
+        let json_balances = future::try_join_all(balance_calls).await?;
+
+        Ok(JsonAddressBalancesResponse {
+            data: json_balances,
+        })
+    }
+
+    pub async fn data_token(&self, token_id: &str) -> Result<JsonTokenMeta> {
+        let token_hash = match Sha256d::from_hex_be(token_id) {
+            Ok(token_hash) => token_hash,
+            Err(_) => bail!(BadRequest(format!("Invalid token ID {}", token_id))),
+        };
+        let token = self.chronik.token(&token_hash).await?;
+        match token_meta_to_json(&token)? {
+            Some(token_meta) => Ok(token_meta),
+            None => bail!(BadRequest(format!("{} is not a token", token_id))),
+        }
+    }
+
+    pub async fn data_tokens(&self, token_ids: Vec<String>) -> Result<JsonTokensResponse> {
+        if token_ids.len() > MAX_BULK_TOKENS {
+            bail!(BadRequest(format!(
+                "Too many tokens, at most {} are allowed per request",
+                MAX_BULK_TOKENS
+            )));
+        }
+
+        let mut token_hashes = HashSet::new();
+        for token_id in &token_ids {
+            match Sha256d::from_hex_be(token_id) {
+                Ok(token_hash) => token_hashes.insert(token_hash),
+                Err(_) => bail!(BadRequest(format!("Invalid token ID {}", token_id))),
+            };
+        }
+        let tokens = self.batch_get_chronik_tokens(token_hashes).await?;
+
+        let mut data = Vec::with_capacity(token_ids.len());
+        for token_id in &token_ids {
+            let token_meta = match tokens.get(&token_id.to_lowercase()) {
+                Some(token) => token_meta_to_json(token)?,
+                None => None,
+            };
+            match token_meta {
+                Some(token_meta) => data.push(token_meta),
+                None => bail!(BadRequest(format!("{} is not a token", token_id))),
+            }
+        }
+        Ok(JsonTokensResponse { data })
+    }
+
+    async fn address_balance(&self, address: &str) -> Result<JsonAddressBalance> {
+        let address = CashAddress::parse_cow(address.into())?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+
+        let page_size = 1; // Set to minimum so that num_pages == total existing tx's
+        let (address_tx_history, utxos) = future::try_join(
+            script_endpoint.history_with_page_size(0, page_size),
+            script_endpoint.utxos(),
+        )
+        .await?;
+
+        let last_activity = address_tx_history.txs.first().map(|tx| match &tx.block {
+            Some(block) => block.timestamp,
+            None => tx.time_first_seen,
+        });
+
+        let mut sats_amount: i64 = 0;
+        let mut token_balances: HashMap<String, JsonTokenBalance> = HashMap::new();
+
+        for utxo in utxos.into_iter().flat_map(|utxo_script| utxo_script.utxos) {
+            match (&utxo.slp_meta, &utxo.slp_token) {
+                (Some(slp_meta), Some(slp_token)) => {
+                    let token_id_hex = hex::encode(&slp_meta.token_id);
+                    match token_balances.entry(token_id_hex) {
+                        Entry::Occupied(mut entry) => {
+                            let entry = entry.get_mut();
+                            entry.sats_amount += utxo.value;
+                            entry.token_amount += i128::from(slp_token.amount);
+                        }
+                        Entry::Vacant(entry) => {
+                            let token_id = entry.key().clone();
+                            entry.insert(JsonTokenBalance {
+                                token_id,
+                                sats_amount: utxo.value,
+                                token_amount: slp_token.amount.into(),
+                            });
+                        }
+                    }
+                }
+                _ => sats_amount += utxo.value,
+            }
+        }
+
+        Ok(JsonAddressBalance {
+            address: address.as_str().to_string(),
+            sats_amount,
+            tokens: token_balances.into_values().collect(),
+            num_txs: address_tx_history.num_pages,
+            last_activity,
+        })
+    }
+
+    /// Runs the queries concurrently and returns their results in the order
+    /// of the queries.
+    /// Each query makes one Chronik request. Txs are returned without their
+    /// coin days destroyed, which would take a request per spent tx.
+    pub async fn data_batch(&self, queries: Vec<JsonBatchQuery>) -> Result<JsonBatchResponse> {
+        let data = stream::iter(queries)
+            .map(|query| async move {
+                let result = match query {
+                    JsonBatchQuery::Tx { hash } => self.batch_tx(&hash).await,
+                    JsonBatchQuery::Block { hash } => self.batch_block(&hash).await,
+                    JsonBatchQuery::BlockHeight { height } => self.batch_block_height(height).await,
+                    JsonBatchQuery::AddressBalance { address } => self
+                        .address_balance(&address)
+                        .await
+                        .map(JsonBatchResult::AddressBalance),
+                };
+                result.unwrap_or_else(|err| JsonBatchResult::Error {
+                    error: err.to_string(),
+                })
+            })
+            .buffered(CHART_FETCH_CONCURRENCY)
+            .collect()
+            .await;
+
+        Ok(JsonBatchResponse { data })
+    }
+
+    /// Checks the number of queries of a batch, which the API quota is
+    /// charged for
+    pub fn check_batch_size(&self, num_queries: usize) -> Result<()> {
+        if num_queries > MAX_BATCH_QUERIES {
+            bail!(BadRequest(format!(
+                "Too many queries, at most {} are allowed per request",
+                MAX_BATCH_QUERIES
+            )));
+        }
+        Ok(())
+    }
+
+    async fn batch_tx(&self, tx_hex: &str) -> Result<JsonBatchResult> {
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        Ok(JsonBatchResult::Tx(tx_details_to_json(
+            &tx,
+            self.satoshi_addr_prefix,
+        )))
+    }
+
+    async fn batch_block(&self, block_hex: &str) -> Result<JsonBatchResult> {
+        let block_hash = Sha256d::from_hex_be(block_hex)?;
+        let block = self.chronik.block_by_hash(&block_hash).await?;
+        let block_info = block.block_info.ok_or_else(|| eyre!("Block has no info"))?;
+        Ok(JsonBatchResult::Block(block_info_to_json(&block_info)))
+    }
+
+    async fn batch_block_height(&self, height: i32) -> Result<JsonBatchResult> {
+        let block = self.chronik.block_by_height(height).await?;
+        let block_info = block.block_info.ok_or_else(|| eyre!("Block has no info"))?;
+        Ok(JsonBatchResult::Block(block_info_to_json(&block_info)))
+    }
+
+    /// Broadcasts dependent txs in order, stopping at the first rejected one.
+    /// Txs broadcast before the failure stay in the mempool, so the response
+    /// reports which ones were accepted.
+    pub async fn data_tx_package(&self, raw_txs: Vec<String>) -> Result<JsonTxPackageResponse> {
+        if raw_txs.is_empty() || raw_txs.len() > MAX_TX_PACKAGE_SIZE {
+            bail!(BadRequest(format!(
+                "A package must contain between 1 and {} txs",
+                MAX_TX_PACKAGE_SIZE
+            )));
+        }
+
+        // Decode everything upfront so a malformed tx doesn't leave a partial package
+        let raw_txs = raw_txs
+            .iter()
+            .map(hex::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut accepted_txids = Vec::with_capacity(raw_txs.len());
+        for (idx, raw_tx) in raw_txs.into_iter().enumerate() {
+            match self.chronik.broadcast_tx(raw_tx).await {
+                Ok(response) => {
+                    self.send_mempool_tx_event(&response.txid).await;
+                    accepted_txids.push(to_be_hex(&response.txid));
+                }
+                Err(err) => {
+                    return Ok(JsonTxPackageResponse {
+                        accepted_txids,
+                        failed_index: Some(idx),
+                        error: Some(err.to_string()),
+                    });
+                }
+            }
+        }
+
+        Ok(JsonTxPackageResponse {
+            accepted_txids,
+            failed_index: None,
+            error: None,
+        })
+    }
+
+    /// Sends the `new-tx` event of a tx that entered the mempool through the
+    /// explorer. Chronik doesn't expose the mempool, so other mempool txs
+    /// only get their event once they're mined.
+    async fn send_mempool_tx_event(&self, txid: &[u8]) {
+        let tx =
+            async { Ok::<_, eyre::Report>(self.chronik.tx(&Sha256d::from_slice(txid)?).await?) };
+        match tx.await {
+            // Sending only fails if nobody is subscribed, which is fine
+            Ok(tx) => {
+                let _ = self
+                    .events
+                    .send(ExplorerEvent::NewTx(new_tx_json(&tx, None)));
+            }
+            Err(err) => eprintln!(
+                "Looking up broadcast tx {} failed: {}",
+                to_be_hex(txid),
+                err
+            ),
+        }
+    }
 }
 
 impl Server {
-    pub async fn block(&self, block_hex: &str) -> Result<String> {
+    pub async fn block(
+        &self,
+        block_hex: &str,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
         let block_hash = Sha256d::from_hex_be(block_hex)?;
 
         let block = self.chronik.block_by_hash(&block_hash).await?;
@@ -176,11 +1947,31 @@ impl Server {
 
         let blockchain_info = self.chronik.blockchain_info().await?;
         let best_height = blockchain_info.tip_height;
+        let prev_block_hash = prev_block_hash(&block_info);
+        let next_block_hash = self.next_block_hash(&block_info, best_height).await?;
 
         let difficulty = calculate_block_difficulty(block_info.n_bits);
+        let work = block_work(block_info.n_bits).to_f64();
+        let chainwork = self
+            .chainwork
+            .get(block_info.height)
+            .map(|chainwork| chainwork.to_hex());
         let timestamp = Utc.timestamp(block_info.timestamp, 0);
-        let coinbase_data = block.txs[0].inputs[0].input_script.clone();
         let confirmations = best_height - block_info.height + 1;
+        let fee_stats = calc_block_fee_stats(&block.txs);
+        let output_types = calc_output_type_counts(&block.txs);
+        let token_summary = calc_block_token_summary(&block.txs);
+        let coinbase = coinbase_to_json(
+            &block.txs[0],
+            &block_info,
+            self.network,
+            self.satoshi_addr_prefix,
+        );
+        let structured_data = block_structured_data(&block_info, self.public_url())?;
+        let received_at = self
+            .block_receive_times
+            .get(&block_hash.to_hex_be())
+            .map(|received_at| Utc.timestamp(received_at, 0));
 
         let block_template = BlockTemplate {
             block_hex,
@@ -189,15 +1980,32 @@ impl Server {
             block_details,
             confirmations,
             timestamp,
+            received_at,
             difficulty,
-            coinbase_data,
-            best_height
+            work,
+            chainwork,
+            best_height,
+            prev_block_hash,
+            next_block_hash,
+            fee_stats,
+            coinbase,
+            output_types,
+            token_summary,
+            structured_data,
+            locale,
+            unit,
+            network: self.network,
         };
 
         Ok(block_template.render().unwrap())
     }
 
-    pub async fn tx(&self, tx_hex: &str) -> Result<String> {
+    pub async fn tx(
+        &self,
+        tx_hex: &str,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
         let tx_hash = Sha256d::from_hex_be(tx_hex)?;
         let tx = self.chronik.tx(&tx_hash).await?;
         let token_id = match &tx.slp_tx_data {
@@ -279,6 +2087,47 @@ impl Server {
         let raw_tx = raw_tx.hex();
 
         let tx_stats = calc_tx_stats(&tx, None);
+        let size_breakdown = calc_tx_size_breakdown(&tx);
+        let warnings = calc_tx_warnings(&tx);
+        let fee_rate = match tx.size {
+            0 => 0,
+            size => (tx_stats.sats_input - tx_stats.sats_output).max(0) * 1000 / i64::from(size),
+        };
+        let token_burns = self.token_burns_to_json(&tx).await?;
+        let tx_flows = calc_tx_flows(&tx, self.satoshi_addr_prefix);
+        let address_labels = self.tx_address_labels(&tx);
+        let sequences = tx
+            .inputs
+            .iter()
+            .map(|input| input.sequence_no)
+            .collect::<Vec<_>>();
+        let lock_time_description = describe_lock_time(tx.lock_time, &sequences);
+        let coin_days_destroyed = self.coin_days_destroyed(&tx).await;
+        let structured_data = tx_structured_data(&tx, self.public_url())?;
+        let slp_genesis_info = token.and_then(|token| token.slp_tx_data?.genesis_info);
+        let slp_meta = tx
+            .slp_tx_data
+            .as_ref()
+            .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref());
+        let token_doc_check = match (&self.token_doc_checker, slp_meta, &slp_genesis_info) {
+            (Some(checker), Some(slp_meta), Some(genesis_info))
+                if genesis_info.token_document_hash.len() == 32 =>
+            {
+                Some(checker.check(
+                    &slp_meta.token_id,
+                    &String::from_utf8_lossy(&genesis_info.token_document_url),
+                    &genesis_info.token_document_hash,
+                ))
+            }
+            _ => None,
+        };
+        let mint_baton = self.mint_baton(&tx).await?;
+        let mempool_chain = self.mempool_chain(&tx).await;
+        let alp_sections = tx
+            .outputs
+            .first()
+            .map(|output| parse_alp_sections(&output.output_script))
+            .unwrap_or_default();
 
         let transaction_template = TransactionTemplate {
             title: &title,
@@ -290,15 +2139,37 @@ impl Server {
                 .slp_tx_data
                 .as_ref()
                 .and_then(|slp_tx_data| slp_tx_data.slp_meta.clone()),
+            time_first_seen: tx_time_first_seen(&tx),
+            confirmation_delay_secs: tx_confirmation_delay_secs(&tx),
             tx,
-            slp_genesis_info: token.and_then(|token| token.slp_tx_data?.genesis_info),
+            slp_genesis_info,
+            token_doc_check,
+            mint_baton,
+            mempool_chain,
             sats_input: tx_stats.sats_input,
             sats_output: tx_stats.sats_output,
             token_input: tx_stats.token_input,
             token_output: tx_stats.token_output,
+            token_burns,
+            tx_flows,
+            alp_sections,
+            address_labels,
+            lock_time_description,
+            coin_days_destroyed: coin_days_destroyed.map(|cdd| cdd.round() as i64),
+            value_usd: self
+                .prices
+                .value_at(tx_stats.sats_output, timestamp.timestamp()),
             raw_tx,
+            size_breakdown,
+            warnings,
+            fee_rate,
             confirmations,
+            is_confirmed: confirmations >= CONFIRMATION_DEPTH,
             timestamp,
+            structured_data,
+            locale,
+            unit,
+            network: self.network,
         };
 
         Ok(transaction_template.render().unwrap())
@@ -306,12 +2177,373 @@ impl Server {
 }
 
 impl Server {
-    pub async fn address<'a>(&'a self, address: &str) -> Result<String> {
+    /// Walks the unconfirmed ancestors of a mempool tx via the input outpoints
+    /// and its descendants via the outputs' spending txs. `None` for mined
+    /// txs. Every tx is fetched from Chronik, so each direction stops after
+    /// `MAX_MEMPOOL_CHAIN_LOOKUPS` lookups. Failed lookups only mark the chain
+    /// as truncated, so they don't fail the tx page.
+    async fn mempool_chain(&self, tx: &Tx) -> Option<MempoolChain> {
+        if tx.block.is_some() {
+            return None;
+        }
+        let mut chain = MempoolChain::default();
+
+        let parent_txids = tx
+            .inputs
+            .iter()
+            .map(|input| input.prev_out.as_ref().expect("Impossible").txid.clone())
+            .collect();
+        let ancestors_truncated = self
+            .walk_mempool_txs(parent_txids, |txid, ancestor, depth| {
+                // Confirmed txs end the chain
+                if ancestor.block.is_some() {
+                    return Vec::new();
+                }
+                if depth == 0 {
+                    chain.parent_tx_hashes.push(to_be_hex(txid));
+                }
+                chain.ancestors.add(ancestor.size, tx_fee_sats(ancestor));
+                ancestor
+                    .inputs
+                    .iter()
+                    .map(|input| input.prev_out.as_ref().expect("Impossible").txid.clone())
+                    .collect()
+            })
+            .await;
+
+        // Txs spending a mempool tx are unconfirmed too
+        let child_txids = spending_txids(tx).into_iter().collect();
+        let descendants_truncated = self
+            .walk_mempool_txs(child_txids, |_, descendant, _| {
+                chain
+                    .descendants
+                    .add(descendant.size, tx_fee_sats(descendant));
+                spending_txids(descendant).into_iter().collect()
+            })
+            .await;
+
+        chain.is_truncated = ancestors_truncated || descendants_truncated;
+        Some(chain)
+    }
+
+    /// Looks up the txs breadth-first, starting at `txids`, and calls `visit`
+    /// with each tx and its depth, which returns the txids of the next level.
+    /// The txs of a level are fetched concurrently. Returns whether the walk
+    /// hit `MAX_MEMPOOL_CHAIN_LOOKUPS` or a lookup failed.
+    async fn walk_mempool_txs(
+        &self,
+        txids: Vec<Vec<u8>>,
+        mut visit: impl FnMut(&[u8], &Tx, usize) -> Vec<Vec<u8>>,
+    ) -> bool {
+        let mut is_truncated = false;
+        let mut seen_txids = HashSet::new();
+        let mut num_lookups = 0;
+        let mut level = txids;
+        let mut depth = 0;
+        while !level.is_empty() {
+            level.retain(|txid| seen_txids.insert(txid.clone()));
+            if num_lookups + level.len() > MAX_MEMPOOL_CHAIN_LOOKUPS {
+                level.truncate(MAX_MEMPOOL_CHAIN_LOOKUPS - num_lookups);
+                is_truncated = true;
+            }
+            num_lookups += level.len();
+
+            let txs = stream::iter(&level)
+                .map(|txid| async move { self.chronik.tx(&Sha256d::from_slice(txid)?).await })
+                .buffered(MEMPOOL_CHAIN_FETCH_CONCURRENCY)
+                .collect::<Vec<_>>()
+                .await;
+            let mut next_level = Vec::new();
+            for (txid, tx) in level.iter().zip(txs) {
+                match tx {
+                    Ok(tx) => next_level.extend(visit(txid, &tx, depth)),
+                    Err(err) => {
+                        eprintln!(
+                            "Looking up mempool chain tx {} failed: {}",
+                            to_be_hex(txid),
+                            err
+                        );
+                        is_truncated = true;
+                    }
+                }
+            }
+            level = next_level;
+            depth += 1;
+        }
+        is_truncated
+    }
+
+    /// Follows the mint baton of a genesis or mint tx through the mints that
+    /// passed it on. `None` for other txs.
+    async fn mint_baton(&self, tx: &Tx) -> Result<Option<MintBaton>> {
+        let slp_meta = match tx
+            .slp_tx_data
+            .as_ref()
+            .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+        {
+            Some(slp_meta) if slp_meta.tx_type() != SlpTxType::Send => slp_meta,
+            _ => return Ok(None),
+        };
+        if slp_meta.token_type() == SlpTokenType::Nft1Child {
+            return Ok(None);
+        }
+
+        let mut tx = Cow::Borrowed(tx);
+        for _ in 0..MAX_MINT_BATON_HOPS {
+            let tx_hash = to_be_hex(&tx.txid);
+            let baton = tx.outputs.iter().enumerate().find(|(_, output)| {
+                output
+                    .slp_token
+                    .as_ref()
+                    .map_or(false, |slp_token| slp_token.is_mint_baton)
+            });
+            let (out_idx, output) = match baton {
+                Some(baton) => baton,
+                None => return Ok(Some(MintBaton::Destroyed { tx_hash })),
+            };
+            let spent_by = match &output.spent_by {
+                Some(spent_by) => spent_by,
+                None => {
+                    let address = match destination_from_script(
+                        self.satoshi_addr_prefix,
+                        &output.output_script,
+                    ) {
+                        Destination::Address(address) => Some(address.as_str().to_string()),
+                        _ => None,
+                    };
+                    return Ok(Some(MintBaton::Active {
+                        tx_hash,
+                        out_idx: out_idx as u32,
+                        address,
+                    }));
+                }
+            };
+
+            // Only a mint of the same token can pass the baton on
+            let spending_tx = self
+                .chronik
+                .tx(&Sha256d::from_slice(&spent_by.txid)?)
+                .await?;
+            let is_mint = spending_tx
+                .slp_tx_data
+                .as_ref()
+                .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+                .map_or(false, |spending_meta| {
+                    spending_meta.tx_type() == SlpTxType::Mint
+                        && spending_meta.token_id == slp_meta.token_id
+                });
+            if !is_mint {
+                return Ok(Some(MintBaton::Destroyed {
+                    tx_hash: to_be_hex(&spending_tx.txid),
+                }));
+            }
+            tx = Cow::Owned(spending_tx);
+        }
+
+        let tx_hash = to_be_hex(&tx.txid);
+        let out_idx = tx
+            .outputs
+            .iter()
+            .position(|output| {
+                output
+                    .slp_token
+                    .as_ref()
+                    .map_or(false, |slp_token| slp_token.is_mint_baton)
+            })
+            .unwrap_or_default() as u32;
+        Ok(Some(MintBaton::Untracked { tx_hash, out_idx }))
+    }
+
+    /// Labels of the addresses of the tx's inputs and outputs
+    fn tx_address_labels(&self, tx: &Tx) -> HashMap<String, String> {
+        let scripts = tx
+            .inputs
+            .iter()
+            .map(|input| &input.output_script)
+            .chain(tx.outputs.iter().map(|output| &output.output_script));
+
+        let mut address_labels = HashMap::new();
+        for script in scripts {
+            if let Destination::Address(address) =
+                destination_from_script(self.satoshi_addr_prefix, script)
+            {
+                if let Some(label) = self.labels.get(&address) {
+                    address_labels.insert(label_key(&address), label);
+                }
+            }
+        }
+        address_labels
+    }
+
+    /// IP address the API quota of a request is counted for
+    pub fn api_client_ip(&self, headers: &HeaderMap, peer_ip: IpAddr) -> IpAddr {
+        match &self.api_quotas {
+            Some(api_quotas) => api_quotas.client_ip(headers, peer_ip),
+            None => peer_ip,
+        }
+    }
+
+    pub fn check_api_quota(&self, api_key: Option<&str>, ip: IpAddr, cost: u32) -> QuotaCheck {
+        match &self.api_quotas {
+            Some(api_quotas) => api_quotas.check(api_key, ip, cost),
+            None => QuotaCheck::Allowed,
+        }
+    }
+
+    pub async fn data_api_usage(&self, api_key: Option<&str>, ip: IpAddr) -> Result<JsonApiUsage> {
+        let api_quotas = match &self.api_quotas {
+            Some(api_quotas) => api_quotas,
+            None => bail!(BadRequest("API quotas are disabled".to_string())),
+        };
+        api_quotas
+            .usage(api_key, ip)
+            .ok_or_else(|| eyre!("Unknown API key"))
+    }
+
+    /// Chronik doesn't tell when the outputs spent by a tx were mined, so this
+    /// looks up the spent txs, unless there are more than `MAX_CDD_PREV_TXS`.
+    /// It's only informational, so it's `None` if a lookup fails. Once the
+    /// tx and the txs it spends are mined it doesn't change, so it's cached.
+    async fn coin_days_destroyed(&self, tx: &Tx) -> Option<f64> {
+        if tx.is_coinbase {
+            return Some(0.0);
+        }
+        if let Some(&cdd) = self.coin_days_destroyed.lock().unwrap().get(&tx.txid) {
+            return Some(cdd);
+        }
+        let prev_txids = tx
+            .inputs
+            .iter()
+            .filter_map(|input| input.prev_out.as_ref())
+            .map(|prev_out| prev_out.txid.clone())
+            .collect::<HashSet<_>>();
+        if prev_txids.len() > MAX_CDD_PREV_TXS {
+            return None;
+        }
+
+        let prev_txs = stream::iter(prev_txids)
+            .map(|txid| async move {
+                let prev_tx_hash = Sha256d::from_slice(&txid)?;
+                self.chronik.tx(&prev_tx_hash).await
+            })
+            .buffered(CDD_FETCH_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await;
+        let prev_txs = match prev_txs {
+            Ok(prev_txs) => prev_txs,
+            Err(err) => {
+                eprintln!(
+                    "Looking up the txs spent by {} failed: {}",
+                    to_be_hex(&tx.txid),
+                    err
+                );
+                return None;
+            }
+        };
+        let is_final = tx.block.is_some() && prev_txs.iter().all(|prev_tx| prev_tx.block.is_some());
+        let prev_timestamps = prev_txs
+            .into_iter()
+            .filter_map(|prev_tx| Some((prev_tx.txid, prev_tx.block?.timestamp)))
+            .collect::<HashMap<_, _>>();
+        let cdd = calc_coin_days_destroyed(tx, &prev_timestamps);
+
+        if is_final {
+            let mut coin_days_destroyed = self.coin_days_destroyed.lock().unwrap();
+            if coin_days_destroyed.len() >= MAX_CACHED_CDD {
+                coin_days_destroyed.clear();
+            }
+            coin_days_destroyed.insert(tx.txid.clone(), cdd);
+        }
+
+        Some(cdd)
+    }
+
+    /// Checks the bearer token of an admin request. Without an
+    /// `admin_token` configured, the admin API is disabled.
+    pub fn check_admin_token(&self, token: Option<&str>, ip: IpAddr) -> AdminCheck {
+        match &self.admin_auth {
+            Some(admin_auth) => admin_auth.check(token, ip),
+            None => AdminCheck::Unauthorized,
+        }
+    }
+
+    pub async fn data_admin_set_label(
+        &self,
+        request: JsonAddressLabelRequest,
+    ) -> Result<JsonAddressLabelRequest> {
+        let address = CashAddress::parse_cow(request.address.as_str().into())?;
+        let label = match request.label {
+            Some(label) if label.trim().is_empty() => None,
+            Some(label) => Some(label.trim().to_string()),
+            None => None,
+        };
+        self.labels.set(&address, label.clone())?;
+
+        Ok(JsonAddressLabelRequest {
+            address: label_key(&address),
+            label,
+        })
+    }
+
+    pub fn data_admin_webhooks(&self) -> Vec<JsonWebhook> {
+        self.webhooks.list()
+    }
+
+    pub fn data_admin_add_webhook(&self, request: JsonWebhookRequest) -> Result<JsonWebhook> {
+        self.webhooks.add(request)
+    }
+
+    pub fn data_admin_remove_webhook(&self, id: u64) -> Result<()> {
+        if !self.webhooks.remove(id) {
+            bail!(BadRequest(format!("No webhook with id {}", id)));
+        }
+        Ok(())
+    }
+
+    async fn token_burns_to_json(&self, tx: &Tx) -> Result<Vec<JsonTokenBurn>> {
+        let burns = calc_token_burns(tx);
+        let token_ids = burns
+            .iter()
+            .map(|(token_id, _, _)| Sha256d::from_hex_be(token_id))
+            .collect::<Result<HashSet<_>, _>>()?;
+        let tokens = self.batch_get_chronik_tokens(token_ids).await?;
+
+        Ok(burns
+            .into_iter()
+            .map(|(token_id, amount, is_invalid_spend)| {
+                let genesis_info = tokens
+                    .get(&token_id)
+                    .and_then(|token| token.slp_tx_data.as_ref()?.genesis_info.as_ref());
+                JsonTokenBurn {
+                    token_ticker: genesis_info
+                        .map(|genesis_info| {
+                            String::from_utf8_lossy(&genesis_info.token_ticker).to_string()
+                        })
+                        .unwrap_or_default(),
+                    decimals: genesis_info.map_or(0, |genesis_info| genesis_info.decimals),
+                    token_id,
+                    amount,
+                    is_invalid_spend,
+                }
+            })
+            .collect())
+    }
+}
+
+impl Server {
+    pub async fn address<'a>(
+        &'a self,
+        address: &str,
+        query: AddressQuery,
+        raw_query: Option<&str>,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
         let address = CashAddress::parse_cow(address.into())?;
         let sats_address = address.with_prefix(self.satoshi_addr_prefix);
         let token_address = address.with_prefix(self.tokens_addr_prefix);
 
-        let legacy_address = to_legacy_address(&address);
+        let legacy_address = to_legacy_address(&address, self.network);
         let sats_address = sats_address.as_str();
         let token_address = token_address.as_str();
 
@@ -319,12 +2551,22 @@ impl Server {
         let script_endpoint = self.chronik.script(script_type, &script_payload);
         let page_size = 1; // Set to minimum so that num_pages == total existing tx's
         let address_tx_history = script_endpoint.history_with_page_size(0, page_size).await?;
-        let address_num_txs = address_tx_history.num_pages;
+        let filter = TxHistoryFilter::new(
+            query.token.clone(),
+            query.from.as_deref(),
+            query.to.as_deref(),
+        )?;
+        let address_num_txs = match filter {
+            Some(filter) => self.filtered_tx_history(&address, &filter).await?.len() as u32,
+            None => address_tx_history.num_pages,
+        };
 
         let utxos = script_endpoint.utxos().await?;
+        let blockchain_info = self.chronik.blockchain_info().await?;
 
         let mut token_dust: i64 = 0;
         let mut total_xec: i64 = 0;
+        let mut immature_xec: i64 = 0;
 
         let mut token_ids: HashSet<Sha256d> = HashSet::new();
         let mut token_utxos: Vec<Utxo> = Vec::new();
@@ -338,23 +2580,13 @@ impl Server {
 
         for utxo_script in utxos.into_iter() {
             for utxo in utxo_script.utxos.into_iter() {
-                let OutPoint { txid, out_idx } = &utxo.outpoint.as_ref().unwrap();
-                let mut json_utxo = JsonUtxo {
-                    tx_hash: to_be_hex(txid),
-                    out_idx: *out_idx,
-                    sats_amount: utxo.value,
-                    token_amount: 0,
-                    is_coinbase: utxo.is_coinbase,
-                    block_height: utxo.block_height,
-                };
+                let json_utxo = utxo_to_json(&utxo, blockchain_info.tip_height);
 
                 match (&utxo.slp_meta, &utxo.slp_token) {
                     (Some(slp_meta), Some(slp_token)) => {
                         let token_id_hex = hex::encode(&slp_meta.token_id);
                         let token_id_hash = Sha256d::from_slice_be_or_null(&slp_meta.token_id);
 
-                        json_utxo.token_amount = slp_token.amount;
-
                         match json_balances.entry(token_id_hex) {
                             Entry::Occupied(mut entry) => {
                                 let entry = entry.get_mut();
@@ -378,6 +2610,9 @@ impl Server {
                     }
                     _ => {
                         total_xec += utxo.value;
+                        if json_utxo.immature_blocks_remaining.is_some() {
+                            immature_xec += utxo.value;
+                        }
                         main_json_balance.utxos.push(json_utxo);
                     }
                 };
@@ -391,12 +2626,47 @@ impl Server {
         let encoded_tokens = serde_json::to_string(&json_tokens)?.replace('\'', r"\'");
         let encoded_balances = serde_json::to_string(&json_balances)?.replace('\'', r"\'");
 
+        let label = self.labels.get(&address);
+        let summary = self.address_summary(&address).await?;
+        let cospent_addresses = self.cospent_addresses(&address).await?;
+        let address_script = address.to_script().bytecode().to_vec();
+        let unconfirmed_txs = self.unconfirmed_txs(&address).await?;
+        let (unconfirmed_received_xec, unconfirmed_sent_xec) =
+            unconfirmed_flows(&unconfirmed_txs, &address_script);
+        let unconfirmed_txs = tx_history_to_json(&address_script, &unconfirmed_txs, &json_tokens)?;
+        let unconfirmed_delta_sats = unconfirmed_txs.iter().map(|tx| tx.stats.delta_sats).sum();
+        let (p2pk_script, p2pk_num_txs) = match self.address_p2pk_history(&address).await? {
+            Some((p2pk_script, p2pk_num_txs)) => (Some(p2pk_script), p2pk_num_txs),
+            None => (None, 0),
+        };
+
         let address_template = AddressTemplate {
+            label,
+            summary,
+            cospent_addresses,
             tokens,
             token_utxos,
             token_dust,
             total_xec,
+            spendable_xec: total_xec - immature_xec,
+            unconfirmed_received_xec,
+            unconfirmed_sent_xec,
+            unconfirmed_txs,
+            unconfirmed_delta_sats,
+            p2pk_script,
+            p2pk_num_txs,
             address_num_txs,
+            has_price_history: !self.prices.is_empty(),
+            pagination: Pagination::from_query(
+                &format!("/address/{}", address.as_str()),
+                raw_query,
+                query.page,
+                query.rows,
+                address_num_txs,
+            ),
+            token_filter: query.token,
+            date_from: query.from,
+            date_to: query.to,
             address: address.as_str(),
             sats_address,
             token_address,
@@ -404,11 +2674,98 @@ impl Server {
             json_balances,
             encoded_tokens,
             encoded_balances,
+            locale,
+            unit,
+            network: self.network,
         };
 
         Ok(address_template.render().unwrap())
     }
 
+    /// Address of a P2PKH or P2SH script, which have their own pages
+    pub fn script_address(&self, script_hex: &str) -> Option<String> {
+        let script = hex::decode(script_hex).ok()?;
+        match destination_from_script(self.satoshi_addr_prefix, &script) {
+            Destination::Address(address) => Some(address.as_str().to_string()),
+            _ => None,
+        }
+    }
+
+    /// History and balance of any locking script, e.g. a bare pubkey (P2PK)
+    /// or a custom script
+    pub async fn script(
+        &self,
+        script_hex: &str,
+        query: PageQuery,
+        raw_query: Option<&str>,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
+        let script = match hex::decode(script_hex) {
+            Ok(script) => script,
+            Err(_) => bail!(BadRequest(format!("Invalid script hex {}", script_hex))),
+        };
+        let (script_type, script_payload) = script_type_payload(&script);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+
+        let rows = query.rows.map_or(DEFAULT_ROWS_PER_PAGE, |rows| rows.max(1));
+        self.check_page_size(rows as usize)?;
+        let num_txs = script_endpoint
+            .history_with_page_size(0, 1)
+            .await?
+            .num_pages;
+        let pagination = Pagination::from_query(
+            &format!("/script/{}", script_hex),
+            raw_query,
+            query.page,
+            Some(rows),
+            num_txs,
+        );
+        let txs = script_endpoint
+            .history_with_page_size(pagination.current_page as usize - 1, rows as usize)
+            .await?
+            .txs;
+        let txs = tx_history_to_json(&script, &txs, &HashMap::new())?;
+
+        let balance_sats = script_endpoint
+            .utxos()
+            .await?
+            .iter()
+            .flat_map(|utxo_script| &utxo_script.utxos)
+            .map(|utxo| utxo.value)
+            .sum();
+
+        let (script_kind, pubkey_address) =
+            match destination_from_script(self.satoshi_addr_prefix, &script) {
+                Destination::P2PK(pubkey) => (
+                    "Pay to public key (P2PK)",
+                    Some(
+                        pubkey_address(self.satoshi_addr_prefix, &pubkey)
+                            .as_str()
+                            .to_string(),
+                    ),
+                ),
+                Destination::Nulldata(_) => ("OP_RETURN data", None),
+                Destination::Address(_) | Destination::Unknown(_) => ("Custom script", None),
+            };
+
+        let script_template = ScriptTemplate {
+            script_hex,
+            script,
+            script_kind,
+            pubkey_address,
+            balance_sats,
+            num_txs,
+            txs,
+            pagination,
+            locale,
+            unit,
+            network: self.network,
+        };
+
+        Ok(script_template.render().unwrap())
+    }
+
     pub async fn batch_get_chronik_tokens(
         &self,
         token_ids: HashSet<Sha256d>,
@@ -432,45 +2789,526 @@ impl Server {
         Ok(token_map)
     }
 
-    pub async fn address_qr(&self, address: &str) -> Result<Vec<u8>> {
+    /// Returns the content type and the QR code image for `address`, or for a
+    /// payment URI if `amount` or `label` are given.
+    pub async fn tx_widget(
+        &self,
+        tx_hex: &str,
+        query: WidgetQuery,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
+        let style = widget_style(&query)?;
+        let tx_hash = Sha256d::from_hex_be(tx_hex)?;
+        let tx = self.chronik.tx(&tx_hash).await?;
+        let blockchain_info = self.chronik.blockchain_info().await?;
+        let confirmations = match &tx.block {
+            Some(block_meta) => blockchain_info.tip_height - block_meta.height + 1,
+            None => 0,
+        };
+
+        // With an address, only count what it received, e.g. for invoices
+        let sats_amount = match &query.address {
+            Some(address) => {
+                let address =
+                    CashAddress::parse_cow(address.into())?.with_prefix(self.satoshi_addr_prefix);
+                tx.outputs
+                    .iter()
+                    .filter(|output| {
+                        match destination_from_script(
+                            self.satoshi_addr_prefix,
+                            &output.output_script,
+                        ) {
+                            Destination::Address(output_address) => {
+                                output_address.as_str() == address.as_str()
+                            }
+                            _ => false,
+                        }
+                    })
+                    .map(|output| output.value)
+                    .sum()
+            }
+            None => tx.outputs.iter().map(|output| output.value).sum(),
+        };
+
+        let widget_template = TxWidgetTemplate {
+            style,
+            tx_hex,
+            sats_amount,
+            confirmations,
+            locale,
+            unit,
+        };
+        Ok(widget_template.render().unwrap())
+    }
+
+    pub async fn address_widget(
+        &self,
+        address: &str,
+        query: WidgetQuery,
+        locale: DisplayLocale,
+        unit: Denomination,
+    ) -> Result<String> {
+        let style = widget_style(&query)?;
+        let address = CashAddress::parse_cow(address.into())?;
+        let sats_address = address.with_prefix(self.satoshi_addr_prefix);
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let script_endpoint = self.chronik.script(script_type, &script_payload);
+        let num_txs = script_endpoint
+            .history_with_page_size(0, 1)
+            .await?
+            .num_pages;
+
+        let mut sats_amount = 0;
+        let mut pending_sats = 0;
+        for utxo_script in script_endpoint.utxos().await? {
+            for utxo in utxo_script.utxos {
+                if utxo.slp_token.is_some() {
+                    continue;
+                }
+                sats_amount += utxo.value;
+                if utxo.block_height < 0 {
+                    pending_sats += utxo.value;
+                }
+            }
+        }
+
+        let widget_template = AddressWidgetTemplate {
+            style,
+            address: sats_address.as_str(),
+            label: self.labels.get(&address),
+            sats_amount,
+            pending_sats,
+            num_txs,
+            locale,
+            unit,
+        };
+        Ok(widget_template.render().unwrap())
+    }
+
+    pub async fn address_qr(
+        &self,
+        address: &str,
+        query: QrQuery,
+    ) -> Result<(&'static str, Vec<u8>)> {
         use qrcode_generator::QrCodeEcc;
         if address.len() > 60 {
-            bail!("Invalid address length");
+            bail!(BadRequest("Invalid address length".to_string()));
+        }
+
+        let amount = query.amount.as_deref();
+        let label = query.label.as_deref();
+        let data = match (amount, label) {
+            (None, None) => address.to_string(),
+            _ => {
+                if label.map_or(0, |label| label.len()) > MAX_QR_LABEL_LENGTH {
+                    bail!(BadRequest(format!(
+                        "Label must be at most {} bytes",
+                        MAX_QR_LABEL_LENGTH
+                    )));
+                }
+                let address = CashAddress::parse_cow(address.into())?;
+                payment_uri(&address, amount, label)?
+            }
+        };
+
+        let size = query.size.unwrap_or(DEFAULT_QR_SIZE);
+        if size == 0 || size > MAX_QR_SIZE {
+            bail!(BadRequest(format!(
+                "QR code size must be between 1 and {}",
+                MAX_QR_SIZE
+            )));
         }
-        let png = qrcode_generator::to_png_to_vec(address, QrCodeEcc::Quartile, 140)?;
-        Ok(png)
-    }
 
-    pub async fn block_height(&self, height: u32) -> Result<Redirect> {
-        let block = self.chronik.block_by_height(height as i32).await.ok();
+        let ecc = match query.ecc {
+            Some(QrEcc::L) => QrCodeEcc::Low,
+            Some(QrEcc::M) => QrCodeEcc::Medium,
+            Some(QrEcc::Q) | None => QrCodeEcc::Quartile,
+            Some(QrEcc::H) => QrCodeEcc::High,
+        };
 
-        match block {
-            Some(block) => {
-                let block_info = block.block_info.expect("Impossible");
-                Ok(self.redirect(format!("/block/{}", to_be_hex(&block_info.hash))))
+        match query.format.unwrap_or(QrFormat::Png) {
+            QrFormat::Png => {
+                let png = qrcode_generator::to_png_to_vec(&data, ecc, size)?;
+                Ok(("image/png", png))
+            }
+            QrFormat::Svg => {
+                let svg = qrcode_generator::to_svg_to_string(&data, ecc, size, None::<&str>)?;
+                Ok(("image/svg+xml", svg.into_bytes()))
             }
-            None => Ok(self.redirect("/404".into())),
         }
     }
 
-    pub async fn search(&self, query: &str) -> Result<Redirect> {
+    pub async fn token_icon(&self, token_id: &str) -> Result<TokenIcon> {
+        if token_id.len() != 64 || hex::decode(token_id).is_err() {
+            bail!(BadRequest(format!("Invalid token ID {}", token_id)));
+        }
+        let token_id = token_id.to_lowercase();
+        match &self.token_icons {
+            Some(token_icons) => token_icons.icon(&token_id).await,
+            None => Ok(TokenIcon {
+                content_type: "image/png".to_string(),
+                data: identicon_png(token_id.as_bytes())?.into(),
+            }),
+        }
+    }
+
+    /// Identicon of `address` as a PNG, the same for its ecash: and etoken:
+    /// forms
+    pub fn address_identicon(&self, address: &str) -> Result<Bytes> {
+        if address.len() > 60 {
+            bail!(BadRequest("Invalid address length".to_string()));
+        }
+        let address = CashAddress::parse_cow(address.into())?;
+        let address = address.with_prefix(self.satoshi_addr_prefix);
+        if let Some(identicon) = self
+            .address_identicons
+            .lock()
+            .unwrap()
+            .get(address.as_str())
+        {
+            return Ok(identicon.clone());
+        }
+
+        let identicon = Bytes::from(identicon_png(address.as_str().as_bytes())?);
+        let mut address_identicons = self.address_identicons.lock().unwrap();
+        if address_identicons.len() >= MAX_CACHED_IDENTICONS {
+            address_identicons.clear();
+        }
+        address_identicons.insert(address.as_str().to_string(), identicon.clone());
+        Ok(identicon)
+    }
+
+    /// Redirects to the block at `height`, `None` if there's no such block
+    pub async fn block_height(&self, height: u32) -> Option<Redirect> {
+        let block = self.chronik.block_by_height(height as i32).await.ok()?;
+        let block_info = block.block_info.expect("Impossible");
+        Some(self.redirect(format!("/block/{}", to_be_hex(&block_info.hash))))
+    }
+
+    /// Redirects to the address, block or tx `query` refers to, `None` if it
+    /// doesn't refer to anything known
+    pub async fn search(&self, query: &str) -> Option<Redirect> {
         if let Ok(address) = CashAddress::parse_cow(query.into()) {
-            return Ok(self.redirect(format!("/address/{}", address.as_str())));
+            return Some(self.redirect(format!("/address/{}", address.as_str())));
         }
-        let bytes = from_be_hex(query)?;
-        let unknown_hash = Sha256d::from_slice(&bytes)?;
+        if let Ok(height) = query.parse::<u32>() {
+            return Some(self.redirect(format!("/block-height/{}", height)));
+        }
+        let bytes = from_be_hex(query).ok()?;
+        let unknown_hash = Sha256d::from_slice(&bytes).ok()?;
 
         if self.chronik.tx(&unknown_hash).await.is_ok() {
-            return Ok(self.redirect(format!("/tx/{}", query)));
+            return Some(self.redirect(format!("/tx/{}", query)));
         }
         if self.chronik.block_by_hash(&unknown_hash).await.is_ok() {
-            return Ok(self.redirect(format!("/block/{}", query)));
+            return Some(self.redirect(format!("/block/{}", query)));
+        }
+        if let Some(tx_hex) = self.reversed_tx_hash(query).await {
+            return Some(self.redirect(format!("/tx/{}", tx_hex)));
+        }
+        if let Some(block_hex) = self.reversed_block_hash(query).await {
+            return Some(self.redirect(format!("/block/{}", block_hex)));
+        }
+
+        None
+    }
+
+    /// Page for a search or URL that matched nothing, with suggestions for
+    /// what was probably meant. Suggestions are best effort, so failing
+    /// lookups leave them out instead of failing the page.
+    pub async fn not_found(&self, query: &str) -> String {
+        let query = query.trim();
+        let mut suggestions = self.search_suggestions(query).await.unwrap_or_default();
+
+        // Heights are often copied with thousands separators
+        let digits = query
+            .chars()
+            .filter(|c| !matches!(c, ',' | '.' | '\'' | '_' | ' '))
+            .collect::<String>();
+        if digits != query {
+            suggestions.extend(self.search_suggestions(&digits).await.unwrap_or_default());
+        }
+        if let Ok(height) = digits.parse::<i32>() {
+            if let Ok(blockchain_info) = self.chronik.blockchain_info().await {
+                if height > blockchain_info.tip_height {
+                    suggestions.push(JsonSearchSuggestion {
+                        kind: SearchSuggestionKind::Block,
+                        label: format!("Latest block {}", blockchain_info.tip_height),
+                        url: format!("/block-height/{}", blockchain_info.tip_height),
+                    });
+                }
+            }
+        }
+
+        let not_found_template = NotFoundTemplate {
+            query,
+            suggestions,
+            network: self.network,
+        };
+        not_found_template.render().unwrap()
+    }
+
+    pub async fn data_search_suggestions(
+        &self,
+        query: SearchQuery,
+    ) -> Result<JsonSearchSuggestionsResponse> {
+        let search = query.q.as_deref().map(str::trim).unwrap_or_default();
+        Ok(JsonSearchSuggestionsResponse {
+            data: self.search_suggestions(search).await?,
+        })
+    }
+
+    /// Suggestions for a block height, a block, tx or token hash (in either
+    /// byte order) or an address, with or without prefix. Used by the search
+    /// box and the not found page.
+    async fn search_suggestions(&self, search: &str) -> Result<Vec<JsonSearchSuggestion>> {
+        let mut suggestions = Vec::new();
+
+        if let Ok(height) = search.parse::<i32>() {
+            let blockchain_info = self.chronik.blockchain_info().await?;
+            if (0..=blockchain_info.tip_height).contains(&height) {
+                suggestions.push(JsonSearchSuggestion {
+                    kind: SearchSuggestionKind::Block,
+                    label: format!("Block {}", height),
+                    url: format!("/block-height/{}", height),
+                });
+            }
+        }
+
+        if search.len() == 64 {
+            let mut hashes = vec![search.to_lowercase()];
+            if let Ok(reversed_hex) = reverse_hex(search) {
+                hashes.push(reversed_hex);
+            }
+            for hash_hex in hashes {
+                let hash = match Sha256d::from_hex_be(&hash_hex) {
+                    Ok(hash) => hash,
+                    Err(_) => break,
+                };
+                if let Ok(block) = self.chronik.block_by_hash(&hash).await {
+                    let height = block.block_info.map_or(0, |block_info| block_info.height);
+                    suggestions.push(JsonSearchSuggestion {
+                        kind: SearchSuggestionKind::Block,
+                        label: format!("Block {}", height),
+                        url: format!("/block/{}", hash_hex),
+                    });
+                }
+                if let Ok(token) = self.chronik.token(&hash).await {
+                    let ticker = token
+                        .slp_tx_data
+                        .and_then(|slp_tx_data| slp_tx_data.genesis_info)
+                        .map(|genesis_info| {
+                            String::from_utf8_lossy(&genesis_info.token_ticker).to_string()
+                        })
+                        .unwrap_or_default();
+                    suggestions.push(JsonSearchSuggestion {
+                        kind: SearchSuggestionKind::Token,
+                        label: format!("Token {}", ticker),
+                        url: format!("/tx/{}", hash_hex),
+                    });
+                } else if self.chronik.tx(&hash).await.is_ok() {
+                    suggestions.push(JsonSearchSuggestion {
+                        kind: SearchSuggestionKind::Tx,
+                        label: format!("Transaction {}", hash_hex),
+                        url: format!("/tx/{}", hash_hex),
+                    });
+                }
+            }
+        }
+
+        let address_search = if search.contains(':') {
+            Cow::Borrowed(search)
+        } else {
+            Cow::Owned(format!("{}:{}", self.satoshi_addr_prefix, search))
+        };
+        if let Ok(address) = CashAddress::parse_cow(address_search) {
+            suggestions.push(JsonSearchSuggestion {
+                kind: SearchSuggestionKind::Address,
+                label: address.as_str().to_string(),
+                url: format!("/address/{}", address.as_str()),
+            });
         }
 
-        Ok(self.redirect("/404".into()))
+        Ok(suggestions)
+    }
+
+    /// Returns the canonical hex of `hash_hex` if it's a known tx hash given in
+    /// the opposite byte order.
+    pub async fn reversed_tx_hash(&self, hash_hex: &str) -> Option<String> {
+        let reversed_hex = reverse_hex(hash_hex).ok()?;
+        let tx_hash = Sha256d::from_hex_be(&reversed_hex).ok()?;
+        self.chronik.tx(&tx_hash).await.ok()?;
+        Some(reversed_hex)
+    }
+
+    /// Returns the canonical hex of `hash_hex` if it's a known block hash given
+    /// in the opposite byte order.
+    pub async fn reversed_block_hash(&self, hash_hex: &str) -> Option<String> {
+        let reversed_hex = reverse_hex(hash_hex).ok()?;
+        let block_hash = Sha256d::from_hex_be(&reversed_hex).ok()?;
+        self.chronik.block_by_hash(&block_hash).await.ok()?;
+        Some(reversed_hex)
     }
 
     pub fn redirect(&self, url: String) -> Redirect {
         Redirect::permanent(&url)
     }
 }
+
+/// Filters for an address' tx history, taken from the `token`, `from` and `to`
+/// query parameters. Dates are `YYYY-MM-DD` in UTC, both ends inclusive.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TxHistoryFilter {
+    token_id: Option<String>,
+    /// Start of the range as a unix timestamp, inclusive
+    from: Option<i64>,
+    /// End of the range as a unix timestamp, exclusive
+    to: Option<i64>,
+}
+
+impl TxHistoryFilter {
+    /// Filter by the `token`, `from` and `to` query params, `None` if all
+    /// are unset
+    fn new(token_id: Option<String>, from: Option<&str>, to: Option<&str>) -> Result<Option<Self>> {
+        let from = parse_date_param("from", from)?;
+        let to = parse_date_param("to", to)?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                bail!(BadRequest("from must not be after to".to_string()));
+            }
+        }
+        if token_id.is_none() && from.is_none() && to.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(TxHistoryFilter {
+            token_id,
+            from: from.map(|date| date.and_hms(0, 0, 0).timestamp()),
+            to: to.map(|date| (date + Duration::days(1)).and_hms(0, 0, 0).timestamp()),
+        }))
+    }
+
+    fn matches(&self, tx: &Tx) -> bool {
+        let timestamp = tx_timestamp(tx);
+        if self.from.map_or(false, |from| timestamp < from) {
+            return false;
+        }
+        if self.to.map_or(false, |to| timestamp >= to) {
+            return false;
+        }
+        match &self.token_id {
+            Some(token_id) => tx
+                .slp_tx_data
+                .as_ref()
+                .and_then(|slp_tx_data| slp_tx_data.slp_meta.as_ref())
+                .map_or(false, |slp_meta| {
+                    &hex::encode(&slp_meta.token_id) == token_id
+                }),
+            None => true,
+        }
+    }
+
+    /// Whether `tx` and all txs after it in the history are older than the
+    /// range.
+    fn is_before_range(&self, tx: &Tx) -> bool {
+        match (self.from, &tx.block) {
+            (Some(from), Some(block)) => block.timestamp < from - BLOCK_TIME_DRIFT_SECS,
+            _ => false,
+        }
+    }
+}
+
+type FilteredHistories = HashMap<(Vec<u8>, TxHistoryFilter), (Vec<u8>, u32, Arc<Vec<Tx>>)>;
+
+fn tx_fee_sats(tx: &Tx) -> i64 {
+    let sats_input = tx.inputs.iter().map(|input| input.value).sum::<i64>();
+    let sats_output = tx.outputs.iter().map(|output| output.value).sum::<i64>();
+    sats_input - sats_output
+}
+
+/// Nearest-rank percentile of sorted fee rates, or the minimum relay fee rate
+/// if there are none
+fn fee_rate_percentile(sorted_fee_rates: &[i64], percent: usize) -> i64 {
+    let rank = (sorted_fee_rates.len() * percent + 99) / 100;
+    sorted_fee_rates
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or(MIN_RELAY_FEE_RATE)
+}
+
+/// XEC received and sent by `address_script` in unconfirmed txs, without
+/// token outputs. Chronik leaves UTXOs spent in the mempool out of the UTXO
+/// set and adds the unconfirmed ones, so this is the unconfirmed part of the
+/// balance.
+fn unconfirmed_flows(unconfirmed_txs: &[Tx], address_script: &[u8]) -> (i64, i64) {
+    let received_sats = unconfirmed_txs
+        .iter()
+        .flat_map(|tx| &tx.outputs)
+        .filter(|output| output.output_script == address_script)
+        .filter(|output| output.slp_token.is_none())
+        .map(|output| output.value)
+        .sum::<i64>();
+    let sent_sats = unconfirmed_txs
+        .iter()
+        .flat_map(|tx| &tx.inputs)
+        .filter(|input| input.output_script == address_script)
+        .filter(|input| input.slp_token.is_none())
+        .map(|input| input.value)
+        .sum::<i64>();
+    (received_sats, sent_sats)
+}
+
+fn prev_block_hash(block_info: &BlockInfo) -> Option<String> {
+    if block_info.height == 0 {
+        return None;
+    }
+    Some(to_be_hex(&block_info.prev_hash))
+}
+
+fn spending_txids(tx: &Tx) -> VecDeque<Vec<u8>> {
+    tx.outputs
+        .iter()
+        .filter_map(|output| Some(output.spent_by.as_ref()?.txid.clone()))
+        .collect()
+}
+
+fn tx_timestamp(tx: &Tx) -> i64 {
+    tx.block
+        .as_ref()
+        .map_or(tx.time_first_seen, |block| block.timestamp)
+}
+
+fn parse_date_param(key: &str, value: Option<&str>) -> Result<Option<NaiveDate>> {
+    match value {
+        Some(value) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            Ok(date) => Ok(Some(date)),
+            Err(_) => bail!(BadRequest(format!(
+                "{} must be a date in the format YYYY-MM-DD",
+                key
+            ))),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Widget theme from the `theme` and `accent` query params
+fn widget_style(query: &WidgetQuery) -> Result<WidgetStyle> {
+    let is_dark = matches!(query.theme, Some(WidgetTheme::Dark));
+    let accent = match &query.accent {
+        Some(accent) => {
+            let accent = accent.trim_start_matches('#');
+            if !matches!(accent.len(), 3 | 6) || !accent.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!(BadRequest(
+                    "accent must be a hex color, e.g. 0074c2".to_string()
+                ));
+            }
+            accent.to_string()
+        }
+        None => DEFAULT_WIDGET_ACCENT.to_string(),
+    };
+    Ok(WidgetStyle {
+        is_dark,
+        accent,
+        refresh_secs: WIDGET_REFRESH_SECS,
+    })
+}
@@ -1,28 +1,49 @@
-use askama::Template;
+use std::fmt;
+
 use axum::{
     http::StatusCode,
-    response::{Html, IntoResponse, Response},
+    response::{IntoResponse, Response},
 };
 
-use crate::templating::ErrorTemplate;
-
 pub struct ServerError {
+    pub status: StatusCode,
     pub message: String,
 }
 
+/// Error for invalid request parameters, e.g. a page size over the limit.
+/// It is reported with status 400 instead of 500.
+#[derive(Debug)]
+pub struct BadRequest(pub String);
+
+impl fmt::Display for BadRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for BadRequest {}
+
+/// Message of a failed request, which the `error_page` middleware renders
+/// into the error page
+pub struct ErrorMessage(pub String);
+
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
-        let error_template = ErrorTemplate {
-            message: self.message,
-        };
-        let error_page = error_template.render().unwrap();
-
-        (StatusCode::INTERNAL_SERVER_ERROR, Html(error_page)).into_response()
+        let mut response = self.status.into_response();
+        response.extensions_mut().insert(ErrorMessage(self.message));
+        response
     }
 }
 
-pub fn to_server_error<T: ToString>(err: T) -> ServerError {
+pub fn to_server_error<E: Into<eyre::Report>>(err: E) -> ServerError {
+    let err = err.into();
+    let status = if err.downcast_ref::<BadRequest>().is_some() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
     ServerError {
+        status,
         message: err.to_string(),
     }
-}
\ No newline at end of file
+}
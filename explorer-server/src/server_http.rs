@@ -1,68 +1,481 @@
 use crate::{
+    admin_auth::AdminCheck,
+    api_keys::QuotaCheck,
+    locale::{Denomination, DisplayLocale},
+    openapi::openapi_spec,
+    query::{
+        AddressQuery, AddressTxsQuery, BlockRangeQuery, ChartQuery, PageQuery, QrQuery,
+        SearchQuery, TxGraphQuery, UnitQuery, UtxosQuery, ValidQuery, WidgetQuery,
+    },
     server::Server,
-    server_error::{to_server_error, ServerError},
-    server_primitives::{JsonBlocksResponse, JsonTxsResponse},
+    server_error::{to_server_error, ErrorMessage, ServerError},
+    server_primitives::{
+        JsonAddressBalancesResponse, JsonAddressLabelRequest, JsonAddressSummary,
+        JsonAddressesRequest, JsonApiUsage, JsonBatchQuery, JsonBatchResponse,
+        JsonBlockLatencyResponse, JsonBlockResponse, JsonBlocksResponse,
+        JsonDifficultyChartResponse, JsonFeeEstimate, JsonMerkleProof, JsonNetworkStats,
+        JsonOutputTypesChartResponse, JsonSearchSuggestionsResponse, JsonStatus, JsonSupply,
+        JsonTokensRequest, JsonTxConfirmations, JsonTxDetails, JsonTxFlows, JsonTxGraph,
+        JsonTxPackageRequest, JsonTxPackageResponse, JsonTxScripts, JsonTxsResponse,
+        JsonUpgradeStatus, JsonUtxosResponse, JsonWebhookRequest,
+    },
 };
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    response::{Html, IntoResponse, Redirect},
+    body::StreamBody,
+    extract::{ConnectInfo, Path, RawQuery},
+    http::{header, Extensions, HeaderMap, Request, StatusCode, Uri},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Html, IntoResponse, Response,
+    },
     routing::{get_service, MethodRouter},
     Extension, Json,
 };
-use futures::future::ready;
-use std::{collections::HashMap, sync::Arc};
+use futures::{future::ready, stream, Stream, StreamExt};
+use serde::Serialize;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+use tokio::sync::broadcast::error::RecvError;
 use tower_http::services::ServeDir;
 
-pub async fn homepage(server: Extension<Arc<Server>>) -> Result<Html<String>, ServerError> {
-    Ok(Html(server.homepage().await.map_err(to_server_error)?))
+/// Genesis data of tokens never changes, but mints and burns change their
+/// supply, so clients revalidate with the ETag after a while
+const TOKEN_CACHE_CONTROL: &str = "public, max-age=300";
+
+pub async fn homepage(
+    ValidQuery(query): ValidQuery<UnitQuery>,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server
+            .homepage(
+                DisplayLocale::from_headers(&headers),
+                Denomination::from_request(&headers, query.unit.as_deref()),
+            )
+            .await
+            .map_err(to_server_error)?,
+    ))
 }
 
 pub async fn blocks(server: Extension<Arc<Server>>) -> Result<Html<String>, ServerError> {
     Ok(Html(server.blocks().await.map_err(to_server_error)?))
 }
 
+pub async fn difficulty_chart(server: Extension<Arc<Server>>) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server.difficulty_chart().await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn forks(
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server
+            .forks(DisplayLocale::from_headers(&headers))
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn supply(
+    ValidQuery(query): ValidQuery<UnitQuery>,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    Ok(Html(
+        server
+            .supply(
+                DisplayLocale::from_headers(&headers),
+                Denomination::from_request(&headers, query.unit.as_deref()),
+            )
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
 pub async fn tx(
     Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<UnitQuery>,
+    headers: HeaderMap,
     server: Extension<Arc<Server>>,
-) -> Result<Html<String>, ServerError> {
-    Ok(Html(server.tx(&hash).await.map_err(to_server_error)?))
+) -> Result<Response, ServerError> {
+    match server
+        .tx(
+            &hash,
+            DisplayLocale::from_headers(&headers),
+            Denomination::from_request(&headers, query.unit.as_deref()),
+        )
+        .await
+    {
+        Ok(tx_page) => Ok(Html(tx_page).into_response()),
+        Err(err) => match server.reversed_tx_hash(&hash).await {
+            Some(tx_hex) => Ok(server.redirect(format!("/tx/{}", tx_hex)).into_response()),
+            None => Err(to_server_error(err)),
+        },
+    }
 }
 
 pub async fn block(
     Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<UnitQuery>,
+    headers: HeaderMap,
     server: Extension<Arc<Server>>,
-) -> Result<Html<String>, ServerError> {
-    Ok(Html(server.block(&hash).await.map_err(to_server_error)?))
+) -> Result<Response, ServerError> {
+    match server
+        .block(
+            &hash,
+            DisplayLocale::from_headers(&headers),
+            Denomination::from_request(&headers, query.unit.as_deref()),
+        )
+        .await
+    {
+        Ok(block_page) => Ok(Html(block_page).into_response()),
+        Err(err) => match server.reversed_block_hash(&hash).await {
+            Some(block_hex) => Ok(server
+                .redirect(format!("/block/{}", block_hex))
+                .into_response()),
+            None => Err(to_server_error(err)),
+        },
+    }
 }
 
 pub async fn address(
     Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<AddressQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    let unit = Denomination::from_request(&headers, query.unit.as_deref());
+    Ok(Html(
+        server
+            .address(
+                &hash,
+                query,
+                raw_query.as_deref(),
+                DisplayLocale::from_headers(&headers),
+                unit,
+            )
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn tx_widget(
+    Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<WidgetQuery>,
+    headers: HeaderMap,
     server: Extension<Arc<Server>>,
 ) -> Result<Html<String>, ServerError> {
-    Ok(Html(server.address(&hash).await.map_err(to_server_error)?))
+    let unit = Denomination::from_request(&headers, query.unit.as_deref());
+    Ok(Html(
+        server
+            .tx_widget(&hash, query, DisplayLocale::from_headers(&headers), unit)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn address_widget(
+    Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<WidgetQuery>,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Html<String>, ServerError> {
+    let unit = Denomination::from_request(&headers, query.unit.as_deref());
+    Ok(Html(
+        server
+            .address_widget(&hash, query, DisplayLocale::from_headers(&headers), unit)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn address_utxos_json(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let utxos = server
+        .address_utxos_export(&hash)
+        .await
+        .map_err(to_server_error)?;
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"utxos-{}.json\"",
+                address_without_prefix(&hash)
+            ),
+        )],
+        Json(utxos),
+    )
+        .into_response())
+}
+
+pub async fn address_utxos_csv(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let csv = server
+        .address_utxos_csv(&hash)
+        .await
+        .map_err(to_server_error)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"utxos-{}.csv\"",
+                    address_without_prefix(&hash)
+                ),
+            ),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
+/// Address for use in file names, which can't contain the prefix's colon on
+/// every system
+fn address_without_prefix(address: &str) -> &str {
+    address.rsplit(':').next().unwrap_or(address)
 }
 
 pub async fn address_qr(
     Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<QrQuery>,
     server: Extension<Arc<Server>>,
 ) -> Result<impl IntoResponse, ServerError> {
-    let qr_code = server.address_qr(&hash).await.map_err(to_server_error)?;
-    Ok((StatusCode::OK, [("content-type", "image/png")], qr_code))
+    let (content_type, qr_code) = server
+        .address_qr(&hash, query)
+        .await
+        .map_err(to_server_error)?;
+    Ok((StatusCode::OK, [("content-type", content_type)], qr_code))
 }
 
-pub async fn block_height(
-    Path(height): Path<u32>,
+pub async fn address_identicon(
+    Path(file): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let address = file.strip_suffix(".png").unwrap_or(&file);
+    let identicon = server.address_identicon(address).map_err(to_server_error)?;
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", "image/png"),
+            ("cache-control", "public, max-age=604800, immutable"),
+        ],
+        identicon,
+    ))
+}
+
+pub async fn token_icon(
+    Path(token_id): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let icon = server
+        .token_icon(&token_id)
+        .await
+        .map_err(to_server_error)?;
+    Ok((
+        StatusCode::OK,
+        [
+            ("content-type", icon.content_type),
+            ("cache-control", "public, max-age=3600".to_string()),
+        ],
+        icon.data,
+    ))
+}
+
+pub async fn script(
+    Path(hex): Path<String>,
+    ValidQuery(query): ValidQuery<PageQuery>,
+    RawQuery(raw_query): RawQuery,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    if let Some(address) = server.script_address(&hex) {
+        return Ok(server
+            .redirect(format!("/address/{}", address))
+            .into_response());
+    }
+    let unit = Denomination::from_request(&headers, query.unit.as_deref());
+    let script_page = server
+        .script(
+            &hex,
+            query,
+            raw_query.as_deref(),
+            DisplayLocale::from_headers(&headers),
+            unit,
+        )
+        .await
+        .map_err(to_server_error)?;
+    Ok(Html(script_page).into_response())
+}
+
+pub async fn block_height(Path(height): Path<u32>, server: Extension<Arc<Server>>) -> Response {
+    match server.block_height(height).await {
+        Some(redirect) => redirect.into_response(),
+        None => not_found_page(&server, &height.to_string()).await,
+    }
+}
+
+pub async fn search(Path(query): Path<String>, server: Extension<Arc<Server>>) -> Response {
+    match server.search(&query).await {
+        Some(redirect) => redirect.into_response(),
+        None => not_found_page(&server, &query).await,
+    }
+}
+
+pub async fn xpub(
+    Path(xpub): Path<String>,
+    ValidQuery(query): ValidQuery<UnitQuery>,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let xpub_page = server
+        .xpub(
+            &xpub,
+            DisplayLocale::from_headers(&headers),
+            Denomination::from_request(&headers, query.unit.as_deref()),
+        )
+        .await
+        .map_err(to_server_error)?;
+    match xpub_page {
+        Some(xpub_page) => Ok(Html(xpub_page).into_response()),
+        None => Ok(not_found_page(&server, &xpub).await),
+    }
+}
+
+/// Fallback for unknown URLs; the last path segment is taken as the query,
+/// e.g. a height in `/812345`
+pub async fn not_found(uri: Uri, server: Extension<Arc<Server>>) -> Response {
+    let query = uri
+        .path()
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or_default();
+    not_found_page(&server, query).await
+}
+
+async fn not_found_page(server: &Server, query: &str) -> Response {
+    (StatusCode::NOT_FOUND, Html(server.not_found(query).await)).into_response()
+}
+
+pub async fn opensearch(
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<impl IntoResponse, ServerError> {
+    let base_url = base_url(&headers, &server);
+    let opensearch = server
+        .opensearch(&base_url)
+        .await
+        .map_err(to_server_error)?;
+    Ok((
+        StatusCode::OK,
+        [("content-type", "application/opensearchdescription+xml")],
+        opensearch,
+    ))
+}
+
+/// 200 if Chronik is reachable, 503 otherwise, regardless of maintenance mode
+pub async fn healthz(server: Extension<Arc<Server>>) -> Response {
+    let health = server.data_health().await;
+    let status = if health.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(health)).into_response()
+}
+
+pub async fn data_openapi(
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Json<serde_json::Value> {
+    Json(openapi_spec(&base_url(&headers, &server)))
+}
+
+pub async fn sitemap_index(server: Extension<Arc<Server>>) -> Result<Response, ServerError> {
+    let sitemap_index = server.sitemap_index().await.map_err(to_server_error)?;
+    Ok(sitemap_response(sitemap_index))
+}
+
+pub async fn block_sitemap(
+    Path(page): Path<i32>,
     server: Extension<Arc<Server>>,
-) -> Result<Redirect, ServerError> {
-    Ok(server.block_height(height).await.map_err(to_server_error)?)
+) -> Result<Response, ServerError> {
+    let sitemap = server.block_sitemap(page).await.map_err(to_server_error)?;
+    Ok(sitemap_response(sitemap))
+}
+
+/// Sitemaps are 404 without a `public_url`
+fn sitemap_response(sitemap: Option<String>) -> Response {
+    match sitemap {
+        Some(sitemap) => (
+            StatusCode::OK,
+            [("content-type", "application/xml")],
+            sitemap,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
 }
 
-pub async fn search(
-    Path(query): Path<String>,
+/// The configured public URL, or else the URL the request was sent to
+fn base_url(headers: &HeaderMap, server: &Server) -> String {
+    if let Some(public_url) = server.public_url() {
+        return public_url.to_string();
+    }
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("explorer.e.cash");
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|scheme| scheme.to_str().ok())
+        .unwrap_or("http");
+    format!("{}://{}", scheme, host)
+}
+
+/// Server-Sent Events of new blocks, their txs and confirmed blocks
+pub async fn events(
     server: Extension<Arc<Server>>,
-) -> Result<Redirect, ServerError> {
-    server.search(&query).await.map_err(to_server_error)
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let receiver = server.subscribe_events();
+    let shutdown = server.shutdown_signal();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                // Skip events missed by slow clients
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .take_until(shutdown)
+    .map(|event| Event::default().event(event.name()).json_data(&event));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+pub async fn data_search_suggestions(
+    ValidQuery(query): ValidQuery<SearchQuery>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonSearchSuggestionsResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_search_suggestions(query)
+            .await
+            .map_err(to_server_error)?,
+    ))
 }
 
 pub async fn data_blocks(
@@ -77,13 +490,147 @@ pub async fn data_blocks(
     ))
 }
 
+pub async fn data_fee_estimate(
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonFeeEstimate>, ServerError> {
+    Ok(Json(
+        server.data_fee_estimate().await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_status(server: Extension<Arc<Server>>) -> Result<Json<JsonStatus>, ServerError> {
+    Ok(Json(server.data_status().await.map_err(to_server_error)?))
+}
+
+pub async fn data_upgrade(
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonUpgradeStatus>, ServerError> {
+    Ok(Json(server.data_upgrade().await.map_err(to_server_error)?))
+}
+
+pub async fn data_network_stats(server: Extension<Arc<Server>>) -> Json<JsonNetworkStats> {
+    Json(server.data_network_stats())
+}
+
+pub async fn data_block_latency_chart(
+    server: Extension<Arc<Server>>,
+) -> Json<JsonBlockLatencyResponse> {
+    Json(server.data_block_latency_chart())
+}
+
+pub async fn data_output_types_chart(
+    ValidQuery(query): ValidQuery<ChartQuery>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonOutputTypesChartResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_output_types_chart(query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_supply(server: Extension<Arc<Server>>) -> Result<Json<JsonSupply>, ServerError> {
+    Ok(Json(server.data_supply().await.map_err(to_server_error)?))
+}
+
+/// Circulating supply in XEC as a plain number, for aggregators
+pub async fn data_supply_circulating(
+    server: Extension<Arc<Server>>,
+) -> Result<String, ServerError> {
+    let supply = server.data_supply().await.map_err(to_server_error)?;
+    Ok(format_xec(supply.circulating_sats))
+}
+
+/// Maximum supply in XEC as a plain number, for aggregators
+pub async fn data_supply_max(server: Extension<Arc<Server>>) -> Result<String, ServerError> {
+    let supply = server.data_supply().await.map_err(to_server_error)?;
+    Ok(format_xec(supply.max_supply_sats))
+}
+
+fn format_xec(sats: i64) -> String {
+    format!("{}.{:02}", sats / 100, sats % 100)
+}
+
+pub async fn data_difficulty_chart(
+    ValidQuery(query): ValidQuery<ChartQuery>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonDifficultyChartResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_difficulty_chart(query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+/// Exports count as one request per chunk of blocks fetched from Chronik
+pub async fn data_export_blocks(
+    ValidQuery(query): ValidQuery<BlockRangeQuery>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(server): Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let (num_chunks, blocks) = Arc::clone(&server)
+        .export_blocks(query)
+        .await
+        .map_err(to_server_error)?;
+    // The first chunk was counted by the api_quota middleware
+    let ip = server.api_client_ip(&headers, addr.ip());
+    let quota_check = server.check_api_quota(api_key(&headers), ip, num_chunks - 1);
+    if let Some(response) = quota_error_response(quota_check) {
+        return Ok(response);
+    }
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        StreamBody::new(blocks),
+    )
+        .into_response())
+}
+
+pub async fn data_block_raw(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let raw_block = server
+        .data_block_raw(&hash)
+        .await
+        .map_err(to_server_error)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.bin\"", hash),
+            ),
+        ],
+        raw_block,
+    )
+        .into_response())
+}
+
 pub async fn data_block_txs(
     Path(hash): Path<String>,
     server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    match server.data_block_txs(&hash).await {
+        Ok(json_txs) => Ok(Json(json_txs).into_response()),
+        Err(err) => match server.reversed_block_hash(&hash).await {
+            Some(block_hex) => Ok(server
+                .redirect(format!("/api/block/{}/transactions", block_hex))
+                .into_response()),
+            None => Err(to_server_error(err)),
+        },
+    }
+}
+
+pub async fn data_block_height_txs(
+    Path(height): Path<u32>,
+    server: Extension<Arc<Server>>,
 ) -> Result<Json<JsonTxsResponse>, ServerError> {
     Ok(Json(
         server
-            .data_block_txs(&hash)
+            .data_block_height_txs(height)
             .await
             .map_err(to_server_error)?,
     ))
@@ -91,7 +638,7 @@ pub async fn data_block_txs(
 
 pub async fn data_address_txs(
     Path(hash): Path<String>,
-    Query(query): Query<HashMap<String, String>>,
+    ValidQuery(query): ValidQuery<AddressTxsQuery>,
     server: Extension<Arc<Server>>,
 ) -> Result<Json<JsonTxsResponse>, ServerError> {
     Ok(Json(
@@ -102,6 +649,397 @@ pub async fn data_address_txs(
     ))
 }
 
+pub async fn data_address_utxos(
+    Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<UtxosQuery>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonUtxosResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_address_utxos(&hash, query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_address_summary(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonAddressSummary>, ServerError> {
+    Ok(Json(
+        server
+            .data_address_summary(&hash)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_address_balances(
+    server: Extension<Arc<Server>>,
+    Json(request): Json<JsonAddressesRequest>,
+) -> Result<Json<JsonAddressBalancesResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_address_balances(request.addresses)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_token(
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    let token_meta = server.data_token(&id).await.map_err(to_server_error)?;
+    json_with_etag(&headers, &token_meta)
+}
+
+pub async fn data_tokens(
+    headers: HeaderMap,
+    server: Extension<Arc<Server>>,
+    Json(request): Json<JsonTokensRequest>,
+) -> Result<Response, ServerError> {
+    let tokens = server
+        .data_tokens(request.token_ids)
+        .await
+        .map_err(to_server_error)?;
+    json_with_etag(&headers, &tokens)
+}
+
+/// Responds with `value` as JSON and an ETag of it, or with 304 Not Modified
+/// if the client sent that ETag in `If-None-Match`
+fn json_with_etag<T: Serialize>(headers: &HeaderMap, value: &T) -> Result<Response, ServerError> {
+    use bitcoin::hashes::{sha256, Hash};
+    let body = serde_json::to_vec(value).map_err(to_server_error)?;
+    let hash = sha256::Hash::hash(&body);
+    let etag = format!("\"{}\"", hex::encode(&hash[..16]));
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, TOKEN_CACHE_CONTROL.to_string()),
+    ];
+    let is_cached = headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|client_etag| client_etag.trim() == etag || client_etag.trim() == "*");
+    if is_cached {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+    Ok((
+        cache_headers,
+        [(header::CONTENT_TYPE, "application/json")],
+        body,
+    )
+        .into_response())
+}
+
+pub async fn data_tx(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxDetails>, ServerError> {
+    Ok(Json(server.data_tx(&hash).await.map_err(to_server_error)?))
+}
+
+pub async fn data_tx_confirmations(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxConfirmations>, ServerError> {
+    Ok(Json(
+        server
+            .data_tx_confirmations(&hash)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_tx_merkle_proof(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonMerkleProof>, ServerError> {
+    Ok(Json(
+        server
+            .data_tx_merkle_proof(&hash)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_block(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonBlockResponse>, ServerError> {
+    Ok(Json(
+        server.data_block(&hash).await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_tx_flows(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxFlows>, ServerError> {
+    Ok(Json(
+        server.data_tx_flows(&hash).await.map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_tx_scripts(
+    Path(hash): Path<String>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxScripts>, ServerError> {
+    Ok(Json(
+        server
+            .data_tx_scripts(&hash)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+pub async fn data_tx_graph(
+    Path(hash): Path<String>,
+    ValidQuery(query): ValidQuery<TxGraphQuery>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonTxGraph>, ServerError> {
+    Ok(Json(
+        server
+            .data_tx_graph(&hash, query)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+/// Enforces the API quotas on all /api routes, if enabled.
+pub async fn api_quota<B>(request: Request<B>, next: Next<B>) -> Response {
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+
+    let server = request
+        .extensions()
+        .get::<Arc<Server>>()
+        .expect("Server extension missing")
+        .clone();
+    let ip = server.api_client_ip(request.headers(), peer_ip(request.extensions()));
+    match quota_error_response(server.check_api_quota(api_key(request.headers()), ip, 1)) {
+        Some(response) => response,
+        None => next.run(request).await,
+    }
+}
+
+fn api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("x-api-key")
+        .and_then(|api_key| api_key.to_str().ok())
+}
+
+/// Response to a request over its quota, `None` if it's allowed
+fn quota_error_response(quota_check: QuotaCheck) -> Option<Response> {
+    match quota_check {
+        QuotaCheck::Allowed => None,
+        QuotaCheck::UnknownKey => {
+            Some((StatusCode::UNAUTHORIZED, "Unknown API key").into_response())
+        }
+        QuotaCheck::Exceeded { retry_after_secs } => Some(
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                "API quota exceeded",
+            )
+                .into_response(),
+        ),
+    }
+}
+
+/// Responds with the maintenance page while in maintenance mode, except for
+/// /healthz and the static files
+pub async fn maintenance_mode<B>(request: Request<B>, next: Next<B>) -> Response {
+    let path = request.uri().path();
+    if path == "/healthz"
+        || path == "/favicon.ico"
+        || path.starts_with("/assets/")
+        || path.starts_with("/code/")
+    {
+        return next.run(request).await;
+    }
+
+    let server = request
+        .extensions()
+        .get::<Arc<Server>>()
+        .expect("Server extension missing")
+        .clone();
+    match server.maintenance_page() {
+        Some((maintenance_page, retry_after_secs)) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Html(maintenance_page),
+        )
+            .into_response(),
+        None => next.run(request).await,
+    }
+}
+
+/// Renders the error page of failed requests, which only carry the error
+/// message as they're built without access to the server
+pub async fn error_page<B>(request: Request<B>, next: Next<B>) -> Response {
+    let server = request
+        .extensions()
+        .get::<Arc<Server>>()
+        .expect("Server extension missing")
+        .clone();
+    let response = next.run(request).await;
+    match response.extensions().get::<ErrorMessage>() {
+        Some(ErrorMessage(message)) => {
+            (response.status(), Html(server.error_page(message))).into_response()
+        }
+        None => response,
+    }
+}
+
+fn peer_ip(extensions: &Extensions) -> IpAddr {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |connect_info| {
+            connect_info.0.ip()
+        })
+}
+
+pub async fn data_api_usage(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+) -> Result<Json<JsonApiUsage>, ServerError> {
+    let ip = server.api_client_ip(&headers, addr.ip());
+    Ok(Json(
+        server
+            .data_api_usage(api_key(&headers), ip)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
+/// Response to an admin request without the admin token as its bearer token,
+/// `None` if it's allowed
+fn admin_error_response(headers: &HeaderMap, peer_ip: IpAddr, server: &Server) -> Option<Response> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|authorization| authorization.to_str().ok())
+        .and_then(|authorization| authorization.strip_prefix("Bearer "));
+    let ip = server.api_client_ip(headers, peer_ip);
+    match server.check_admin_token(token, ip) {
+        AdminCheck::Allowed => None,
+        AdminCheck::Unauthorized => {
+            Some((StatusCode::UNAUTHORIZED, "Unauthorized").into_response())
+        }
+        AdminCheck::LockedOut { retry_after_secs } => Some(
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                "Too many failed attempts",
+            )
+                .into_response(),
+        ),
+    }
+}
+
+pub async fn data_admin_set_label(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+    Json(request): Json<JsonAddressLabelRequest>,
+) -> Result<Response, ServerError> {
+    if let Some(response) = admin_error_response(&headers, addr.ip(), &server) {
+        return Ok(response);
+    }
+
+    Ok(Json(
+        server
+            .data_admin_set_label(request)
+            .await
+            .map_err(to_server_error)?,
+    )
+    .into_response())
+}
+
+pub async fn data_admin_webhooks(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    if let Some(response) = admin_error_response(&headers, addr.ip(), &server) {
+        return Ok(response);
+    }
+
+    Ok(Json(server.data_admin_webhooks()).into_response())
+}
+
+pub async fn data_admin_add_webhook(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+    Json(request): Json<JsonWebhookRequest>,
+) -> Result<Response, ServerError> {
+    if let Some(response) = admin_error_response(&headers, addr.ip(), &server) {
+        return Ok(response);
+    }
+
+    Ok(Json(
+        server
+            .data_admin_add_webhook(request)
+            .map_err(to_server_error)?,
+    )
+    .into_response())
+}
+
+pub async fn data_admin_remove_webhook(
+    Path(id): Path<u64>,
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+) -> Result<Response, ServerError> {
+    if let Some(response) = admin_error_response(&headers, addr.ip(), &server) {
+        return Ok(response);
+    }
+
+    server
+        .data_admin_remove_webhook(id)
+        .map_err(to_server_error)?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Batches count as one request per query
+pub async fn data_batch(
+    headers: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    server: Extension<Arc<Server>>,
+    Json(queries): Json<Vec<JsonBatchQuery>>,
+) -> Result<Response, ServerError> {
+    server
+        .check_batch_size(queries.len())
+        .map_err(to_server_error)?;
+    // The first query was counted by the api_quota middleware
+    let cost = queries.len().saturating_sub(1) as u32;
+    let ip = server.api_client_ip(&headers, addr.ip());
+    if let Some(response) =
+        quota_error_response(server.check_api_quota(api_key(&headers), ip, cost))
+    {
+        return Ok(response);
+    }
+    Ok(Json(server.data_batch(queries).await.map_err(to_server_error)?).into_response())
+}
+
+pub async fn data_tx_package(
+    server: Extension<Arc<Server>>,
+    Json(request): Json<JsonTxPackageRequest>,
+) -> Result<Json<JsonTxPackageResponse>, ServerError> {
+    Ok(Json(
+        server
+            .data_tx_package(request.raw_txs)
+            .await
+            .map_err(to_server_error)?,
+    ))
+}
+
 pub fn serve_files(path: &std::path::Path) -> MethodRouter {
     get_service(ServeDir::new(path)).handle_error(|_| ready(StatusCode::INTERNAL_SERVER_ERROR))
 }
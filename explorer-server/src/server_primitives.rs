@@ -1,7 +1,14 @@
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 
-#[derive(Serialize)]
+/// Token amounts can exceed the integers JavaScript numbers hold exactly, so
+/// they're serialized as decimal strings
+fn serialize_token_amount<S: Serializer>(amount: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(amount)
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonUtxo {
     pub tx_hash: String,
@@ -10,18 +17,138 @@ pub struct JsonUtxo {
     pub token_amount: u64,
     pub is_coinbase: bool,
     pub block_height: i32,
+    pub confirmations: i32,
+    pub age_days: u32,
+    pub is_dust: bool,
+    /// Blocks until a coinbase output can be spent, `None` once it's mature
+    pub immature_blocks_remaining: Option<i32>,
+}
+
+/// A UTXO in the shape wallets import them, as in Electrum's `listunspent`
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUtxoExport {
+    pub txid: String,
+    pub vout: u32,
+    /// In sats
+    pub value: i64,
+    /// 0 for unconfirmed UTXOs
+    pub height: i32,
+    pub script_pub_key: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonBalance {
     pub token_id: Option<String>,
     pub sats_amount: i64,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
     pub token_amount: i128,
     pub utxos: Vec<JsonUtxo>,
 }
 
-#[derive(Serialize, Clone)]
+/// Amounts are in sats
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSupply {
+    pub height: i32,
+    pub circulating_sats: i64,
+    pub max_supply_sats: i64,
+    pub block_subsidy_sats: i64,
+    pub next_halving_height: i32,
+    /// Estimated from the 10 minute block interval
+    pub next_halving_timestamp: i64,
+}
+
+/// Main chain blocks replaced by a competing chain, as seen by the explorer
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFork {
+    pub detected_at: i64,
+    /// Height of the first replaced block
+    pub fork_height: i32,
+    /// Number of replaced blocks
+    pub depth: u32,
+    pub stale_block_hashes: Vec<String>,
+    pub new_tip_height: i32,
+    pub new_tip_hash: String,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonStatus {
+    pub tip_height: i32,
+    pub tip_hash: String,
+    /// Forks at least 2 blocks deep seen in the last 24 hours
+    pub fork_alerts: Vec<JsonFork>,
+}
+
+/// Health of the explorer for load balancers and monitoring. Maintenance
+/// mode doesn't change whether the explorer is healthy.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonHealth {
+    /// Whether Chronik is reachable
+    pub healthy: bool,
+    pub maintenance: bool,
+    pub tip_height: Option<i32>,
+    pub tip_timestamp: Option<i64>,
+    /// Why Chronik isn't reachable, if it isn't
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUpgradeStatus {
+    pub tip_height: i32,
+    pub upgrades: Vec<JsonUpgrade>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUpgrade {
+    pub name: String,
+    /// Median time past the upgrade activates at, if activated by time
+    pub activation_time: Option<i64>,
+    /// Height the upgrade activates at, if activated by height
+    pub activation_height: Option<i32>,
+    pub activated: bool,
+    /// First block with the upgrade's rules, once mined
+    pub activation_block_height: Option<i32>,
+    /// Unix timestamp the upgrade is expected to activate at, assuming
+    /// 10 minute blocks for upgrades activated by height
+    pub estimated_activation_time: i64,
+}
+
+/// Activity in the blocks mined in the last 24 hours. Amounts are in sats
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonNetworkStats {
+    pub num_blocks: u32,
+    pub num_txs: u64,
+    /// Sum of the outputs of the non-coinbase txs, including change
+    pub sats_transferred: i64,
+    pub fees_sats: i64,
+    /// `null` with fewer than 2 blocks
+    pub avg_block_interval_secs: Option<f64>,
+    /// Height of the last block counted
+    pub tip_height: Option<i32>,
+}
+
+/// Recommended fee rates in sats per kB
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonFeeEstimate {
+    pub next_block: i64,
+    pub hour: i64,
+    pub economic: i64,
+    /// Height of the last block the estimate is based on
+    pub tip_height: i32,
+    pub num_blocks: u32,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonToken {
     pub token_id: String,
@@ -32,7 +159,43 @@ pub struct JsonToken {
     pub group_id: Option<String>,
 }
 
-#[derive(Serialize)]
+/// Token metadata from the genesis tx, with the supply so far. Amounts are
+/// decimal strings adjusted by the token's decimals, e.g. "1000.50".
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenMeta {
+    pub token_id: String,
+    pub token_type: u32,
+    pub token_ticker: String,
+    pub token_name: String,
+    pub token_document_url: String,
+    /// Hex, empty if the genesis tx has no document hash
+    pub token_document_hash: String,
+    pub decimals: u32,
+    pub group_id: Option<String>,
+    /// `None` if the genesis tx is unconfirmed
+    pub genesis_block_height: Option<i32>,
+    pub genesis_timestamp: i64,
+    pub total_minted: String,
+    pub total_burned: String,
+    /// Minted minus burned
+    pub circulating_supply: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokensRequest {
+    pub token_ids: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokensResponse {
+    /// In the order of the requested token IDs
+    pub data: Vec<JsonTokenMeta>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonBlock {
     pub hash: String,
@@ -43,11 +206,135 @@ pub struct JsonBlock {
     pub num_txs: u64,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDifficultyPoint {
+    /// Timestamp of the last block of the day
+    pub timestamp: i64,
+    pub height: i32,
+    /// Average difficulty of the blocks of the day
+    pub difficulty: f64,
+    /// Estimated network hashrate in hashes per second
+    pub hashrate: f64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDifficultyChartResponse {
+    pub data: Vec<JsonDifficultyPoint>,
+}
+
+/// Number of outputs by the type of their locking script
+#[derive(Serialize, Clone, Copy, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonOutputTypeCounts {
+    pub p2pkh: u64,
+    pub p2sh: u64,
+    /// Bare public keys
+    pub p2pk: u64,
+    pub op_return: u64,
+    /// Any other script
+    pub unknown: u64,
+}
+
+/// Token activity in a block, counting only SLP txs
+#[derive(Serialize, Clone, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockTokenSummary {
+    pub num_token_txs: u64,
+    /// Distinct tokens sent, minted or created
+    pub num_tokens: u64,
+    /// Tokens whose GENESIS tx is in the block
+    pub created_tokens: Vec<JsonCreatedToken>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCreatedToken {
+    pub token_id: String,
+    pub token_ticker: String,
+    pub token_name: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonOutputTypesPoint {
+    /// Timestamp of the last block of the day
+    pub timestamp: i64,
+    pub height: i32,
+    pub outputs: JsonOutputTypeCounts,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonOutputTypesChartResponse {
+    pub data: Vec<JsonOutputTypesPoint>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockLatency {
+    pub hash: String,
+    pub height: i32,
+    /// Timestamp set by the miner
+    pub timestamp: i64,
+    /// When the explorer first saw the block
+    pub received_at: i64,
+    /// `received_at - timestamp`, negative if the miner's clock is ahead
+    pub skew_secs: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockLatencyResponse {
+    pub data: Vec<JsonBlockLatency>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockFeeStats {
+    pub total_fees: i64,
+    pub min_fee_rate: i64,
+    pub median_fee_rate: i64,
+    pub max_fee_rate: i64,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CoinbaseOutputKind {
+    Miner,
+    MinerFund,
+    StakingReward,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCoinbaseOutput {
+    pub out_idx: u32,
+    pub kind: CoinbaseOutputKind,
+    pub address: Option<String>,
+    pub sats_amount: i64,
+}
+
+/// Decoded coinbase input script and how the block reward was split
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCoinbase {
+    pub script_hex: String,
+    /// Height pushed by the coinbase, `None` before BIP34
+    pub height: Option<i64>,
+    pub extra_nonce: Option<String>,
+    pub miner_tag: Option<String>,
+    pub outputs: Vec<JsonCoinbaseOutput>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonTx {
     pub tx_hash: String,
     pub block_height: Option<i32>,
+    /// Whether the tx is still unconfirmed
+    pub is_mempool: bool,
     pub timestamp: i64,
     pub is_coinbase: bool,
     pub size: i32,
@@ -56,21 +343,124 @@ pub struct JsonTx {
     pub stats: JsonTxStats,
     pub token_id: Option<String>,
     pub token: Option<JsonToken>,
+    /// XEC/USD closing price of the day of the tx, if known
+    pub price_usd: Option<f64>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonTxStats {
     pub sats_input: i64,
     pub sats_output: i64,
     pub delta_sats: i64,
-    pub delta_tokens: i64,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
+    pub delta_tokens: i128,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
     pub token_input: i128,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
     pub token_output: i128,
     pub does_burn_slp: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenBurn {
+    pub token_id: String,
+    pub token_ticker: String,
+    pub decimals: u32,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
+    pub amount: i128,
+    /// Whether the tokens were burned by spending them in a tx that isn't a
+    /// valid tx of their token, instead of sending less than the input amount
+    pub is_invalid_spend: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxInput {
+    pub prev_tx_hash: String,
+    pub prev_out_idx: u32,
+    pub sats_amount: i64,
+    pub address: Option<String>,
+    pub sequence: u32,
+    pub sequence_description: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxOutput {
+    pub sats_amount: i64,
+    pub address: Option<String>,
+    pub output_script: String,
+    pub spent_by_tx_hash: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxDetails {
+    pub tx_hash: String,
+    pub version: i32,
+    pub lock_time: u32,
+    pub lock_time_description: String,
+    pub size: u32,
+    pub is_coinbase: bool,
+    pub block_height: Option<i32>,
+    pub timestamp: i64,
+    /// When Chronik first saw the tx in the mempool, null if it only saw it
+    /// in a block
+    pub time_first_seen: Option<i64>,
+    /// Seconds from first seen to the timestamp of the block that mined it
+    pub confirmation_delay_secs: Option<i64>,
+    pub stats: JsonTxStats,
+    /// In XEC-days, null in batch responses and if the spent txs couldn't
+    /// all be looked up, e.g. because there are too many
+    pub coin_days_destroyed: Option<f64>,
+    pub size_breakdown: JsonTxSizeBreakdown,
+    pub warnings: Vec<JsonTxWarning>,
+    pub inputs: Vec<JsonTxInput>,
+    pub outputs: Vec<JsonTxOutput>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TxWarningKind {
+    /// An output below the dust limit, which nodes don't relay
+    DustOutput,
+    /// An output script of no standard type
+    NonStandardScript,
+    /// An OP_RETURN output larger than nodes relay
+    LargeOpReturn,
+    /// A fee rate below the minimum relay fee rate
+    LowFeeRate,
+}
+
+/// Something unusual about a tx, usually something nodes wouldn't relay, so
+/// the tx was mined directly or by a miner with non-default policies
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxWarning {
+    pub kind: TxWarningKind,
+    /// Output the warning is about, if any
+    pub out_idx: Option<u32>,
+    pub message: String,
+}
+
+/// Bytes of the serialized tx taken by each part. eCash has no segwit, so
+/// the virtual size is the same as the size.
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxSizeBreakdown {
+    pub inputs_size: u32,
+    pub outputs_size: u32,
+    /// Version, lock time and the input and output counts
+    pub overhead_size: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonTxs {
     pub txs: Vec<JsonTx>,
@@ -78,14 +468,305 @@ pub struct JsonTxs {
     pub token_indices: HashMap<Vec<u8>, usize>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonBlocksResponse {
     pub data: Vec<JsonBlock>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBlockResponse {
+    pub block: JsonBlock,
+    pub fee_stats: Option<JsonBlockFeeStats>,
+    pub coinbase: JsonCoinbase,
+    pub output_types: JsonOutputTypeCounts,
+    pub token_summary: JsonBlockTokenSummary,
+    /// Total work of the chain up to this block as 64 hex digits, `null`
+    /// while the explorer is still indexing it
+    pub chainwork: Option<String>,
+    /// `null` for the genesis block
+    pub prev_block_hash: Option<String>,
+    /// Next block on the main chain, `null` for the tip and stale blocks
+    pub next_block_hash: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct JsonTxsResponse {
     pub data: Vec<JsonTx>,
 }
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonUtxosResponse {
+    pub data: Vec<JsonUtxo>,
+    pub num_utxos: usize,
+    /// XEC the address received in unconfirmed txs, already in `data`
+    pub unconfirmed_received_sats: i64,
+    /// XEC the address spent in unconfirmed txs, whose UTXOs are left out of `data`
+    pub unconfirmed_sent_sats: i64,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressesRequest {
+    pub addresses: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTokenBalance {
+    pub token_id: String,
+    pub sats_amount: i64,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
+    pub token_amount: i128,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressBalance {
+    pub address: String,
+    pub sats_amount: i64,
+    pub tokens: Vec<JsonTokenBalance>,
+    pub num_txs: u32,
+    pub last_activity: Option<i64>,
+}
+
+/// Totals are in sats and `None` if the address has too many txs to sum up.
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressSummary {
+    pub num_txs: u32,
+    pub first_seen: Option<i64>,
+    pub last_activity: Option<i64>,
+    pub total_received: Option<i64>,
+    pub total_sent: Option<i64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressBalancesResponse {
+    pub data: Vec<JsonAddressBalance>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxPackageRequest {
+    pub raw_txs: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxPackageResponse {
+    pub accepted_txids: Vec<String>,
+    pub failed_index: Option<usize>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonApiUsage {
+    /// Name of the API key, or null for anonymous clients
+    pub key_name: Option<String>,
+    pub per_minute_limit: u32,
+    pub daily_limit: u32,
+    pub used_this_minute: u32,
+    pub used_today: u32,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonAddressLabelRequest {
+    pub address: String,
+    /// Removes the label if null or empty
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWebhook {
+    pub id: u64,
+    pub address: String,
+    pub url: String,
+    /// Notified on crossing this balance if set, otherwise on every tx
+    pub balance_threshold_sats: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonWebhookRequest {
+    pub address: String,
+    pub url: String,
+    pub balance_threshold_sats: Option<i64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraphNode {
+    pub tx_hash: String,
+    /// Negative for ancestors, positive for descendants, 0 for the requested tx
+    pub depth: i32,
+    pub block_height: Option<i32>,
+    pub is_coinbase: bool,
+    pub sats_output: i64,
+}
+
+#[derive(Serialize, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraphEdge {
+    pub from_tx_hash: String,
+    pub out_idx: u32,
+    pub to_tx_hash: String,
+    pub input_idx: u32,
+    pub sats_amount: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxGraph {
+    pub nodes: Vec<JsonTxGraphNode>,
+    pub edges: Vec<JsonTxGraphEdge>,
+    /// Whether the graph was cut off at the node limit
+    pub is_truncated: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchSuggestionKind {
+    Block,
+    Tx,
+    Token,
+    Address,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSearchSuggestion {
+    pub kind: SearchSuggestionKind,
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSearchSuggestionsResponse {
+    pub data: Vec<JsonSearchSuggestion>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonMerkleProof {
+    pub tx_hash: String,
+    pub block_hash: String,
+    pub block_height: i32,
+    pub merkle_root: String,
+    pub position: usize,
+    pub branch: Vec<String>,
+}
+
+/// Just the confirmation status of a tx, for the tx page to poll
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxConfirmations {
+    pub tx_hash: String,
+    /// `None` while the tx is unconfirmed
+    pub block_hash: Option<String>,
+    pub block_height: Option<i32>,
+    pub confirmations: i32,
+    /// Whether the block has 10 confirmations, too deep to be reorged
+    pub is_confirmed: bool,
+}
+
+/// A script split into opcodes, for `/api/tx/:hash/scripts` and the tx page
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonScript {
+    pub hex: String,
+    /// Opcodes separated by spaces, with pushes as hex
+    pub asm: String,
+    pub ops: Vec<JsonScriptOp>,
+    /// Set if a push runs past the end of the script; `ops` then ends before it
+    pub is_malformed: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonScriptOp {
+    pub opcode: String,
+    pub description: &'static str,
+    pub data_hex: Option<String>,
+    /// Pushed data if it's printable UTF-8
+    pub data_utf8: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxScripts {
+    pub tx_hash: String,
+    pub input_scripts: Vec<JsonScript>,
+    pub output_scripts: Vec<JsonScript>,
+}
+
+/// Net flow of an address (or other output script) in a tx
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxFlow {
+    pub address: Option<String>,
+    pub script_hex: String,
+    pub sats_input: i64,
+    pub sats_output: i64,
+    pub delta_sats: i64,
+    #[serde(serialize_with = "serialize_token_amount")]
+    #[schemars(with = "String")]
+    pub delta_tokens: i128,
+}
+
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTxFlows {
+    pub tx_hash: String,
+    /// Senders first, then receivers
+    pub flows: Vec<JsonTxFlow>,
+    pub fee_sats: i64,
+}
+
+/// A query of a `POST /api/batch` request, e.g. `{"type": "tx", "hash": "..."}`
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonBatchQuery {
+    Tx { hash: String },
+    Block { hash: String },
+    BlockHeight { height: i32 },
+    AddressBalance { address: String },
+}
+
+/// Result of a batch query. Queries fail independently, a failed query
+/// results in `{"error": "..."}`.
+#[derive(Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum JsonBatchResult {
+    Tx(JsonTxDetails),
+    Block(JsonBlock),
+    AddressBalance(JsonAddressBalance),
+    Error { error: String },
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonBatchResponse {
+    pub data: Vec<JsonBatchResult>,
+}
+
+/// Payload of the `new-tx` event
+#[derive(Serialize, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonNewTx {
+    pub txid: String,
+    pub block_height: Option<i32>,
+    pub is_coinbase: bool,
+    pub num_inputs: u32,
+    pub num_outputs: u32,
+    pub sats_output: i64,
+}
@@ -0,0 +1,57 @@
+use std::{collections::HashMap, sync::Mutex};
+
+/// Number of block pages listed per sitemap file, well below the limit of
+/// 50,000 URLs per sitemap
+pub const BLOCKS_PER_SITEMAP: i32 = 10_000;
+
+/// Blocks at least this deep are assumed to be final, so sitemaps of them
+/// never change
+const MIN_CACHE_DEPTH: i32 = 100;
+
+const MAX_CACHED_SITEMAPS: usize = 200;
+
+pub struct SitemapUrl {
+    pub loc: String,
+    /// W3C date, e.g. "2022-08-01"
+    pub lastmod: Option<String>,
+}
+
+/// Height range of the blocks listed in block sitemap `page`, or `None` if
+/// the page is past the tip.
+pub fn block_sitemap_range(page: i32, tip_height: i32) -> Option<(i32, i32)> {
+    let start_height = page.checked_mul(BLOCKS_PER_SITEMAP)?;
+    if page < 0 || start_height > tip_height {
+        return None;
+    }
+    let end_height = (start_height + BLOCKS_PER_SITEMAP - 1).min(tip_height);
+    Some((start_height, end_height))
+}
+
+pub fn num_block_sitemaps(tip_height: i32) -> i32 {
+    tip_height / BLOCKS_PER_SITEMAP + 1
+}
+
+/// Rendered block sitemaps by page. Only sitemaps of final blocks are kept,
+/// the most recent one is rebuilt on every request.
+#[derive(Default)]
+pub struct SitemapCache {
+    sitemaps: Mutex<HashMap<i32, String>>,
+}
+
+impl SitemapCache {
+    pub fn get(&self, page: i32) -> Option<String> {
+        let sitemaps = self.sitemaps.lock().unwrap();
+        sitemaps.get(&page).cloned()
+    }
+
+    pub fn insert(&self, page: i32, end_height: i32, tip_height: i32, sitemap: &str) {
+        if tip_height - end_height < MIN_CACHE_DEPTH {
+            return;
+        }
+        let mut sitemaps = self.sitemaps.lock().unwrap();
+        if sitemaps.len() >= MAX_CACHED_SITEMAPS {
+            sitemaps.clear();
+        }
+        sitemaps.insert(page, sitemap.to_string());
+    }
+}
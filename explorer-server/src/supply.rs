@@ -0,0 +1,41 @@
+//! Emission schedule of XEC. The block subsidy started at 50 coins, which
+//! became 50,000,000 XEC (5,000,000,000 sats) with the redenomination, and
+//! halves every 210,000 blocks. Fees go to the miner, so they don't change
+//! the supply.
+
+pub const HALVING_INTERVAL: i32 = 210_000;
+const INITIAL_SUBSIDY_SATS: i64 = 5_000_000_000;
+/// After 64 halvings, the subsidy is shifted out completely
+const MAX_HALVINGS: i32 = 64;
+
+/// New sats created by the block at `height`
+pub fn block_subsidy(height: i32) -> i64 {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= MAX_HALVINGS {
+        return 0;
+    }
+    INITIAL_SUBSIDY_SATS >> halvings
+}
+
+/// Sats created by the blocks up to and including `height`, including the
+/// genesis block's subsidy, which can't be spent
+pub fn supply_at_height(height: i32) -> i64 {
+    let num_blocks = i64::from(height) + 1;
+    (0..MAX_HALVINGS)
+        .map(|era| {
+            let era_start = i64::from(era) * i64::from(HALVING_INTERVAL);
+            let era_blocks = (num_blocks - era_start).clamp(0, i64::from(HALVING_INTERVAL));
+            era_blocks * (INITIAL_SUBSIDY_SATS >> era)
+        })
+        .sum()
+}
+
+/// Sats that will ever be created
+pub fn max_supply() -> i64 {
+    supply_at_height(MAX_HALVINGS * HALVING_INTERVAL)
+}
+
+/// Height of the first block with a halved subsidy after `height`
+pub fn next_halving_height(height: i32) -> i32 {
+    (height / HALVING_INTERVAL + 1) * HALVING_INTERVAL
+}
@@ -5,18 +5,135 @@ use bitcoinsuite_chronik_client::proto::{
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
-use crate::{blockchain::Destination, server_primitives::JsonBalance};
+use crate::{
+    alp::AlpSection,
+    blockchain::{Destination, MempoolChain, MintBaton},
+    config::Network,
+    locale::{Denomination, DisplayLocale},
+    pagination::Pagination,
+    server_primitives::{
+        CoinbaseOutputKind, JsonAddressSummary, JsonBalance, JsonBlock, JsonBlockFeeStats,
+        JsonBlockTokenSummary, JsonCoinbase, JsonFork, JsonNetworkStats, JsonOutputTypeCounts,
+        JsonSearchSuggestion, JsonSupply, JsonTokenBurn, JsonTx, JsonTxFlows, JsonTxSizeBreakdown,
+        JsonTxWarning, TxWarningKind,
+    },
+    sitemap::SitemapUrl,
+    token_docs::DocCheck,
+    xpub::XpubWallet,
+};
 
 mod filters;
 
 #[derive(Template)]
 #[template(path = "pages/homepage.html")]
-pub struct HomepageTemplate {}
+pub struct HomepageTemplate {
+    pub stats: JsonNetworkStats,
+    /// Set by the operator in the config
+    pub announcement: Option<String>,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "opensearch.xml")]
+pub struct OpenSearchTemplate<'a> {
+    pub base_url: &'a str,
+}
+
+/// Look of an embedded widget, set with the `theme` and `accent` query params
+pub struct WidgetStyle {
+    pub is_dark: bool,
+    /// Hex color of links and badges, without the `#`
+    pub accent: String,
+    pub refresh_secs: u32,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/tx.html")]
+pub struct TxWidgetTemplate<'a> {
+    pub style: WidgetStyle,
+    pub tx_hex: &'a str,
+    pub sats_amount: i64,
+    pub confirmations: i32,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+}
+
+#[derive(Template)]
+#[template(path = "widgets/address.html")]
+pub struct AddressWidgetTemplate<'a> {
+    pub style: WidgetStyle,
+    pub address: &'a str,
+    pub label: Option<String>,
+    pub sats_amount: i64,
+    /// Sats in unconfirmed UTXOs
+    pub pending_sats: i64,
+    pub num_txs: u32,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+}
+
+#[derive(Template)]
+#[template(path = "sitemap_index.xml")]
+pub struct SitemapIndexTemplate {
+    pub sitemap_urls: Vec<String>,
+}
+
+#[derive(Template)]
+#[template(path = "sitemap.xml")]
+pub struct SitemapTemplate {
+    pub urls: Vec<SitemapUrl>,
+}
 
 #[derive(Template)]
 #[template(path = "pages/blocks.html")]
 pub struct BlocksTemplate {
     pub last_block_height: u32,
+    pub blocks: Vec<JsonBlock>,
+    pub encoded_blocks: String,
+    pub pagination: Pagination,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/difficulty_chart.html")]
+pub struct DifficultyChartTemplate {
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/forks.html")]
+pub struct ForksTemplate {
+    pub forks: Vec<JsonFork>,
+    /// Forks at least this deep are highlighted
+    pub alert_depth: u32,
+    pub locale: DisplayLocale,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/xpub.html")]
+pub struct XpubTemplate<'a> {
+    pub xpub: &'a str,
+    pub wallet: XpubWallet,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/supply.html")]
+pub struct SupplyTemplate {
+    pub supply: JsonSupply,
+    pub percent_emitted: f64,
+    /// Estimated from the 10 minute block interval
+    pub next_halving_at: DateTime<Utc>,
+    /// Supply and maximum supply in XEC by height, for the chart
+    pub chart_data: String,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
 }
 
 #[derive(Template)]
@@ -28,9 +145,26 @@ pub struct BlockTemplate<'a> {
     pub block_details: BlockDetails,
     pub confirmations: i32,
     pub timestamp: DateTime<chrono::Utc>,
+    /// When the explorer first saw the block, if it was running then
+    pub received_at: Option<DateTime<chrono::Utc>>,
     pub difficulty: f64,
-    pub coinbase_data: Vec<u8>,
+    /// Expected number of hashes to mine the block
+    pub work: f64,
+    /// Cumulative chain work as hex, if indexed yet
+    pub chainwork: Option<String>,
     pub best_height: i32,
+    /// `None` for the genesis block
+    pub prev_block_hash: Option<String>,
+    /// Next block on the main chain, `None` for the tip and stale blocks
+    pub next_block_hash: Option<String>,
+    pub fee_stats: Option<JsonBlockFeeStats>,
+    pub coinbase: JsonCoinbase,
+    pub output_types: JsonOutputTypeCounts,
+    pub token_summary: JsonBlockTokenSummary,
+    pub structured_data: String,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
 }
 
 #[derive(Template)]
@@ -43,24 +177,70 @@ pub struct TransactionTemplate<'a> {
     pub token_hex: Option<String>,
     pub tx: Tx,
     pub slp_genesis_info: Option<SlpGenesisInfo>,
+    pub token_doc_check: Option<DocCheck>,
+    pub mint_baton: Option<MintBaton>,
+    /// Unconfirmed ancestors and descendants, only for unconfirmed txs
+    pub mempool_chain: Option<MempoolChain>,
     pub slp_meta: Option<SlpMeta>,
     pub raw_tx: String,
+    pub size_breakdown: JsonTxSizeBreakdown,
+    pub warnings: Vec<JsonTxWarning>,
+    /// Fee in sats per kB
+    pub fee_rate: i64,
     pub confirmations: i32,
+    /// Whether the tx is too deep to be reorged, so the page stops polling
+    pub is_confirmed: bool,
     pub timestamp: DateTime<Utc>,
+    /// When Chronik first saw the tx in its mempool
+    pub time_first_seen: Option<i64>,
+    /// Seconds from first seen until it was mined
+    pub confirmation_delay_secs: Option<i64>,
     pub sats_input: i64,
     pub sats_output: i64,
     pub token_input: i128,
     pub token_output: i128,
+    pub token_burns: Vec<JsonTokenBurn>,
+    pub tx_flows: JsonTxFlows,
+    pub alp_sections: Vec<Result<AlpSection, String>>,
+    pub address_labels: HashMap<String, String>,
+    pub lock_time_description: String,
+    pub coin_days_destroyed: Option<i64>,
+    /// USD value of the outputs at the XEC price of the day of the tx
+    pub value_usd: Option<f64>,
+    pub structured_data: String,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
 }
 
 #[derive(Template)]
 #[template(path = "pages/address.html")]
 pub struct AddressTemplate<'a> {
+    pub label: Option<String>,
+    pub summary: JsonAddressSummary,
+    pub cospent_addresses: Vec<(String, u32)>,
     pub tokens: HashMap<String, Token>,
     pub token_dust: i64,
     pub total_xec: i64,
+    /// Balance without immature coinbase outputs
+    pub spendable_xec: i64,
+    /// Already in `total_xec`
+    pub unconfirmed_received_xec: i64,
+    /// Already taken out of `total_xec`
+    pub unconfirmed_sent_xec: i64,
+    /// Script of the address' bare pubkey, if coins were sent to it
+    /// Unconfirmed txs, shown above the confirmed history
+    pub unconfirmed_txs: Vec<JsonTx>,
+    pub unconfirmed_delta_sats: i64,
+    pub p2pk_script: Option<String>,
+    pub p2pk_num_txs: u32,
     pub token_utxos: Vec<Utxo>,
     pub address_num_txs: u32,
+    pub has_price_history: bool,
+    pub pagination: Pagination,
+    pub token_filter: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
     pub address: &'a str,
     pub sats_address: &'a str,
     pub token_address: &'a str,
@@ -68,10 +248,47 @@ pub struct AddressTemplate<'a> {
     pub json_balances: HashMap<String, JsonBalance>,
     pub encoded_tokens: String,
     pub encoded_balances: String,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/script.html")]
+pub struct ScriptTemplate<'a> {
+    pub script_hex: &'a str,
+    pub script: Vec<u8>,
+    pub script_kind: &'a str,
+    /// P2PKH address of the pubkey of a P2PK script
+    pub pubkey_address: Option<String>,
+    pub balance_sats: i64,
+    pub num_txs: u32,
+    pub txs: Vec<JsonTx>,
+    pub pagination: Pagination,
+    pub locale: DisplayLocale,
+    pub unit: Denomination,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/404.html")]
+pub struct NotFoundTemplate<'a> {
+    /// What was searched for or the last part of the URL
+    pub query: &'a str,
+    pub suggestions: Vec<JsonSearchSuggestion>,
+    pub network: Network,
+}
+
+#[derive(Template)]
+#[template(path = "pages/maintenance.html")]
+pub struct MaintenanceTemplate<'a> {
+    pub message: &'a str,
+    pub network: Network,
 }
 
 #[derive(Template)]
 #[template(path = "pages/error.html")]
-pub struct ErrorTemplate {
-    pub message: String,
-}
\ No newline at end of file
+pub struct ErrorTemplate<'a> {
+    pub message: &'a str,
+    pub network: Network,
+}
@@ -1,15 +1,23 @@
 use std::collections::HashMap;
 
-use chrono::DateTime;
-use chrono_humanize::HumanTime;
+use chrono::{DateTime, TimeZone};
+use chrono_humanize::{Accuracy, HumanTime, Tense};
 use maud::{html, PreEscaped};
 
 use bitcoinsuite_chronik_client::proto::{OutPoint, SlpToken, Token};
-use bitcoinsuite_core::Script;
+use bitcoinsuite_core::CashAddress;
 use humansize::{file_size_opts as options, FileSize};
 use num_format::{Locale, ToFormattedString};
 
-use crate::blockchain;
+use crate::{
+    api, blockchain,
+    config::Network,
+    labels::label_key,
+    locale::{Denomination, DisplayLocale},
+    pagination::Pagination,
+    script_analysis::{self, RedeemScriptAnalysis},
+    server_primitives::JsonScript,
+};
 
 fn render_integer_with_small_flag(int: i128, smallify: bool) -> askama::Result<String> {
     let string = int.to_formatted_string(&Locale::en);
@@ -38,14 +46,117 @@ pub fn check_is_coinbase(outpoint: &OutPoint) -> askama::Result<bool> {
 pub fn destination_from_script<'a>(
     script: &'a [u8],
     is_token: &bool,
+    network: &Network,
 ) -> askama::Result<blockchain::Destination<'a>> {
-    let prefix = if *is_token { "etoken" } else { "ecash" };
+    let prefix = if *is_token {
+        network.token_addr_prefix()
+    } else {
+        network.addr_prefix()
+    };
     Ok(blockchain::destination_from_script(prefix, script))
 }
 
-pub fn get_script(signature_script: &[u8]) -> askama::Result<String> {
-    let script = Script::from_slice(signature_script);
-    Ok(script.hex())
+/// Redeem script analysis of an input spending a P2SH output, if any.
+pub fn analyze_redeem_script(
+    input_script: &[u8],
+    output_script: &[u8],
+) -> askama::Result<Option<RedeemScriptAnalysis>> {
+    Ok(script_analysis::analyze_p2sh_input(
+        output_script,
+        input_script,
+    ))
+}
+
+pub fn get_script(script: &[u8]) -> askama::Result<JsonScript> {
+    Ok(api::script_to_json(script))
+}
+
+/// Renders the opcodes of a script with tooltips. Pushes are rendered both as
+/// hex and UTF-8, and common.js switches between them and the plain ASM.
+pub fn render_script_ops(script: &JsonScript) -> askama::Result<String> {
+    let output = html! {
+        div.script-ops data-view="hex" {
+            div.script-ops__toggle {
+                button.ui.mini.button.active data-view="hex" { "Hex" }
+                button.ui.mini.button data-view="utf8" { "UTF-8" }
+                button.ui.mini.button data-view="asm" { "ASM" }
+            }
+            div.script-ops__annotated {
+                @for op in &script.ops {
+                    @match &op.data_hex {
+                        Some(data_hex) => {
+                            span.script-op.script-op--push title=(op.description) {
+                                span.script-op__name { (op.opcode) }
+                                " "
+                                span.script-op__hex.hex { (data_hex) }
+                                span.script-op__utf8 {
+                                    @match &op.data_utf8 {
+                                        Some(data_utf8) => { "\"" (data_utf8) "\"" },
+                                        None => { (data_hex) },
+                                    }
+                                }
+                            }
+                        }
+                        None => {
+                            span.script-op title=(op.description) { (op.opcode) }
+                        }
+                    }
+                }
+                @if script.is_malformed {
+                    span.script-op.script-op--malformed { "[malformed push]" }
+                }
+            }
+            div.script-ops__asm.hex { (script.asm) }
+        }
+    };
+    Ok(output.into_string())
+}
+
+pub fn render_mint_baton(mint_baton: &blockchain::MintBaton) -> askama::Result<String> {
+    let output = match mint_baton {
+        blockchain::MintBaton::Active {
+            tx_hash,
+            out_idx,
+            address,
+        } => html! {
+            div.ui.green.horizontal.label { "Active" }
+            @match address {
+                Some(address) => { "at " a href=(format!("/address/{}", address)) { (address) } }
+                None => { "at " a href=(format!("/tx/{}", tx_hash)) { (minify_hash(tx_hash)?) ":" (out_idx) } }
+            }
+        },
+        blockchain::MintBaton::Destroyed { tx_hash } => html! {
+            div.ui.red.horizontal.label { "Destroyed" }
+            "by " a href=(format!("/tx/{}", tx_hash)) { (minify_hash(tx_hash)?) }
+        },
+        blockchain::MintBaton::Untracked { tx_hash, out_idx } => html! {
+            div.ui.grey.horizontal.label { "Many mints" }
+            "last seen at " a href=(format!("/tx/{}", tx_hash)) { (minify_hash(tx_hash)?) ":" (out_idx) }
+        },
+    };
+    Ok(output.into_string())
+}
+
+/// Same markup as `generatePaginationUI` in common.js, which takes over the
+/// links once it's loaded.
+pub fn render_pagination(pagination: &Pagination) -> askama::Result<String> {
+    if pagination.pages.is_empty() {
+        return Ok(String::new());
+    }
+    let output = html! {
+        div.ui.pagination.menu {
+            @for &page in &pagination.pages {
+                @if page == pagination.current_page {
+                    a.item.active { (page) }
+                } @else {
+                    a.item href=(pagination.page_url(page)) onclick=(format!("goToPage(event, {})", page)) {
+                        (page)
+                    }
+                }
+            }
+        }
+    };
+    Ok(output.into_string())
 }
 
 pub fn check_is_token(slp_token: &Option<SlpToken>) -> askama::Result<bool> {
@@ -55,8 +166,56 @@ pub fn check_is_token(slp_token: &Option<SlpToken>) -> askama::Result<bool> {
         .unwrap_or(false))
 }
 
-pub fn human_time(timestamp: &DateTime<chrono::Utc>) -> askama::Result<HumanTime> {
-    Ok(HumanTime::from(*timestamp))
+/// Renders the absolute time in UTC and the relative time. timestamps.js
+/// switches the absolute time to the user's timezone preference and keeps the
+/// relative time up to date.
+pub fn render_timestamp(
+    timestamp: &DateTime<chrono::Utc>,
+    locale: &DisplayLocale,
+) -> askama::Result<String> {
+    let output = html! {
+        span.timestamp data-timestamp=(timestamp.timestamp()) {
+            span.timestamp__absolute { (timestamp.format(locale.date_format())) }
+            " "
+            small.timestamp__relative { "(" (HumanTime::from(*timestamp)) ")" }
+        }
+    };
+    Ok(output.into_string())
+}
+
+pub fn from_unix_timestamp(timestamp: &i64) -> askama::Result<DateTime<chrono::Utc>> {
+    Ok(chrono::Utc.timestamp(*timestamp, 0))
+}
+
+/// Renders the relative time only, kept up to date by timestamps.js.
+pub fn render_age(timestamp: &DateTime<chrono::Utc>) -> askama::Result<String> {
+    let output = html! {
+        span.timestamp-age data-timestamp=(timestamp.timestamp()) {
+            (HumanTime::from(*timestamp))
+        }
+    };
+    Ok(output.into_string())
+}
+
+/// Renders a number of seconds as a rough duration, e.g. "5 minutes"
+pub fn render_duration(secs: &i64) -> askama::Result<String> {
+    Ok(HumanTime::from(chrono::Duration::seconds(*secs))
+        .to_text_en(Accuracy::Rough, Tense::Present))
+}
+
+pub fn render_address_label(
+    address: &CashAddress,
+    address_labels: &HashMap<String, String>,
+) -> askama::Result<String> {
+    let output = match address_labels.get(&label_key(address)) {
+        Some(label) => html! { span.address-label { (label) } },
+        None => html! {},
+    };
+    Ok(output.into_string())
+}
+
+pub fn describe_sequence(sequence: &u32, tx_version: &i32) -> askama::Result<String> {
+    Ok(blockchain::describe_sequence(*sequence, *tx_version))
 }
 
 pub fn render_integer(int: &i128) -> askama::Result<String> {
@@ -67,21 +226,44 @@ pub fn render_integer_smallify(int: &i128) -> askama::Result<String> {
     render_integer_with_small_flag(*int, true)
 }
 
+pub fn render_usd(value: &f64) -> askama::Result<String> {
+    Ok(format!("${:.2}", value))
+}
+
 pub fn render_human_size(value: &u64) -> askama::Result<String> {
     Ok(value.file_size(options::CONVENTIONAL).unwrap())
 }
 
-pub fn render_difficulty(difficulty: &f64) -> askama::Result<String> {
+pub fn render_hashrate(difficulty: &f64) -> askama::Result<String> {
     let est_hashrate = difficulty * (0xffffffffu64 as f64) / 600.0;
     let hashrate = if est_hashrate < 1e12 {
-        html! { (format!("{:.2} GH/s", est_hashrate / 1e9)) }
+        format!("{:.2} GH/s", est_hashrate / 1e9)
     } else if est_hashrate < 1e15 {
-        html! { (format!("{:.2} TH/s", est_hashrate / 1e12)) }
+        format!("{:.2} TH/s", est_hashrate / 1e12)
     } else if est_hashrate < 1e18 {
-        html! { (format!("{:.2} PH/s", est_hashrate / 1e15)) }
+        format!("{:.2} PH/s", est_hashrate / 1e15)
     } else {
-        html! { (format!("{:.2} EH/s", est_hashrate / 1e18)) }
+        format!("{:.2} EH/s", est_hashrate / 1e18)
     };
+    Ok(hashrate)
+}
+
+/// Same units as `renderSize` in blocks.js, so the server rendered rows
+/// don't change when the table takes over.
+pub fn render_block_size(size: &u64) -> askama::Result<String> {
+    let size = *size as f64;
+    let output = if size < 1024.0 {
+        format!("{} B", size)
+    } else if size < 1024.0 * 1024.0 {
+        format!("{:.2} kB", size / 1e3)
+    } else {
+        format!("{:.2} MB", size / 1e6)
+    };
+    Ok(output)
+}
+
+pub fn render_difficulty(difficulty: &f64) -> askama::Result<String> {
+    let hashrate = render_hashrate(difficulty)?;
     let num_digits = difficulty.log10().floor();
     let exponent = (num_digits / 3.0) as u32;
     let difficulty = match exponent {
@@ -104,14 +286,15 @@ pub fn render_difficulty(difficulty: &f64) -> askama::Result<String> {
     Ok(output.into_string())
 }
 
-pub fn render_integer_with_commas(int: &u64) -> askama::Result<String> {
-    let string = int.to_formatted_string(&Locale::en);
-    let parts = string.split(',').collect::<Vec<_>>();
+pub fn render_integer_with_commas(int: &u64, locale: &DisplayLocale) -> askama::Result<String> {
+    let separator = locale.grouping_separator();
+    let string = int.to_formatted_string(&locale.num_format_locale());
+    let parts = string.split(separator).collect::<Vec<_>>();
 
     let output = html! {
         @for (idx, part) in parts.iter().enumerate() {
             @if idx != 0 {
-                span.non-selectable { "," }
+                span.non-selectable { (separator) }
             }
             span { (part) }
         }
@@ -120,25 +303,35 @@ pub fn render_integer_with_commas(int: &u64) -> askama::Result<String> {
     Ok(output.into_string())
 }
 
-pub fn render_sats(sats: &i64) -> askama::Result<String> {
-    let coins = *sats as f64 / 100.0;
-    let fmt = format!("{:.2}", coins);
-    let mut parts = fmt.split('.');
-    let integer_part: u64 = parts.next().unwrap().parse().unwrap();
-    let fract_part = parts.next().unwrap();
+/// Renders an amount of sats in the user's unit, followed by its symbol. The
+/// fraction is split off with integer arithmetic, so nothing is rounded.
+pub fn render_amount(
+    sats: &i64,
+    locale: &DisplayLocale,
+    unit: &Denomination,
+) -> askama::Result<String> {
+    let sign = if *sats < 0 { "-" } else { "" };
+    let integer_part = sats.unsigned_abs() / unit.sats_per_unit();
+    let fract_part = sats.unsigned_abs() % unit.sats_per_unit();
 
-    let output = {
-        let output = html! {
-            (PreEscaped(render_integer_with_commas(&integer_part)?))
-            "."
+    let output = html! {
+        (sign)
+        (PreEscaped(render_integer_with_commas(&integer_part, locale)?))
+        @if unit.decimals() > 0 {
+            (locale.decimal_separator())
             small {
-                (fract_part)
+                (format!("{:0width$}", fract_part, width = unit.decimals()))
             }
-        };
-        output.into_string()
+        }
+        " "
+        (unit.symbol())
     };
 
-    Ok(output)
+    Ok(output.into_string())
+}
+
+pub fn minify_hash(hash: &str) -> askama::Result<String> {
+    Ok(format!("{}...{}", &hash[..1], &hash[39..]))
 }
 
 pub fn hexify_u8_vector(value: &[u8]) -> askama::Result<String> {
@@ -161,12 +354,22 @@ pub fn to_i128<T: Into<i128> + Copy>(value: &T) -> askama::Result<i128> {
     Ok((*value).into())
 }
 
-pub fn render_token_amount(base_amount: &i128, decimals: &u32) -> askama::Result<String> {
+pub fn render_token_amount(
+    base_amount: &i128,
+    decimals: &u32,
+    locale: &DisplayLocale,
+) -> askama::Result<String> {
     let decimals = *decimals as usize;
     if decimals == 0 {
         return render_integer(base_amount);
     }
-    let base_amount_str = format!("{:0digits$}", base_amount, digits = decimals + 1);
+    // Formatted without the sign, which would be padded as a digit
+    let sign = if *base_amount < 0 { "-" } else { "" };
+    let base_amount_str = format!(
+        "{:0digits$}",
+        base_amount.unsigned_abs(),
+        digits = decimals + 1
+    );
     let decimal_idx = base_amount_str.len() - decimals;
     let integer_part: i128 = base_amount_str[..decimal_idx].parse().unwrap();
     let fract_part = &base_amount_str[decimal_idx..];
@@ -186,7 +389,9 @@ pub fn render_token_amount(base_amount: &i128, decimals: &u32) -> askama::Result
             (rendered)
         };
     }
-    let output = html! { (PreEscaped(render_integer(&integer_part)?)) "." (rendered) };
+    let output = html! {
+        (sign) (PreEscaped(render_integer(&integer_part)?)) (locale.decimal_separator()) (rendered)
+    };
     Ok(output.into_string())
 }
 
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bitcoin::hashes::{sha256, Hash};
+use bitcoinsuite_error::Result;
+use eyre::{bail, eyre};
+use reqwest::{redirect, Url};
+
+use crate::config::TokenDocCheckConfig;
+
+const MAX_CACHED_CHECKS: usize = 10_000;
+
+/// Result of comparing a token's document with the hash in its genesis tx
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DocCheck {
+    /// The document is being fetched in the background
+    Pending,
+    Verified,
+    Mismatch,
+    /// The document couldn't be fetched, e.g. it's too large or the server is
+    /// down. The reason is only logged, as it can reveal details of the
+    /// explorer's network.
+    Failed,
+}
+
+impl DocCheck {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DocCheck::Pending => "Checking document",
+            DocCheck::Verified => "Document verified",
+            DocCheck::Mismatch => "Document mismatch",
+            DocCheck::Failed => "Document unavailable",
+        }
+    }
+
+    pub fn label_color(&self) -> &'static str {
+        match self {
+            DocCheck::Verified => "green",
+            DocCheck::Mismatch => "red",
+            DocCheck::Pending | DocCheck::Failed => "grey",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            DocCheck::Pending => "Reload the page to see the result".to_string(),
+            DocCheck::Verified => "The document at the URI matches this hash".to_string(),
+            DocCheck::Mismatch => "The document at the URI doesn't match this hash".to_string(),
+            DocCheck::Failed => "The document at the URI couldn't be fetched".to_string(),
+        }
+    }
+}
+
+/// Fetches token documents in the background and caches whether they match
+/// the document hash, so pages never wait on third-party servers.
+///
+/// Document URIs are chosen by token creators, so only public addresses are
+/// fetched and redirects aren't followed, which keeps the explorer from
+/// being used to reach services on its own network.
+pub struct TokenDocChecker {
+    timeout: Duration,
+    max_size: usize,
+    cache_ttl: Duration,
+    /// Check results by token id, with the time they were made
+    checks: Mutex<HashMap<Vec<u8>, (Instant, DocCheck)>>,
+}
+
+impl TokenDocChecker {
+    pub fn new(config: &TokenDocCheckConfig) -> Result<Self> {
+        Ok(TokenDocChecker {
+            timeout: Duration::from_secs(config.timeout_secs),
+            max_size: config.max_size,
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            checks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the cached check of the token's document, or starts one in the
+    /// background and returns [`DocCheck::Pending`].
+    pub fn check(
+        self: &Arc<Self>,
+        token_id: &[u8],
+        document_url: &str,
+        document_hash: &[u8],
+    ) -> DocCheck {
+        {
+            let mut checks = self.checks.lock().unwrap();
+            if let Some((checked_at, check)) = checks.get(token_id) {
+                if *check == DocCheck::Pending || checked_at.elapsed() < self.cache_ttl {
+                    return check.clone();
+                }
+            }
+            if checks.len() >= MAX_CACHED_CHECKS {
+                checks.clear();
+            }
+            checks.insert(token_id.to_vec(), (Instant::now(), DocCheck::Pending));
+        }
+
+        let checker = Arc::clone(self);
+        let token_id = token_id.to_vec();
+        let document_url = document_url.to_string();
+        let document_hash = document_hash.to_vec();
+        tokio::spawn(async move {
+            let check = match checker.fetch_document(&document_url).await {
+                Ok(document) => {
+                    if sha256::Hash::hash(&document)[..] == document_hash[..] {
+                        DocCheck::Verified
+                    } else {
+                        DocCheck::Mismatch
+                    }
+                }
+                Err(err) => {
+                    eprintln!(
+                        "Checking document {} of token {} failed: {}",
+                        document_url,
+                        hex::encode(&token_id),
+                        err
+                    );
+                    DocCheck::Failed
+                }
+            };
+            let mut checks = checker.checks.lock().unwrap();
+            checks.insert(token_id, (Instant::now(), check));
+        });
+
+        DocCheck::Pending
+    }
+
+    async fn fetch_document(&self, document_url: &str) -> Result<Vec<u8>> {
+        let url = Url::parse(document_url)?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            bail!("Only http(s) documents can be checked");
+        }
+        let host = url.host_str().ok_or_else(|| eyre!("URL has no host"))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| eyre!("URL has no port"))?;
+        // Resolve the host once and connect to that address, so a second DNS
+        // lookup by the client can't return a different, private address
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs = tokio::net::lookup_host((host, port))
+            .await?
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            bail!("{} doesn't resolve to any address", host);
+        }
+        if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+            bail!("{} resolves to non-public address {}", host, addr.ip());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .no_proxy()
+            .redirect(redirect::Policy::none())
+            .resolve(host, addrs[0])
+            .build()?;
+
+        let mut response = client.get(url).send().await?;
+        if !response.status().is_success() {
+            bail!("Server responded with {}", response.status());
+        }
+        if let Some(content_length) = response.content_length() {
+            if content_length > self.max_size as u64 {
+                bail!("Document is larger than {} bytes", self.max_size);
+            }
+        }
+        let mut document = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if document.len() + chunk.len() > self.max_size {
+                bail!("Document is larger than {} bytes", self.max_size);
+            }
+            document.extend_from_slice(&chunk);
+        }
+        Ok(document)
+    }
+}
+
+/// Whether the address is reachable on the public internet, i.e. not a
+/// loopback, private, link-local or otherwise reserved address
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ipv4) => {
+            let [a, b, ..] = ipv4.octets();
+            !(ipv4.is_unspecified()
+                || ipv4.is_loopback()
+                || ipv4.is_private()
+                || ipv4.is_link_local()
+                || ipv4.is_broadcast()
+                || ipv4.is_documentation()
+                || ipv4.is_multicast()
+                // Shared address space (100.64.0.0/10), used by carrier-grade NAT
+                || (a == 100 && b & 0xc0 == 64)
+                // "This network" (0.0.0.0/8)
+                || a == 0)
+        }
+        IpAddr::V6(ipv6) => {
+            let segments = ipv6.segments();
+            if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_ip(IpAddr::from([a, b, c, d]));
+            }
+            !(ipv6.is_unspecified()
+                || ipv6.is_loopback()
+                || ipv6.is_multicast()
+                // Unique local addresses (fc00::/7)
+                || segments[0] & 0xfe00 == 0xfc00
+                // Link-local addresses (fe80::/10)
+                || segments[0] & 0xffc0 == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::is_public_ip;
+
+    fn is_public(ip: &str) -> bool {
+        is_public_ip(ip.parse::<IpAddr>().unwrap())
+    }
+
+    #[test]
+    fn test_public_ips() {
+        assert!(is_public("1.1.1.1"));
+        assert!(is_public("93.184.216.34"));
+        assert!(is_public("2606:4700:4700::1111"));
+        assert!(is_public("::ffff:8.8.8.8"));
+    }
+
+    #[test]
+    fn test_non_public_ips() {
+        for ip in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "255.255.255.255",
+            "224.0.0.1",
+            "::",
+            "::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip), "{} should not be public", ip);
+        }
+    }
+}
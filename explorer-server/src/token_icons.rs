@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::body::Bytes;
+use bitcoinsuite_error::Result;
+use eyre::bail;
+use reqwest::{header::CONTENT_TYPE, StatusCode};
+
+use crate::{config::TokenIconsConfig, identicon::identicon_png};
+
+const MAX_CACHED_ICONS: usize = 1_000;
+/// Extensions of icons in a local icon directory, with their content types
+const LOCAL_ICON_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("jpg", "image/jpeg"),
+    ("webp", "image/webp"),
+];
+
+#[derive(Clone)]
+pub struct TokenIcon {
+    pub content_type: String,
+    pub data: Bytes,
+}
+
+/// Where token icons are looked up
+pub enum IconSource {
+    /// Directory with icons named by token id, e.g. `<token_id>.png`
+    LocalDir(PathBuf),
+    /// URL with `{token_id}` in place of the token id
+    UrlTemplate(String),
+}
+
+/// Looks up token icons in the configured sources, in order, and falls back
+/// to an identicon of the token id. Icons, including the identicons, are
+/// cached in memory, so sources are only asked again after the cache TTL.
+pub struct TokenIcons {
+    client: reqwest::Client,
+    sources: Vec<IconSource>,
+    max_size: usize,
+    cache_ttl: Duration,
+    /// Icons by token id, with the time they were looked up
+    icons: Mutex<HashMap<String, (Instant, TokenIcon)>>,
+}
+
+impl TokenIcons {
+    pub fn new(config: &TokenIconsConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            // upstream_proxy is only for Chronik
+            .no_proxy()
+            .build()?;
+        let mut sources = Vec::new();
+        if let Some(local_dir) = &config.local_dir {
+            sources.push(IconSource::LocalDir(local_dir.clone()));
+        }
+        if let Some(url_template) = &config.url_template {
+            sources.push(IconSource::UrlTemplate(url_template.clone()));
+        }
+        Ok(TokenIcons {
+            client,
+            sources,
+            max_size: config.max_size,
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            icons: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Icon of the token with the given hex id, which must be validated by
+    /// the caller as it ends up in file paths and URLs
+    pub async fn icon(&self, token_id: &str) -> Result<TokenIcon> {
+        if let Some((looked_up_at, icon)) = self.icons.lock().unwrap().get(token_id) {
+            if looked_up_at.elapsed() < self.cache_ttl {
+                return Ok(icon.clone());
+            }
+        }
+
+        let mut found_icon = None;
+        for source in &self.sources {
+            match self.fetch(source, token_id).await {
+                Ok(Some(icon)) => {
+                    found_icon = Some(icon);
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("Fetching icon of token {} failed: {}", token_id, err),
+            }
+        }
+        let icon = match found_icon {
+            Some(icon) => icon,
+            None => TokenIcon {
+                content_type: "image/png".to_string(),
+                data: identicon_png(token_id.as_bytes())?.into(),
+            },
+        };
+
+        let mut icons = self.icons.lock().unwrap();
+        if icons.len() >= MAX_CACHED_ICONS {
+            icons.clear();
+        }
+        icons.insert(token_id.to_string(), (Instant::now(), icon.clone()));
+        Ok(icon)
+    }
+
+    /// The icon from `source`, `None` if it has none for the token
+    async fn fetch(&self, source: &IconSource, token_id: &str) -> Result<Option<TokenIcon>> {
+        match source {
+            IconSource::LocalDir(local_dir) => {
+                for (extension, content_type) in LOCAL_ICON_TYPES {
+                    let path = local_dir.join(format!("{}.{}", token_id, extension));
+                    let metadata = match tokio::fs::metadata(&path).await {
+                        Ok(metadata) => metadata,
+                        Err(_) => continue,
+                    };
+                    if metadata.len() > self.max_size as u64 {
+                        bail!("{} is larger than {} bytes", path.display(), self.max_size);
+                    }
+                    return Ok(Some(TokenIcon {
+                        content_type: content_type.to_string(),
+                        data: tokio::fs::read(&path).await?.into(),
+                    }));
+                }
+                Ok(None)
+            }
+            IconSource::UrlTemplate(url_template) => {
+                let url = url_template.replace("{token_id}", token_id);
+                let mut response = self.client.get(&url).send().await?;
+                if response.status() == StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                response.error_for_status_ref()?;
+                let content_type = match response.headers().get(CONTENT_TYPE) {
+                    Some(content_type) => content_type.to_str()?.to_string(),
+                    None => bail!("{} has no content type", url),
+                };
+                if !content_type.starts_with("image/") {
+                    bail!("{} is not an image but {}", url, content_type);
+                }
+                let mut data = Vec::new();
+                while let Some(chunk) = response.chunk().await? {
+                    if data.len() + chunk.len() > self.max_size {
+                        bail!("{} is larger than {} bytes", url, self.max_size);
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                Ok(Some(TokenIcon {
+                    content_type,
+                    data: data.into(),
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Network upgrades scheduled in the config. Most eCash upgrades activate
+//! once the median time past of the chain reaches their activation time, so
+//! the first block on top of such a chain is the activation block.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use bitcoinsuite_chronik_client::{proto::BlockInfo, ChronikClient};
+use bitcoinsuite_error::Result;
+
+use crate::{
+    config::UpgradeConfig,
+    server_primitives::{JsonUpgrade, JsonUpgradeStatus},
+};
+
+/// Blocks the median time past is taken over
+const MEDIAN_TIME_BLOCKS: i32 = 11;
+const BLOCK_INTERVAL_SECS: i64 = 600;
+
+#[derive(Default)]
+pub struct Upgrades {
+    upgrades: Vec<UpgradeConfig>,
+    /// Activation blocks of time activated upgrades by name, once mined
+    activation_heights: Mutex<HashMap<String, i32>>,
+    /// Status with the tip hash it was computed at
+    status: Mutex<Option<(Vec<u8>, JsonUpgradeStatus)>>,
+}
+
+impl Upgrades {
+    pub fn new(upgrades: Vec<UpgradeConfig>) -> Self {
+        Upgrades {
+            upgrades,
+            ..Default::default()
+        }
+    }
+
+    pub async fn status(&self, chronik: &ChronikClient) -> Result<JsonUpgradeStatus> {
+        let blockchain_info = chronik.blockchain_info().await?;
+        if let Some((tip_hash, status)) = &*self.status.lock().unwrap() {
+            if *tip_hash == blockchain_info.tip_hash {
+                return Ok(status.clone());
+            }
+        }
+
+        let tip_height = blockchain_info.tip_height;
+        let recent_blocks = chronik
+            .blocks((tip_height - MEDIAN_TIME_BLOCKS + 1).max(0), tip_height)
+            .await?;
+        let tip_timestamp = recent_blocks.last().map_or(0, |block| block.timestamp);
+        let tip_median_time = median_time_past(&recent_blocks);
+
+        let mut upgrades = Vec::with_capacity(self.upgrades.len());
+        for upgrade in &self.upgrades {
+            let (activation_block_height, estimated_activation_time) =
+                match (upgrade.activation_height, upgrade.activation_time) {
+                    (Some(activation_height), _) => (
+                        Some(activation_height).filter(|&height| height <= tip_height),
+                        tip_timestamp
+                            + i64::from(activation_height - tip_height) * BLOCK_INTERVAL_SECS,
+                    ),
+                    (None, Some(activation_time)) if tip_median_time >= activation_time => {
+                        let activation_height = self
+                            .activation_height(chronik, &upgrade.name, activation_time, tip_height)
+                            .await?;
+                        (
+                            Some(activation_height).filter(|&height| height <= tip_height),
+                            activation_time,
+                        )
+                    }
+                    (None, Some(activation_time)) => (None, activation_time),
+                    // Rejected by Config::validate
+                    (None, None) => continue,
+                };
+            upgrades.push(JsonUpgrade {
+                name: upgrade.name.clone(),
+                activation_time: upgrade.activation_time,
+                activation_height: upgrade.activation_height,
+                activated: activation_block_height.is_some(),
+                activation_block_height,
+                estimated_activation_time,
+            });
+        }
+
+        let status = JsonUpgradeStatus {
+            tip_height,
+            upgrades,
+        };
+        *self.status.lock().unwrap() = Some((blockchain_info.tip_hash, status.clone()));
+        Ok(status)
+    }
+
+    /// Height of the first block whose parent's median time past reached
+    /// `activation_time`, which is the tip height + 1 if it's not mined yet.
+    /// The median time past never decreases, so it's found by bisection.
+    async fn activation_height(
+        &self,
+        chronik: &ChronikClient,
+        name: &str,
+        activation_time: i64,
+        tip_height: i32,
+    ) -> Result<i32> {
+        if let Some(&height) = self.activation_heights.lock().unwrap().get(name) {
+            return Ok(height);
+        }
+
+        let (mut low, mut high) = (1, tip_height + 1);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let parent_blocks = chronik
+                .blocks((mid - MEDIAN_TIME_BLOCKS).max(0), mid - 1)
+                .await?;
+            if median_time_past(&parent_blocks) >= activation_time {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        if low <= tip_height {
+            let mut activation_heights = self.activation_heights.lock().unwrap();
+            activation_heights.insert(name.to_string(), low);
+        }
+        Ok(low)
+    }
+}
+
+/// Median timestamp of the blocks, which should be the last 11 blocks of the
+/// chain or all of them if it's shorter
+fn median_time_past(blocks: &[BlockInfo]) -> i64 {
+    let mut timestamps = blocks
+        .iter()
+        .map(|block| block.timestamp)
+        .collect::<Vec<_>>();
+    timestamps.sort_unstable();
+    timestamps
+        .get(timestamps.len() / 2)
+        .copied()
+        .unwrap_or_default()
+}
@@ -0,0 +1,372 @@
+//! Webhooks notifying operators of activity on watched addresses. Addresses
+//! are checked whenever the block poller broadcasts a new block, so txs are
+//! reported once they're mined. Notifications are POSTed as JSON from a
+//! queue, and failed deliveries are retried with exponential backoff.
+
+use std::{sync::Mutex, time::Duration};
+
+use bitcoinsuite_chronik_client::ChronikClient;
+use bitcoinsuite_core::CashAddress;
+use bitcoinsuite_error::Result;
+use eyre::bail;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    api::calc_tx_stats,
+    blockchain::{cash_addr_to_script_type_payload, to_be_hex},
+    config::WebhookConfig,
+    events::ExplorerEvent,
+    server_error::BadRequest,
+    server_primitives::{JsonWebhook, JsonWebhookRequest},
+};
+
+/// Each webhook costs a Chronik request per block
+const MAX_WEBHOOKS: usize = 100;
+/// Latest txs of an address checked per block; txs beyond this in a single
+/// block aren't reported
+const HISTORY_PAGE_SIZE: usize = 50;
+const QUEUE_CAPACITY: usize = 1000;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+/// Delay before the first retry, doubled for each further retry
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Notification POSTed to a webhook's URL
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum WebhookEvent {
+    /// A tx sending to or from the address was mined
+    #[serde(rename_all = "camelCase")]
+    Tx {
+        webhook_id: u64,
+        address: String,
+        txid: String,
+        block_height: i32,
+        delta_sats: i64,
+    },
+    /// The balance of the address reached the threshold
+    #[serde(rename_all = "camelCase")]
+    BalanceAbove {
+        webhook_id: u64,
+        address: String,
+        threshold_sats: i64,
+        balance_sats: i64,
+        block_height: i32,
+    },
+    /// The balance of the address fell below the threshold
+    #[serde(rename_all = "camelCase")]
+    BalanceBelow {
+        webhook_id: u64,
+        address: String,
+        threshold_sats: i64,
+        balance_sats: i64,
+        block_height: i32,
+    },
+}
+
+struct Delivery {
+    url: String,
+    event: WebhookEvent,
+    attempt: u32,
+}
+
+struct Watch {
+    webhook: JsonWebhook,
+    /// Txs up to this height have been checked, `None` before the first check
+    checked_height: Option<i32>,
+    /// Balance at the last check, for webhooks with a threshold
+    balance_sats: Option<i64>,
+}
+
+#[derive(Default)]
+struct Watches {
+    watches: Vec<Watch>,
+    next_id: u64,
+}
+
+/// Webhooks from the config and the admin API. Webhooks added with the
+/// admin API are only kept in memory.
+pub struct Webhooks {
+    client: reqwest::Client,
+    watches: Mutex<Watches>,
+    queue: mpsc::Sender<Delivery>,
+    /// Taken by [`Webhooks::deliver_forever`]
+    queue_receiver: Mutex<Option<mpsc::Receiver<Delivery>>>,
+}
+
+impl Webhooks {
+    pub fn new(configs: &[WebhookConfig]) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .no_proxy()
+            .build()?;
+        let (queue, queue_receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let webhooks = Webhooks {
+            client,
+            watches: Mutex::new(Watches::default()),
+            queue,
+            queue_receiver: Mutex::new(Some(queue_receiver)),
+        };
+        for config in configs {
+            webhooks.add(JsonWebhookRequest {
+                address: config.address.clone(),
+                url: config.url.clone(),
+                balance_threshold_sats: config.balance_threshold_sats,
+            })?;
+        }
+        Ok(webhooks)
+    }
+
+    pub fn add(&self, request: JsonWebhookRequest) -> Result<JsonWebhook> {
+        if !request.url.starts_with("http://") && !request.url.starts_with("https://") {
+            bail!(BadRequest(format!(
+                "Webhook URL must be an http:// or https:// URL, got {}",
+                request.url
+            )));
+        }
+        let address = CashAddress::parse_cow(request.address.as_str().into())?;
+
+        let mut watches = self.watches.lock().unwrap();
+        if watches.watches.len() >= MAX_WEBHOOKS {
+            bail!(BadRequest(format!(
+                "At most {} webhooks are allowed",
+                MAX_WEBHOOKS
+            )));
+        }
+        let webhook = JsonWebhook {
+            id: watches.next_id,
+            address: address.as_str().to_string(),
+            url: request.url,
+            balance_threshold_sats: request.balance_threshold_sats,
+        };
+        watches.next_id += 1;
+        watches.watches.push(Watch {
+            webhook: webhook.clone(),
+            checked_height: None,
+            balance_sats: None,
+        });
+        Ok(webhook)
+    }
+
+    /// Removes the webhook, returns whether it existed
+    pub fn remove(&self, id: u64) -> bool {
+        let mut watches = self.watches.lock().unwrap();
+        let num_watches = watches.watches.len();
+        watches.watches.retain(|watch| watch.webhook.id != id);
+        watches.watches.len() != num_watches
+    }
+
+    pub fn list(&self) -> Vec<JsonWebhook> {
+        let watches = self.watches.lock().unwrap();
+        watches
+            .watches
+            .iter()
+            .map(|watch| watch.webhook.clone())
+            .collect()
+    }
+
+    /// Checks the watched addresses for each new block broadcast by the
+    /// block poller, until the poller stops
+    pub async fn check_forever(
+        &self,
+        chronik: &ChronikClient,
+        mut events: broadcast::Receiver<ExplorerEvent>,
+    ) {
+        loop {
+            match events.recv().await {
+                Ok(ExplorerEvent::NewBlock(block)) => self.check(chronik, block.height).await,
+                // A missed block is covered by the check of the next one
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn check(&self, chronik: &ChronikClient, tip_height: i32) {
+        let watches = self
+            .watches
+            .lock()
+            .unwrap()
+            .watches
+            .iter()
+            .map(|watch| {
+                (
+                    watch.webhook.clone(),
+                    watch.checked_height,
+                    watch.balance_sats,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (webhook, checked_height, balance_sats) in watches {
+            let balance_sats = match self
+                .check_webhook(chronik, &webhook, checked_height, balance_sats, tip_height)
+                .await
+            {
+                Ok(balance_sats) => balance_sats,
+                Err(err) => {
+                    eprintln!("Checking webhook {} failed: {}", webhook.id, err);
+                    continue;
+                }
+            };
+            let mut watches = self.watches.lock().unwrap();
+            // Skip webhooks removed in the meantime
+            if let Some(watch) = watches
+                .watches
+                .iter_mut()
+                .find(|watch| watch.webhook.id == webhook.id)
+            {
+                watch.checked_height = Some(tip_height);
+                watch.balance_sats = balance_sats;
+            }
+        }
+    }
+
+    /// Queues the webhook's notifications for the blocks up to `tip_height`
+    /// and returns the balance, if the webhook has a threshold. The first
+    /// check only records the current state.
+    async fn check_webhook(
+        &self,
+        chronik: &ChronikClient,
+        webhook: &JsonWebhook,
+        checked_height: Option<i32>,
+        last_balance_sats: Option<i64>,
+        tip_height: i32,
+    ) -> Result<Option<i64>> {
+        let address = CashAddress::parse_cow(webhook.address.as_str().into())?;
+        let (script_type, script_payload) = cash_addr_to_script_type_payload(&address);
+        let script_endpoint = chronik.script(script_type, &script_payload);
+
+        let threshold_sats = match webhook.balance_threshold_sats {
+            Some(threshold_sats) => threshold_sats,
+            None => {
+                let checked_height = match checked_height {
+                    Some(checked_height) => checked_height,
+                    None => return Ok(None),
+                };
+                let address_script = address.to_script().bytecode().to_vec();
+                let history = script_endpoint
+                    .history_with_page_size(0, HISTORY_PAGE_SIZE)
+                    .await?;
+                // History is latest first
+                for tx in history.txs.iter().rev() {
+                    let block_height = match &tx.block {
+                        Some(block)
+                            if block.height > checked_height && block.height <= tip_height =>
+                        {
+                            block.height
+                        }
+                        _ => continue,
+                    };
+                    self.enqueue(
+                        &webhook.url,
+                        WebhookEvent::Tx {
+                            webhook_id: webhook.id,
+                            address: webhook.address.clone(),
+                            txid: to_be_hex(&tx.txid),
+                            block_height,
+                            delta_sats: calc_tx_stats(tx, Some(&address_script)).delta_sats,
+                        },
+                    );
+                }
+                return Ok(None);
+            }
+        };
+
+        let utxos = script_endpoint.utxos().await?;
+        let balance_sats = utxos
+            .iter()
+            .flat_map(|script_utxos| &script_utxos.utxos)
+            .map(|utxo| utxo.value)
+            .sum();
+        let event = match last_balance_sats {
+            Some(last_balance_sats)
+                if last_balance_sats < threshold_sats && balance_sats >= threshold_sats =>
+            {
+                Some(WebhookEvent::BalanceAbove {
+                    webhook_id: webhook.id,
+                    address: webhook.address.clone(),
+                    threshold_sats,
+                    balance_sats,
+                    block_height: tip_height,
+                })
+            }
+            Some(last_balance_sats)
+                if last_balance_sats >= threshold_sats && balance_sats < threshold_sats =>
+            {
+                Some(WebhookEvent::BalanceBelow {
+                    webhook_id: webhook.id,
+                    address: webhook.address.clone(),
+                    threshold_sats,
+                    balance_sats,
+                    block_height: tip_height,
+                })
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            self.enqueue(&webhook.url, event);
+        }
+        Ok(Some(balance_sats))
+    }
+
+    fn enqueue(&self, url: &str, event: WebhookEvent) {
+        let delivery = Delivery {
+            url: url.to_string(),
+            event,
+            attempt: 0,
+        };
+        if let Err(err) = self.queue.try_send(delivery) {
+            eprintln!(
+                "Webhook queue is full, dropping {:?}",
+                err.into_inner().event
+            );
+        }
+    }
+
+    /// POSTs the queued notifications, retrying failed deliveries in the
+    /// background. Runs until the server shuts down.
+    pub async fn deliver_forever(&self) {
+        let mut queue_receiver = match self.queue_receiver.lock().unwrap().take() {
+            Some(queue_receiver) => queue_receiver,
+            None => return,
+        };
+        while let Some(delivery) = queue_receiver.recv().await {
+            let err = match self.deliver(&delivery).await {
+                Ok(()) => continue,
+                Err(err) => err,
+            };
+            if delivery.attempt + 1 >= MAX_DELIVERY_ATTEMPTS {
+                eprintln!(
+                    "Giving up on delivering webhook to {} after {} attempts: {}",
+                    delivery.url, MAX_DELIVERY_ATTEMPTS, err
+                );
+                continue;
+            }
+            let queue = self.queue.clone();
+            let delay = RETRY_DELAY * 2u32.pow(delivery.attempt);
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                let _ = queue
+                    .send(Delivery {
+                        attempt: delivery.attempt + 1,
+                        ..delivery
+                    })
+                    .await;
+            });
+        }
+    }
+
+    async fn deliver(&self, delivery: &Delivery) -> Result<()> {
+        self.client
+            .post(&delivery.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&delivery.event)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
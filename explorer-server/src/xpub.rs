@@ -0,0 +1,186 @@
+//! Wallets given by an extended public key (BIP32). The receive and change
+//! addresses are derived until `gap_limit` unused addresses in a row, like
+//! wallets do, and each is looked up in Chronik. Scans are capped at
+//! `max_addresses` per chain and cached, as each address costs requests.
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bitcoin::{
+    secp256k1::{Secp256k1, VerifyOnly},
+    util::bip32::{ChildNumber, ExtendedPubKey},
+};
+use bitcoinsuite_chronik_client::ChronikClient;
+use bitcoinsuite_error::Result;
+use eyre::bail;
+use futures::{stream, StreamExt, TryStreamExt};
+
+use crate::{
+    blockchain::{cash_addr_to_script_type_payload, pubkey_address},
+    config::XpubConfig,
+    server_error::BadRequest,
+};
+
+const MAX_CACHED_WALLETS: usize = 100;
+const FETCH_CONCURRENCY: usize = 8;
+/// Chains derived from the xpub: receive addresses, then change addresses
+const CHAINS: [u32; 2] = [0, 1];
+
+#[derive(Clone)]
+pub struct XpubAddress {
+    /// Derivation path relative to the xpub, e.g. "0/5"
+    pub path: String,
+    pub address: String,
+    pub num_txs: u32,
+    /// XEC balance, excluding token UTXOs
+    pub balance_sats: i64,
+}
+
+#[derive(Clone)]
+pub struct XpubWallet {
+    /// Used addresses, receive addresses first
+    pub addresses: Vec<XpubAddress>,
+    pub balance_sats: i64,
+    /// Sum of the tx counts of the addresses, so txs between addresses of
+    /// the wallet are counted for each of them
+    pub num_txs: u64,
+    pub num_derived: usize,
+    /// Whether a chain hit `max_addresses` before the gap limit, so there
+    /// might be used addresses that weren't scanned
+    pub truncated: bool,
+}
+
+pub struct XpubScanner {
+    secp: Secp256k1<VerifyOnly>,
+    gap_limit: u32,
+    max_addresses: u32,
+    cache_ttl: Duration,
+    /// Wallets by xpub, with the time they were scanned
+    wallets: Mutex<HashMap<String, (Instant, XpubWallet)>>,
+}
+
+impl XpubScanner {
+    pub fn new(config: &XpubConfig) -> Self {
+        XpubScanner {
+            secp: Secp256k1::verification_only(),
+            gap_limit: config.gap_limit,
+            max_addresses: config.max_addresses,
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            wallets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn wallet(
+        &self,
+        chronik: &ChronikClient,
+        prefix: &str,
+        xpub: &str,
+    ) -> Result<XpubWallet> {
+        if let Some((scanned_at, wallet)) = self.wallets.lock().unwrap().get(xpub) {
+            if scanned_at.elapsed() < self.cache_ttl {
+                return Ok(wallet.clone());
+            }
+        }
+
+        let ext_pubkey = match ExtendedPubKey::from_str(xpub) {
+            Ok(ext_pubkey) => ext_pubkey,
+            Err(err) => bail!(BadRequest(format!("Invalid xpub: {}", err))),
+        };
+        let mut wallet = XpubWallet {
+            addresses: Vec::new(),
+            balance_sats: 0,
+            num_txs: 0,
+            num_derived: 0,
+            truncated: false,
+        };
+        for chain in CHAINS {
+            let chain_pubkey =
+                ext_pubkey.ckd_pub(&self.secp, ChildNumber::from_normal_idx(chain)?)?;
+            self.scan_chain(chronik, prefix, chain, &chain_pubkey, &mut wallet)
+                .await?;
+        }
+
+        let mut wallets = self.wallets.lock().unwrap();
+        if wallets.len() >= MAX_CACHED_WALLETS {
+            wallets.clear();
+        }
+        wallets.insert(xpub.to_string(), (Instant::now(), wallet.clone()));
+        Ok(wallet)
+    }
+
+    /// Looks up the addresses of a chain in batches of `gap_limit`, until a
+    /// batch ends with `gap_limit` unused addresses in a row
+    async fn scan_chain(
+        &self,
+        chronik: &ChronikClient,
+        prefix: &str,
+        chain: u32,
+        chain_pubkey: &ExtendedPubKey,
+        wallet: &mut XpubWallet,
+    ) -> Result<()> {
+        let mut num_unused = 0;
+        let mut index = 0;
+        while num_unused < self.gap_limit {
+            if index >= self.max_addresses {
+                wallet.truncated = true;
+                break;
+            }
+            let batch_end = (index + self.gap_limit).min(self.max_addresses);
+            let addresses = (index..batch_end)
+                .map(|index| {
+                    let child_pubkey =
+                        chain_pubkey.ckd_pub(&self.secp, ChildNumber::from_normal_idx(index)?)?;
+                    let address = pubkey_address(prefix, &child_pubkey.public_key.to_bytes());
+                    Ok((index, address))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let looked_up = stream::iter(&addresses)
+                .map(|(index, address)| async move {
+                    let (script_type, script_payload) = cash_addr_to_script_type_payload(address);
+                    let script_endpoint = chronik.script(script_type, &script_payload);
+                    let num_txs = script_endpoint
+                        .history_with_page_size(0, 1)
+                        .await?
+                        .num_pages;
+                    let mut balance_sats = 0;
+                    // Unused addresses can't have UTXOs
+                    if num_txs > 0 {
+                        for script_utxos in script_endpoint.utxos().await? {
+                            for utxo in script_utxos.utxos {
+                                if utxo.slp_token.is_none() {
+                                    balance_sats += utxo.value;
+                                }
+                            }
+                        }
+                    }
+                    Ok::<_, eyre::Report>(XpubAddress {
+                        path: format!("{}/{}", chain, index),
+                        address: address.as_str().to_string(),
+                        num_txs,
+                        balance_sats,
+                    })
+                })
+                .buffered(FETCH_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            wallet.num_derived += looked_up.len();
+            for address in looked_up {
+                if address.num_txs == 0 {
+                    num_unused += 1;
+                    continue;
+                }
+                num_unused = 0;
+                wallet.balance_sats += address.balance_sats;
+                wallet.num_txs += u64::from(address.num_txs);
+                wallet.addresses.push(address);
+            }
+            index = batch_end;
+        }
+        Ok(())
+    }
+}